@@ -1,7 +1,13 @@
-use crate::config::{AgentInfo, ConfigPaths};
+use crate::config::{AgentInfo, ConfigPaths, PreferenceDrift};
 use crate::credentials::{CredentialManager, TokenStatus};
-use crate::preferences::{McpAuth, McpServerConfig};
+use crate::preferences::{
+    AgentPreferences, GeneralSetting, McpAuth, McpServerConfig, ToolCategory,
+};
 use anyhow::Result;
+use ratatui::layout::{Position, Rect};
+use serde::{Deserialize, Serialize};
+use std::cell::Cell;
+use std::collections::HashMap;
 use std::sync::mpsc::Receiver;
 use std::time::{Duration, Instant};
 
@@ -16,19 +22,38 @@ pub enum AppMode {
     ViewDiff,
     ViewBackups,
     Search,
+    SectionOutline,
     AddTool,
     EditMcp,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
 pub enum ActiveTab {
+    #[default]
     Dashboard,
     Preferences,
     McpServers,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+impl ActiveTab {
+    pub const ALL: [ActiveTab; 3] = [
+        ActiveTab::Dashboard,
+        ActiveTab::Preferences,
+        ActiveTab::McpServers,
+    ];
+
+    pub fn title(&self) -> &'static str {
+        match self {
+            ActiveTab::Dashboard => "[1] Dashboard",
+            ActiveTab::Preferences => "[2] Preferences",
+            ActiveTab::McpServers => "[3] MCP Servers",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
 pub enum Focus {
+    #[default]
     Agents,
     Global,
     Project,
@@ -39,6 +64,7 @@ pub enum PrefEditorFocus {
     Presets,
     IndividualTools,
     GeneralSettings,
+    MooagentTools,
 }
 
 pub struct PreferenceEditorState {
@@ -46,8 +72,13 @@ pub struct PreferenceEditorState {
     pub selected_preset: usize,
     pub selected_tool: usize,
     pub selected_general: usize,
+    /// Agent whose `[general]` overrides the General Settings panel edits; `None` is global.
+    pub general_scope: Option<String>,
+    pub selected_mooagent_tool: usize,
     pub preset_list: Vec<String>,
     pub individual_tool_list: Vec<String>,
+    /// Tools of mooagent's own MCP server, toggled via its `disabled_tools`.
+    pub mooagent_tool_list: Vec<String>,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
@@ -79,9 +110,12 @@ impl McpAuthType {
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum McpFieldFocus {
     Name,
+    Description,
+    Tags,
     Command,
     Args,
     Env,
+    Headers,
     DisabledTools,
     AutoAllow,
     AuthType,
@@ -92,18 +126,108 @@ pub enum McpFieldFocus {
     OAuthAuthServerUrl,
 }
 
+/// Cursor movement keys shared by the single-line text inputs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CursorMove {
+    Left,
+    Right,
+    Home,
+    End,
+}
+
+/// Byte offset of the `cursor`-th character, or the end of `text` past the last one.
+fn char_to_byte(text: &str, cursor: usize) -> usize {
+    text.char_indices()
+        .nth(cursor)
+        .map_or(text.len(), |(i, _)| i)
+}
+
+/// Inserts `s` at the cursor (a character index) and moves the cursor past it.
+pub fn insert_at_cursor(text: &mut String, cursor: &mut usize, s: &str) {
+    *cursor = (*cursor).min(text.chars().count());
+    text.insert_str(char_to_byte(text, *cursor), s);
+    *cursor += s.chars().count();
+}
+
+/// Backspace: removes the character left of the cursor.
+pub fn delete_before_cursor(text: &mut String, cursor: &mut usize) {
+    *cursor = (*cursor).min(text.chars().count());
+    if *cursor > 0 {
+        *cursor -= 1;
+        text.remove(char_to_byte(text, *cursor));
+    }
+}
+
+/// Delete: removes the character under the cursor.
+pub fn delete_at_cursor(text: &mut String, cursor: &mut usize) {
+    *cursor = (*cursor).min(text.chars().count());
+    if *cursor < text.chars().count() {
+        text.remove(char_to_byte(text, *cursor));
+    }
+}
+
+pub fn move_cursor(text: &str, cursor: &mut usize, movement: CursorMove) {
+    let len = text.chars().count();
+    *cursor = match movement {
+        CursorMove::Left => (*cursor).min(len).saturating_sub(1),
+        CursorMove::Right => (*cursor + 1).min(len),
+        CursorMove::Home => 0,
+        CursorMove::End => len,
+    };
+}
+
+const ACTIVE_POLL_INTERVAL: Duration = Duration::from_millis(100);
+const DEFAULT_IDLE_POLL_MS: u64 = 1000;
+
+fn idle_poll_interval(general: &crate::preferences::GeneralPreferences) -> Duration {
+    let idle_ms = general
+        .idle_poll_interval_ms
+        .unwrap_or(DEFAULT_IDLE_POLL_MS);
+    Duration::from_millis(idle_ms.max(ACTIVE_POLL_INTERVAL.as_millis() as u64))
+}
+pub const DEFAULT_STATUS_LOG_CAPACITY: usize = 100;
+
+/// Writes the session's status messages to a timestamped file in `dir`, oldest first.
+pub fn export_status_log(
+    dir: &std::path::Path,
+    entries: &[(String, chrono::DateTime<chrono::Local>)],
+) -> Result<std::path::PathBuf> {
+    let mut content = String::new();
+    for (msg, time) in entries {
+        content.push_str(&format!("[{}] {}\n", time.format("%Y-%m-%d %H:%M:%S"), msg));
+    }
+    let file = dir.join(format!(
+        "status_log_{}.txt",
+        chrono::Local::now().format("%Y%m%d_%H%M%S")
+    ));
+    crate::config::ensure_dir(dir)?;
+    crate::config::write_atomic(&file, content)?;
+    Ok(file)
+}
+
 pub struct McpEditorState {
     pub selected_server_idx: usize,
+    /// Servers shown in the MCP tab, narrowed by `filter`.
     pub server_list: Vec<String>,
+    /// The MCP tab's `/` filter; see `McpServerConfig::matches_filter`.
+    pub filter: String,
+    pub filter_cursor: usize,
 
     pub is_new: bool,
     pub editing_name: String,
+    pub editing_description: String,
+    pub editing_tags: String,
     pub editing_command: String,
     pub editing_args: String,
     pub editing_env: String,
+    pub editing_headers: String,
     pub editing_disabled_tools: String,
     pub editing_auto_allow: bool,
     pub focus: McpFieldFocus,
+    /// Character position in the focused text field.
+    pub cursor: usize,
+    /// Show the generated agent config for the selected server instead of its details.
+    pub show_preview: bool,
 
     pub editing_auth_type: McpAuthType,
     pub editing_bearer_token: String,
@@ -115,64 +239,232 @@ pub struct McpEditorState {
 
 impl Default for PreferenceEditorState {
     fn default() -> Self {
+        // Every preset member, already grouped in preset order
+        let mut individual_tool_list: Vec<String> = Vec::new();
+        for preset in crate::preferences::PRESET_NAMES {
+            for tool in crate::preferences::get_preset_tools(preset).unwrap_or_default() {
+                if !individual_tool_list.iter().any(|t| t == tool) {
+                    individual_tool_list.push(tool.to_string());
+                }
+            }
+        }
+
         Self {
             focus: PrefEditorFocus::Presets,
             selected_preset: 0,
             selected_tool: 0,
             selected_general: 0,
-            preset_list: vec![
-                "core_unix_tools".to_string(),
-                "file_operations".to_string(),
-                "code_search".to_string(),
-                "network_tools".to_string(),
-                "development_tools".to_string(),
-                "web_access".to_string(),
-            ],
-            individual_tool_list: vec![
-                // Regular Tools
-                "ls".to_string(),
-                "cd".to_string(),
-                "pwd".to_string(),
-                "cat".to_string(),
-                "grep".to_string(),
-                "find".to_string(),
-                "sed".to_string(),
-                "awk".to_string(),
-                "head".to_string(),
-                "tail".to_string(),
-                "sort".to_string(),
-                "uniq".to_string(),
-                "cp".to_string(),
-                "mv".to_string(),
-                "rm".to_string(),
-                "mkdir".to_string(),
-                "touch".to_string(),
-                "curl".to_string(),
-                "wget".to_string(),
-                "ping".to_string(),
-                "git".to_string(),
-                "npm".to_string(),
-                "cargo".to_string(),
-                "docker".to_string(),
-                "just".to_string(),
-                // LLM Tools
-                "Read".to_string(),
-                "Write".to_string(),
-                "Edit".to_string(),
-                "Glob".to_string(),
-                "Search".to_string(),
-                "WebFetch".to_string(),
-                "WebSearch".to_string(),
-            ],
+            general_scope: None,
+            selected_mooagent_tool: 0,
+            mooagent_tool_list: crate::mcp::tool_names(),
+            preset_list: crate::preferences::PRESET_NAMES
+                .iter()
+                .map(|p| p.to_string())
+                .collect(),
+            individual_tool_list,
+        }
+    }
+}
+
+/// The Individual Tools section a tool is listed under, with its position among the
+/// sections: its first preset, else a custom section split by tool kind.
+pub fn tool_section(
+    name: &str,
+    index: &HashMap<&'static str, Vec<&'static str>>,
+    prefs: &AgentPreferences,
+) -> (usize, &'static str) {
+    let presets = crate::preferences::PRESET_NAMES;
+    match index.get(name).and_then(|p| p.first()) {
+        Some(preset) => (
+            presets.iter().position(|p| p == preset).unwrap_or(0),
+            preset,
+        ),
+        None if prefs.tool_category(name) == ToolCategory::Llm => {
+            (presets.len(), "Custom LLM Tools")
+        }
+        None => (presets.len() + 1, "Custom Programs"),
+    }
+}
+
+/// Orders tools by section, keeping their relative order within each section.
+pub fn sort_tool_list(tools: &mut [String], prefs: &AgentPreferences) {
+    let index = crate::preferences::tool_preset_index();
+    tools.sort_by_key(|tool| tool_section(tool, &index, prefs).0);
+}
+
+/// Tab bar titles as drawn; Preferences gets a `*` while it has unsaved changes.
+pub fn tab_titles(prefs_dirty: bool) -> Vec<String> {
+    ActiveTab::ALL
+        .iter()
+        .map(|tab| {
+            if *tab == ActiveTab::Preferences && prefs_dirty {
+                format!("{} *", tab.title())
+            } else {
+                tab.title().to_string()
+            }
+        })
+        .collect()
+}
+
+/// Index of the tab drawn `offset` columns into the tab bar.
+pub fn tab_at(titles: &[String], offset: u16) -> Option<usize> {
+    // Titles are padded by one space on each side and separated by a one-column divider
+    let mut x = 0;
+    for (index, title) in titles.iter().enumerate() {
+        let width = title.chars().count() as u16 + 2;
+        if offset < x + width {
+            return Some(index);
+        }
+        x += width + 1;
+    }
+    None
+}
+
+/// Keeps a scroll offset at the same relative position when a document changes length,
+/// clamped so it never points past the last line.
+pub fn rescale_scroll(scroll: usize, old_lines: usize, new_lines: usize) -> usize {
+    let scroll = if old_lines == 0 || old_lines == new_lines {
+        scroll
+    } else {
+        scroll * new_lines / old_lines
+    };
+    scroll.min(new_lines.saturating_sub(1))
+}
+
+/// Rows scrolled by page/half-page moves when a pane's size isn't known yet.
+const DEFAULT_PAGE_ROWS: usize = 10;
+
+/// Rows each line of `content` takes when word-wrapped to `width` columns, as the rule
+/// panes render it (leading whitespace trimmed).
+pub fn wrapped_line_rows(content: &str, width: usize) -> Vec<usize> {
+    let width = width.max(1);
+    content
+        .lines()
+        .map(|line| {
+            let mut rows = 1;
+            let mut col = 0;
+            for word in line.split_whitespace() {
+                let len = word.chars().count();
+                if col > 0 && col + 1 + len > width {
+                    rows += 1;
+                    col = 0;
+                } else if col > 0 {
+                    col += 1;
+                }
+                col += len;
+                while col > width {
+                    rows += 1;
+                    col -= width;
+                }
+            }
+            rows
+        })
+        .collect()
+}
+
+/// First line to show so the last rendered row sits at the bottom of a `height`-row pane.
+pub fn end_scroll(rows: &[usize], height: usize) -> usize {
+    let mut used = 0;
+    let mut start = rows.len();
+    while start > 0 && used + rows[start - 1] <= height {
+        used += rows[start - 1];
+        start -= 1;
+    }
+    // A single line taller than the pane still gets shown from its start
+    start.min(rows.len().saturating_sub(1))
+}
+
+/// Scroll position after moving down by about `page` rendered rows.
+pub fn page_down_scroll(scroll: usize, rows: &[usize], page: usize) -> usize {
+    let end = end_scroll(rows, page.max(1));
+    let mut used = 0;
+    let mut next = scroll;
+    while next < rows.len() && used + rows[next] <= page {
+        used += rows[next];
+        next += 1;
+    }
+    next.max(scroll + 1).min(end.max(scroll))
+}
+
+/// Scroll position after moving up by about `page` rendered rows.
+pub fn page_up_scroll(scroll: usize, rows: &[usize], page: usize) -> usize {
+    let mut used = 0;
+    let mut prev = scroll.min(rows.len());
+    while prev > 0 && used + rows[prev - 1] <= page {
+        used += rows[prev - 1];
+        prev -= 1;
+    }
+    if prev == scroll {
+        scroll.saturating_sub(1)
+    } else {
+        prev
+    }
+}
+
+/// Parses the `KEY=VAL,KEY=VAL` format used by the env and headers fields. A value
+/// containing commas can be double-quoted, with `\"` and `\\` escapes inside the quotes.
+pub fn parse_key_values(input: &str) -> HashMap<String, String> {
+    let mut values = HashMap::new();
+    let mut chars = input.chars().peekable();
+
+    while chars.peek().is_some() {
+        let mut key = String::new();
+        let mut has_value = false;
+        for c in chars.by_ref() {
+            match c {
+                '=' => {
+                    has_value = true;
+                    break;
+                }
+                ',' => break,
+                _ => key.push(c),
+            }
+        }
+        if !has_value {
+            continue;
+        }
+
+        while chars.next_if(|c| c.is_whitespace()).is_some() {}
+        let value = if chars.next_if_eq(&'"').is_some() {
+            let mut value = String::new();
+            while let Some(c) = chars.next() {
+                match c {
+                    '\\' => value.extend(chars.next()),
+                    '"' => break,
+                    _ => value.push(c),
+                }
+            }
+            // Anything between the closing quote and the next comma is ignored
+            chars.by_ref().take_while(|&c| c != ',').for_each(drop);
+            value
+        } else {
+            let value: String = chars.by_ref().take_while(|&c| c != ',').collect();
+            value.trim().to_string()
+        };
+
+        let key = key.trim();
+        if !key.is_empty() {
+            values.insert(key.to_string(), value);
         }
     }
+    values
 }
 
-pub fn is_llm_tool(name: &str) -> bool {
-    matches!(
-        name,
-        "Read" | "Write" | "Edit" | "Glob" | "Search" | "WebFetch" | "WebSearch"
-    )
+/// Inverse of `parse_key_values`, quoting values that would not survive it unquoted.
+pub fn format_key_values(values: &HashMap<String, String>) -> String {
+    let mut pairs: Vec<String> = values
+        .iter()
+        .map(|(k, v)| {
+            if v.contains(',') || v.starts_with('"') || v.trim() != v {
+                let escaped = v.replace('\\', "\\\\").replace('"', "\\\"");
+                format!("{}=\"{}\"", k, escaped)
+            } else {
+                format!("{}={}", k, v)
+            }
+        })
+        .collect();
+    pairs.sort();
+    pairs.join(",")
 }
 
 #[derive(Debug, PartialEq, Eq)]
@@ -185,16 +477,23 @@ pub enum PresetState {
 impl Default for McpEditorState {
     fn default() -> Self {
         Self {
+            show_preview: false,
             selected_server_idx: 0,
             server_list: Vec::new(),
+            filter: String::new(),
+            filter_cursor: 0,
             is_new: false,
             editing_name: String::new(),
+            editing_description: String::new(),
+            editing_tags: String::new(),
             editing_command: String::new(),
             editing_args: String::new(),
             editing_env: String::new(),
+            editing_headers: String::new(),
             editing_disabled_tools: String::new(),
             editing_auto_allow: false,
             focus: McpFieldFocus::Name,
+            cursor: 0,
             editing_auth_type: McpAuthType::None,
             editing_bearer_token: String::new(),
             editing_oauth_client_id: String::new(),
@@ -215,10 +514,185 @@ impl McpEditorState {
         self.editing_oauth_auth_server_url.clear();
     }
 
+    /// The focused field's text and the cursor within it, if the field takes typed text.
+    pub fn focused_text(&mut self) -> Option<(&mut String, &mut usize)> {
+        let field = match self.focus {
+            McpFieldFocus::Name if self.is_new => &mut self.editing_name,
+            McpFieldFocus::Description => &mut self.editing_description,
+            McpFieldFocus::Tags => &mut self.editing_tags,
+            McpFieldFocus::Command => &mut self.editing_command,
+            McpFieldFocus::Args => &mut self.editing_args,
+            McpFieldFocus::Env => &mut self.editing_env,
+            McpFieldFocus::Headers => &mut self.editing_headers,
+            McpFieldFocus::DisabledTools => &mut self.editing_disabled_tools,
+            McpFieldFocus::BearerToken => &mut self.editing_bearer_token,
+            McpFieldFocus::OAuthClientId => &mut self.editing_oauth_client_id,
+            McpFieldFocus::OAuthClientSecret => &mut self.editing_oauth_client_secret,
+            McpFieldFocus::OAuthScopes => &mut self.editing_oauth_scopes,
+            McpFieldFocus::OAuthAuthServerUrl => &mut self.editing_oauth_auth_server_url,
+            McpFieldFocus::Name | McpFieldFocus::AutoAllow | McpFieldFocus::AuthType => {
+                return None;
+            }
+        };
+        Some((field, &mut self.cursor))
+    }
+
+    fn cursor_to_end(&mut self) {
+        let end = self
+            .focused_text()
+            .map_or(0, |(text, _)| text.chars().count());
+        self.cursor = end;
+    }
+
     pub fn is_remote_server(&self) -> bool {
         let cmd = self.editing_command.trim();
         cmd.starts_with("http://") || cmd.starts_with("https://")
     }
+
+    /// Drops values left over from the other server type after the command changed between
+    /// a URL and a local binary: auth and headers for stdio, args and env for remote.
+    pub fn clear_fields_for_server_type(&mut self) {
+        if self.is_remote_server() {
+            self.editing_args.clear();
+            self.editing_env.clear();
+        } else {
+            self.editing_headers.clear();
+            self.clear_auth_fields();
+        }
+    }
+
+    /// Builds the server entry from the form. `headers_override_auth` has no field in the
+    /// editor, so the caller carries it over from the saved entry.
+    pub fn to_config(&self, headers_override_auth: bool) -> McpServerConfig {
+        let command = self.editing_command.trim().to_string();
+        let disabled_tools: Vec<String> = self
+            .editing_disabled_tools
+            .split(',')
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty())
+            .collect();
+        let description =
+            Some(self.editing_description.trim().to_string()).filter(|s| !s.is_empty());
+        let tags: Vec<String> = self
+            .editing_tags
+            .split(',')
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty())
+            .collect();
+
+        if self.is_remote_server() {
+            McpServerConfig::Sse {
+                url: command,
+                auth: self.build_auth_config(),
+                headers: parse_key_values(&self.editing_headers),
+                headers_override_auth,
+                disabled_tools,
+                auto_allow: self.editing_auto_allow,
+                description,
+                tags,
+            }
+        } else {
+            McpServerConfig::Stdio {
+                command,
+                args: self
+                    .editing_args
+                    .split_whitespace()
+                    .map(String::from)
+                    .collect(),
+                env: parse_key_values(&self.editing_env),
+                disabled_tools,
+                auto_allow: self.editing_auto_allow,
+                description,
+                tags,
+            }
+        }
+    }
+
+    fn build_auth_config(&self) -> McpAuth {
+        match self.editing_auth_type {
+            McpAuthType::None => McpAuth::None,
+            McpAuthType::Bearer => {
+                let token = self.editing_bearer_token.trim();
+                if token.is_empty() {
+                    McpAuth::None
+                } else {
+                    McpAuth::Bearer {
+                        token: token.to_string(),
+                    }
+                }
+            }
+            McpAuthType::OAuth => {
+                let client_id = self.editing_oauth_client_id.trim();
+                if client_id.is_empty() {
+                    McpAuth::None
+                } else {
+                    let client_secret = {
+                        let s = self.editing_oauth_client_secret.trim();
+                        if s.is_empty() {
+                            None
+                        } else {
+                            Some(s.to_string())
+                        }
+                    };
+                    let auth_server_url = {
+                        let s = self.editing_oauth_auth_server_url.trim();
+                        if s.is_empty() {
+                            None
+                        } else {
+                            Some(s.to_string())
+                        }
+                    };
+                    let scopes: Vec<String> = self
+                        .editing_oauth_scopes
+                        .split_whitespace()
+                        .map(String::from)
+                        .collect();
+
+                    McpAuth::OAuth {
+                        client_id: client_id.to_string(),
+                        client_secret,
+                        auth_server_url,
+                        scopes,
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// UI state persisted between sessions, separate from preferences.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UiState {
+    pub active_tab: ActiveTab,
+    pub focus: Focus,
+    pub auto_sync: bool,
+}
+
+impl UiState {
+    /// Falls back to defaults if the state file is missing or unreadable.
+    pub fn load(path: &std::path::Path) -> Self {
+        std::fs::read_to_string(path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self, path: &std::path::Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+}
+
+/// Screen areas from the last dashboard render, used to hit-test mouse clicks.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DashboardLayout {
+    pub agent_table: Rect,
+    pub global_pane: Rect,
+    pub project_pane: Rect,
 }
 
 pub struct OAuthFlowConfig {
@@ -236,26 +710,56 @@ pub struct App {
     pub global_content: String,
     pub status_message: Option<(String, Instant)>,
     pub event_rx: Option<Receiver<()>>,
+    /// Expired OAuth tokens being refreshed in the background; yields the refreshed servers.
+    oauth_refresh: Option<Receiver<Vec<String>>>,
+    /// Set when state changed since the last frame; the event loop skips drawing otherwise.
+    pub needs_redraw: bool,
+    /// Set on resize; scroll offsets are re-clamped once the next frame has laid out panes.
+    pub clamp_scroll_after_draw: bool,
     pub selected_agent: usize,
     pub project_scroll: usize,
     pub global_scroll: usize,
     pub detail_scroll: usize,
+    /// Highlighted entry in the backups view.
+    pub backup_selected: usize,
+    pub outline_selected: usize,
     pub mode: AppMode,
     pub active_tab: ActiveTab,
     pub focus: Focus,
     pub pending_g: bool,
-    pub status_log: Vec<(String, Instant)>,
+    pub status_log: Vec<(String, chrono::DateTime<chrono::Local>)>,
     pub search_query: String,
+    pub search_cursor: usize,
     pub status_message_timeout: u64,
+    /// `general.idle_poll_interval_ms`, read when preferences load rather than every tick.
+    idle_poll_interval: Duration,
     pub auto_sync: bool,
     pub filtered_agents: Vec<usize>,
     pub show_error_log: bool,
-    pub preference_drift: bool,
+    /// Recent MCP tool calls, reloaded each time the log overlay opens.
+    pub audit_entries: Vec<crate::audit::AuditEntry>,
+    pub preference_drift: Vec<PreferenceDrift>,
+    pub stale_mooagent_command: Option<String>,
+    pub oversized_rules: Vec<String>,
+    /// Agents in .mooagent.toml that share a target file.
+    pub target_conflicts: Vec<String>,
+    /// Set when the built-in agents are shown because `.mooagent.toml` doesn't list any.
+    pub default_agents_note: Option<&'static str>,
+    /// Agents about to be synced whose files were edited by hand, with the diff that
+    /// syncing would discard.
+    pub hand_edits: Vec<(usize, String)>,
     pub pref_editor_state: PreferenceEditorState,
     pub mcp_editor_state: McpEditorState,
+    /// Preference changes made in memory but not yet written to disk.
+    pub prefs_dirty: bool,
     pub new_tool_input: String,
+    pub new_tool_cursor: usize,
+    /// Category picked in the Add Tool dialog; `None` guesses from the name.
+    pub new_tool_category: Option<ToolCategory>,
     pub should_quit: bool,
     pub credentials: CredentialManager,
+    pub dashboard_layout: Cell<DashboardLayout>,
+    pub tab_area: Cell<Rect>,
 }
 
 impl App {
@@ -307,6 +811,9 @@ impl App {
         let mut credentials = CredentialManager::new(&paths.config_dir);
         let _ = credentials.load();
 
+        let ui_state = UiState::load(&paths.ui_state_file);
+
+        let idle_poll_interval = idle_poll_interval(&paths.preferences.get_merged().general);
         let mut app = Self {
             paths,
             agents,
@@ -314,41 +821,99 @@ impl App {
             global_content,
             status_message: None,
             event_rx,
+            oauth_refresh: None,
+            needs_redraw: true,
+            clamp_scroll_after_draw: false,
             selected_agent: 0,
             project_scroll: 0,
             global_scroll: 0,
             detail_scroll: 0,
+            backup_selected: 0,
+            outline_selected: 0,
             mode: AppMode::Normal,
-            active_tab: ActiveTab::Dashboard,
-            focus: Focus::Agents,
+            active_tab: ui_state.active_tab,
+            focus: ui_state.focus,
             pending_g: false,
             status_log: Vec::new(),
             search_query: String::new(),
+            search_cursor: 0,
             status_message_timeout: 5,
-            auto_sync: false,
+            idle_poll_interval,
+            auto_sync: ui_state.auto_sync,
             filtered_agents,
             show_error_log: false,
-            preference_drift: false,
+            audit_entries: Vec::new(),
+            preference_drift: Vec::new(),
+            stale_mooagent_command: None,
+            oversized_rules: Vec::new(),
+            target_conflicts: Vec::new(),
+            default_agents_note: None,
+            hand_edits: Vec::new(),
             pref_editor_state: PreferenceEditorState::default(),
             mcp_editor_state: McpEditorState::default(),
+            prefs_dirty: false,
             new_tool_input: String::new(),
+            new_tool_cursor: 0,
+            new_tool_category: None,
             should_quit: false,
             credentials,
+            dashboard_layout: Cell::new(DashboardLayout::default()),
+            tab_area: Cell::new(Rect::default()),
         };
 
+        app.stale_mooagent_command = app.paths.stale_mooagent_command();
+        app.oversized_rules = app.paths.oversized_rules();
+        app.target_conflicts = app.paths.target_conflicts();
+        app.default_agents_note = crate::config::default_agents_reason(&app.paths.config_file);
+        app.load_custom_tools();
         app.update_mcp_list();
         Ok(app)
     }
 
+    /// Lists tools added by hand in earlier sessions alongside the preset members.
+    fn load_custom_tools(&mut self) {
+        let prefs = &self.paths.preferences.global_prefs;
+        let list = &mut self.pref_editor_state.individual_tool_list;
+        let mut custom: Vec<&String> = prefs
+            .individual_tools
+            .keys()
+            .filter(|tool| !list.contains(tool))
+            .collect();
+        custom.sort();
+        list.extend(custom.into_iter().cloned());
+        sort_tool_list(list, prefs);
+    }
+
     pub fn refresh(&mut self) {
+        self.needs_redraw = true;
+        self.idle_poll_interval = idle_poll_interval(&self.paths.preferences.get_merged().general);
+        let old_project_lines = self.project_content.lines().count();
+        let old_global_lines = self.global_content.lines().count();
+
         self.project_content = self.paths.read_project_content();
         self.global_content = if self.paths.global_rules_primary.exists() {
             std::fs::read_to_string(&self.paths.global_rules_primary).unwrap_or_default()
         } else {
             String::new()
         };
+
+        self.project_scroll = rescale_scroll(
+            self.project_scroll,
+            old_project_lines,
+            self.project_content.lines().count(),
+        );
+        self.global_scroll = rescale_scroll(
+            self.global_scroll,
+            old_global_lines,
+            self.global_content.lines().count(),
+        );
         self.agents = self.paths.get_agents();
-        self.preference_drift = self.paths.check_preference_drift();
+        self.preference_drift = self.paths.preference_drift_details();
+        self.stale_mooagent_command = self.paths.stale_mooagent_command();
+        self.oversized_rules = self.paths.oversized_rules();
+        if let Err(e) = self.credentials.load() {
+            log::warn!("Failed to reload credentials: {}", e);
+        }
 
         self.update_filter();
 
@@ -372,13 +937,17 @@ impl App {
             .keys()
             .cloned()
             .collect();
+        let filter = &self.mcp_editor_state.filter;
+        if !filter.trim().is_empty() {
+            let all = &self.paths.preferences.global_prefs.mcp_servers;
+            servers.retain(|name| all[name].matches_filter(name, filter));
+        }
         servers.sort();
         self.mcp_editor_state.server_list = servers;
 
-        if self.mcp_editor_state.selected_server_idx >= self.mcp_editor_state.server_list.len()
-            && !self.mcp_editor_state.server_list.is_empty()
-        {
-            self.mcp_editor_state.selected_server_idx = self.mcp_editor_state.server_list.len() - 1;
+        if self.mcp_editor_state.selected_server_idx >= self.mcp_editor_state.server_list.len() {
+            self.mcp_editor_state.selected_server_idx =
+                self.mcp_editor_state.server_list.len().saturating_sub(1);
         }
     }
 
@@ -403,13 +972,17 @@ impl App {
     pub fn mcp_start_add(&mut self) {
         self.mcp_editor_state.is_new = true;
         self.mcp_editor_state.editing_name.clear();
+        self.mcp_editor_state.editing_description.clear();
+        self.mcp_editor_state.editing_tags.clear();
         self.mcp_editor_state.editing_command.clear();
         self.mcp_editor_state.editing_args.clear();
         self.mcp_editor_state.editing_env.clear();
+        self.mcp_editor_state.editing_headers.clear();
         self.mcp_editor_state.editing_disabled_tools.clear();
         self.mcp_editor_state.editing_auto_allow = false;
         self.mcp_editor_state.clear_auth_fields();
         self.mcp_editor_state.focus = McpFieldFocus::Name;
+        self.mcp_editor_state.cursor_to_end();
         self.mode = AppMode::EditMcp;
     }
 
@@ -432,36 +1005,56 @@ impl App {
             return;
         };
 
+        if config.is_unknown() {
+            self.set_status(format!(
+                "'{}' has an unrecognized config; fix it in {}",
+                server_name,
+                self.paths.preferences.global_path.display()
+            ));
+            return;
+        }
+
         self.mcp_editor_state.is_new = false;
         self.mcp_editor_state.editing_name = server_name;
+        self.mcp_editor_state.editing_description =
+            config.description().unwrap_or_default().to_string();
+        self.mcp_editor_state.editing_tags = config.tags().join(", ");
         self.mcp_editor_state.editing_disabled_tools = config.disabled_tools().join(", ");
         self.mcp_editor_state.editing_auto_allow = config.auto_allow();
+        self.mcp_editor_state.editing_headers.clear();
         self.mcp_editor_state.clear_auth_fields();
 
         match &config {
             McpServerConfig::Stdio { command, args, env, .. } => {
                 self.mcp_editor_state.editing_command = command.clone();
                 self.mcp_editor_state.editing_args = args.join(" ");
-                self.mcp_editor_state.editing_env = env
-                    .iter()
-                    .map(|(k, v)| format!("{}={}", k, v))
-                    .collect::<Vec<_>>()
-                    .join(",");
+                self.mcp_editor_state.editing_env = format_key_values(env);
             }
-            McpServerConfig::Sse { url, auth, .. } => {
+            McpServerConfig::Sse {
+                url, auth, headers, ..
+            } => {
                 self.mcp_editor_state.editing_command = url.clone();
                 self.mcp_editor_state.editing_args.clear();
                 self.mcp_editor_state.editing_env.clear();
+                self.mcp_editor_state.editing_headers = format_key_values(headers);
                 self.populate_auth_fields(auth);
             }
-            McpServerConfig::Http { http_url, auth, .. } => {
+            McpServerConfig::Http {
+                http_url,
+                auth,
+                headers,
+                ..
+            } => {
                 self.mcp_editor_state.editing_command = http_url.clone();
                 self.mcp_editor_state.editing_args.clear();
                 self.mcp_editor_state.editing_env.clear();
+                self.mcp_editor_state.editing_headers = format_key_values(headers);
                 self.populate_auth_fields(auth);
             }
+            McpServerConfig::Unknown(_) => {}
         }
         self.mcp_editor_state.focus = McpFieldFocus::Command;
+        self.mcp_editor_state.cursor_to_end();
         self.mode = AppMode::EditMcp;
     }
 
@@ -474,6 +1067,14 @@ impl App {
                 self.mcp_editor_state.editing_auth_type = McpAuthType::Bearer;
                 self.mcp_editor_state.editing_bearer_token = token.clone();
             }
+            McpAuth::BearerRef { key } => {
+                self.mcp_editor_state.editing_auth_type = McpAuthType::Bearer;
+                self.mcp_editor_state.editing_bearer_token = self
+                    .credentials
+                    .get_token(key)
+                    .map(|t| t.access_token.clone())
+                    .unwrap_or_default();
+            }
             McpAuth::OAuth {
                 client_id,
                 client_secret,
@@ -491,6 +1092,34 @@ impl App {
         }
     }
 
+    /// Writes global preferences after an in-memory change. If the write fails the change
+    /// stays in memory and `prefs_dirty` marks the Preferences tab until a save succeeds.
+    fn save_global_prefs(&mut self) {
+        self.prefs_dirty = true;
+        match self.paths.preferences.save_global() {
+            Ok(()) => self.prefs_dirty = false,
+            Err(e) => self.set_status(format!("Failed to save preferences: {}", e)),
+        }
+    }
+
+    /// Explicitly writes global and project preferences, clearing the dirty marker.
+    pub fn save_prefs(&mut self) {
+        let result = self.paths.preferences.save_global().and_then(|()| {
+            if self.paths.preferences.project_prefs.is_some() {
+                self.paths.preferences.save_project(&self.paths.config_file)
+            } else {
+                Ok(())
+            }
+        });
+        match result {
+            Ok(()) => {
+                self.prefs_dirty = false;
+                self.set_status("Preferences saved".to_string());
+            }
+            Err(e) => self.set_status(format!("Failed to save preferences: {}", e)),
+        }
+    }
+
     pub fn mcp_confirm_delete(&mut self) {
         if self.mcp_editor_state.server_list.is_empty() {
             return;
@@ -505,14 +1134,55 @@ impl App {
         let server_name =
             self.mcp_editor_state.server_list[self.mcp_editor_state.selected_server_idx].clone();
 
-        self.paths
+        let Some(removed) = self
+            .paths
             .preferences
             .global_prefs
             .mcp_servers
-            .remove(&server_name);
-        let _ = self.paths.preferences.save_global();
+            .remove(&server_name)
+        else {
+            return;
+        };
+        self.save_global_prefs();
         self.update_mcp_list();
-        self.set_status(format!("Deleted MCP server: {}", server_name));
+
+        let mut removed_tokens = 0;
+        let orphaned = crate::preferences::orphaned_credential_keys(
+            &removed,
+            &self.paths.preferences.global_prefs.mcp_servers,
+        );
+        for key in orphaned {
+            match self.credentials.remove_token(key) {
+                Ok(Some(_)) => removed_tokens += 1,
+                Ok(None) => {}
+                Err(e) => log::warn!("Failed to remove stored token for {}: {}", key, e),
+            }
+        }
+
+        if removed_tokens > 0 {
+            self.set_status(format!(
+                "Deleted MCP server: {} and its stored credentials",
+                server_name
+            ));
+        } else {
+            self.set_status(format!("Deleted MCP server: {}", server_name));
+        }
+    }
+
+    /// Whether deleting the selected server would also drop a stored token.
+    pub fn selected_mcp_has_stored_credentials(&self) -> bool {
+        let Some(config) = self
+            .mcp_editor_state
+            .server_list
+            .get(self.mcp_editor_state.selected_server_idx)
+            .and_then(|name| self.paths.preferences.global_prefs.mcp_servers.get(name))
+        else {
+            return false;
+        };
+        config
+            .credential_keys()
+            .iter()
+            .any(|key| self.credentials.get_token(key).is_some())
     }
 
     pub fn mcp_toggle_enabled(&mut self) {
@@ -522,111 +1192,106 @@ impl App {
         let server_name =
             self.mcp_editor_state.server_list[self.mcp_editor_state.selected_server_idx].clone();
 
-        if self.paths.preferences.project_prefs.is_none() {
-            self.paths.preferences.project_prefs =
-                Some(crate::preferences::AgentPreferences::default());
+        let project_prefs = self.project_prefs_mut();
+        if let Some(pos) = project_prefs
+            .disabled_mcp_servers
+            .iter()
+            .position(|x| x == &server_name)
+        {
+            project_prefs.disabled_mcp_servers.remove(pos);
+            self.set_status(format!(
+                "Enabled MCP server: {} for this project",
+                server_name
+            ));
+        } else {
+            project_prefs.disabled_mcp_servers.push(server_name.clone());
+            self.set_status(format!(
+                "Disabled MCP server: {} for this project",
+                server_name
+            ));
         }
+        self.save_project_prefs();
+    }
 
-        if let Some(project_prefs) = &mut self.paths.preferences.project_prefs {
-            if let Some(pos) = project_prefs
-                .disabled_mcp_servers
-                .iter()
-                .position(|x| x == &server_name)
-            {
-                project_prefs.disabled_mcp_servers.remove(pos);
-                self.set_status(format!(
-                    "Enabled MCP server: {} for this project",
-                    server_name
-                ));
-            } else {
-                project_prefs.disabled_mcp_servers.push(server_name.clone());
-                self.set_status(format!(
-                    "Disabled MCP server: {} for this project",
-                    server_name
-                ));
-            }
+    /// Disables every listed server (only remote ones with `remote_only`) for the project,
+    /// or re-enables them all if they are already disabled.
+    pub fn mcp_toggle_all_enabled(&mut self, remote_only: bool) {
+        let servers = &self.paths.preferences.global_prefs.mcp_servers;
+        let targets: Vec<String> = self
+            .mcp_editor_state
+            .server_list
+            .iter()
+            .filter(|name| !remote_only || servers.get(*name).is_some_and(|c| c.url().is_some()))
+            .cloned()
+            .collect();
+        let kind = if remote_only { "remote " } else { "" };
+        if targets.is_empty() {
+            self.set_status(format!("No {}MCP servers to toggle", kind));
+            return;
+        }
 
-            if let Err(e) = self.paths.preferences.save_project(&self.paths.config_file) {
-                self.set_status(format!("Failed to save project config: {}", e));
-            }
+        let (disabled, changed) = self
+            .project_prefs_mut()
+            .toggle_disabled_mcp_servers(&targets);
+        self.set_status(format!(
+            "{} {} {}MCP server(s) for this project",
+            if disabled { "Disabled" } else { "Enabled" },
+            changed,
+            kind
+        ));
+        self.save_project_prefs();
+    }
 
-            if self.auto_sync {
-                let _ = self.sync_preferences();
+    fn project_prefs_mut(&mut self) -> &mut crate::preferences::AgentPreferences {
+        self.paths.preferences.project_prefs.get_or_insert_with(|| {
+            crate::preferences::AgentPreferences {
+                version: crate::preferences::PREFERENCES_VERSION,
+                ..Default::default()
             }
+        })
+    }
+
+    fn save_project_prefs(&mut self) {
+        if let Err(e) = self.paths.preferences.save_project(&self.paths.config_file) {
+            self.prefs_dirty = true;
+            self.set_status(format!("Failed to save project config: {}", e));
+        }
+
+        if self.auto_sync {
+            let _ = self.sync_preferences();
         }
     }
 
     pub fn mcp_submit(&mut self) {
         let name = self.mcp_editor_state.editing_name.trim().to_string();
         let command = self.mcp_editor_state.editing_command.trim().to_string();
-        let args: Vec<String> = self
-            .mcp_editor_state
-            .editing_args
-            .split_whitespace()
-            .map(String::from)
-            .collect();
-        let mut env = std::collections::HashMap::new();
-
-        for pair in self.mcp_editor_state.editing_env.split(',') {
-            if let Some((k, v)) = pair.split_once('=') {
-                env.insert(k.trim().to_string(), v.trim().to_string());
-            }
-        }
-
-        let disabled_tools: Vec<String> = self
-            .mcp_editor_state
-            .editing_disabled_tools
-            .split(',')
-            .map(|s| s.trim().to_string())
-            .filter(|s| !s.is_empty())
-            .collect();
-
-        let auto_allow = self.mcp_editor_state.editing_auto_allow;
 
         if name.is_empty() || command.is_empty() {
             self.set_status("Name and Command/URL are required".to_string());
             return;
         }
 
-        let config = if command.starts_with("http://") || command.starts_with("https://") {
-            let auth = self.build_auth_config();
-            let requires_oauth = auth.requires_oauth();
-
-            let config = McpServerConfig::Sse {
-                url: command,
-                auth,
-                disabled_tools,
-                auto_allow,
-            };
-
-            self.paths
-                .preferences
-                .global_prefs
-                .mcp_servers
-                .insert(name.clone(), config);
-
-            let _ = self.paths.preferences.save_global();
-            self.update_mcp_list();
-            self.mode = AppMode::Normal;
+        self.mcp_editor_state.clear_fields_for_server_type();
 
-            if requires_oauth {
-                self.set_status(format!(
-                    "Saved MCP server: {} - Press 'o' to authenticate",
-                    name
-                ));
-            } else {
-                self.set_status(format!("Saved MCP server: {} (syncs to all agents)", name));
-            }
-            return;
-        } else {
-            McpServerConfig::Stdio {
-                command,
-                args,
-                env,
-                disabled_tools,
-                auto_allow,
-            }
-        };
+        let headers_override_auth = self
+            .paths
+            .preferences
+            .global_prefs
+            .mcp_servers
+            .get(&name)
+            .is_some_and(|existing| match existing {
+                McpServerConfig::Sse {
+                    headers_override_auth,
+                    ..
+                }
+                | McpServerConfig::Http {
+                    headers_override_auth,
+                    ..
+                } => *headers_override_auth,
+                _ => false,
+            });
+        let config = self.mcp_editor_state.to_config(headers_override_auth);
+        let requires_oauth = config.requires_oauth();
 
         self.paths
             .preferences
@@ -634,97 +1299,50 @@ impl App {
             .mcp_servers
             .insert(name.clone(), config);
 
-        let _ = self.paths.preferences.save_global();
+        self.save_global_prefs();
         self.update_mcp_list();
         self.mode = AppMode::Normal;
-        self.set_status(format!("Saved MCP server: {} (syncs to all agents)", name));
-    }
-
-    fn build_auth_config(&self) -> McpAuth {
-        match self.mcp_editor_state.editing_auth_type {
-            McpAuthType::None => McpAuth::None,
-            McpAuthType::Bearer => {
-                let token = self.mcp_editor_state.editing_bearer_token.trim();
-                if token.is_empty() {
-                    McpAuth::None
-                } else {
-                    McpAuth::Bearer {
-                        token: token.to_string(),
-                    }
-                }
-            }
-            McpAuthType::OAuth => {
-                let client_id = self.mcp_editor_state.editing_oauth_client_id.trim();
-                if client_id.is_empty() {
-                    McpAuth::None
-                } else {
-                    let client_secret = {
-                        let s = self.mcp_editor_state.editing_oauth_client_secret.trim();
-                        if s.is_empty() {
-                            None
-                        } else {
-                            Some(s.to_string())
-                        }
-                    };
-                    let auth_server_url = {
-                        let s = self.mcp_editor_state.editing_oauth_auth_server_url.trim();
-                        if s.is_empty() {
-                            None
-                        } else {
-                            Some(s.to_string())
-                        }
-                    };
-                    let scopes: Vec<String> = self
-                        .mcp_editor_state
-                        .editing_oauth_scopes
-                        .split_whitespace()
-                        .map(String::from)
-                        .collect();
 
-                    McpAuth::OAuth {
-                        client_id: client_id.to_string(),
-                        client_secret,
-                        auth_server_url,
-                        scopes,
-                    }
-                }
-            }
+        if requires_oauth {
+            self.set_status(format!(
+                "Saved MCP server: {} - Press 'o' to authenticate",
+                name
+            ));
+        } else {
+            self.set_status(format!("Saved MCP server: {} (syncs to all agents)", name));
         }
     }
 
     pub fn magic_mcp_setup(&mut self) {
-        let defaults = vec![
-            (
-                "filesystem",
-                "npx",
-                vec!["-y", "@modelcontextprotocol/server-filesystem", "."],
-            ),
-            (
-                "memory",
-                "npx",
-                vec!["-y", "@modelcontextprotocol/server-memory"],
-            ),
-            ("filesystem-uvx", "uvx", vec!["mcp-server-filesystem", "."]),
-            ("memory-uvx", "uvx", vec!["mcp-server-memory"]),
-        ];
-
-        let mut added_count = 0;
+        let defaults = match crate::preferences::load_default_mcp_servers(&self.paths.config_dir) {
+            Ok(defaults) => defaults,
+            Err(e) => {
+                self.set_status(format!("Error loading default MCP servers: {}", e));
+                return;
+            }
+        };
+
+        let mut added = Vec::new();
+        let mut skipped = Vec::new();
         let mcp_servers = &mut self.paths.preferences.global_prefs.mcp_servers;
 
-        for (name, cmd, args) in &defaults {
-            if !mcp_servers.contains_key(*name) {
-                mcp_servers.insert(
-                    name.to_string(),
-                    McpServerConfig::Stdio {
-                        command: cmd.to_string(),
-                        args: args.iter().map(|s| s.to_string()).collect(),
-                        env: std::collections::HashMap::new(),
-                        disabled_tools: Vec::new(),
-                        auto_allow: false,
-                    },
+        for (name, config) in defaults {
+            if mcp_servers.contains_key(&name) {
+                continue;
+            }
+            if let McpServerConfig::Stdio { command, .. } = &config
+                && !crate::config::command_exists(command)
+            {
+                log::info!(
+                    "Skipping default MCP server '{}': '{}' is not installed",
+                    name,
+                    command
                 );
-                added_count += 1;
+                skipped.push(format!("{} ({})", name, command));
+                continue;
             }
+            added.push(name.clone());
+            mcp_servers.insert(name, config);
         }
 
         if !mcp_servers.contains_key("mooagent")
@@ -740,18 +1358,29 @@ impl App {
                     env: std::collections::HashMap::new(),
                     disabled_tools: Vec::new(),
                     auto_allow: false,
+                    description: None,
+                    tags: Vec::new(),
                 },
             );
-            added_count += 1;
+            added.push("mooagent".to_string());
         }
 
-        if added_count > 0 {
-            let _ = self.paths.preferences.save_global();
+        let skipped_note = if skipped.is_empty() {
+            String::new()
+        } else {
+            format!(" | Skipped, not installed: {}", skipped.join(", "))
+        };
+
+        if !added.is_empty() {
+            self.save_global_prefs();
             self.update_mcp_list();
             self.set_status(format!(
-                "Added {} default MCP servers (sync to apply to all agents)",
-                added_count
+                "Added default MCP servers: {} (sync to apply to all agents){}",
+                added.join(", "),
+                skipped_note
             ));
+        } else if !skipped.is_empty() {
+            self.set_status(format!("No default MCP servers added{}", skipped_note));
         } else {
             self.set_status("All default MCP servers already configured".to_string());
         }
@@ -766,16 +1395,18 @@ impl App {
         let auth_type = self.mcp_editor_state.editing_auth_type;
 
         self.mcp_editor_state.focus = match self.mcp_editor_state.focus {
-            McpFieldFocus::Name => McpFieldFocus::Command,
+            McpFieldFocus::Name => McpFieldFocus::Description,
+            McpFieldFocus::Description => McpFieldFocus::Tags,
+            McpFieldFocus::Tags => McpFieldFocus::Command,
             McpFieldFocus::Command => {
                 if is_remote {
-                    McpFieldFocus::DisabledTools
+                    McpFieldFocus::Headers
                 } else {
                     McpFieldFocus::Args
                 }
             }
             McpFieldFocus::Args => McpFieldFocus::Env,
-            McpFieldFocus::Env => McpFieldFocus::DisabledTools,
+            McpFieldFocus::Env | McpFieldFocus::Headers => McpFieldFocus::DisabledTools,
             McpFieldFocus::DisabledTools => McpFieldFocus::AutoAllow,
             McpFieldFocus::AutoAllow => {
                 if is_remote {
@@ -795,6 +1426,7 @@ impl App {
             McpFieldFocus::OAuthScopes => McpFieldFocus::OAuthAuthServerUrl,
             McpFieldFocus::OAuthAuthServerUrl => McpFieldFocus::Name,
         };
+        self.mcp_editor_state.cursor_to_end();
     }
 
     pub fn mcp_cycle_auth_type(&mut self, forward: bool) {
@@ -809,15 +1441,6 @@ impl App {
 
     pub fn mcp_input_char(&mut self, c: char) {
         match self.mcp_editor_state.focus {
-            McpFieldFocus::Name => {
-                if self.mcp_editor_state.is_new {
-                    self.mcp_editor_state.editing_name.push(c)
-                }
-            }
-            McpFieldFocus::Command => self.mcp_editor_state.editing_command.push(c),
-            McpFieldFocus::Args => self.mcp_editor_state.editing_args.push(c),
-            McpFieldFocus::Env => self.mcp_editor_state.editing_env.push(c),
-            McpFieldFocus::DisabledTools => self.mcp_editor_state.editing_disabled_tools.push(c),
             McpFieldFocus::AutoAllow => {
                 if c == ' ' {
                     self.mcp_editor_state.editing_auto_allow =
@@ -831,64 +1454,88 @@ impl App {
                     self.mcp_cycle_auth_type(false);
                 }
             }
-            McpFieldFocus::BearerToken => {
-                self.mcp_editor_state.editing_bearer_token.push(c);
-            }
-            McpFieldFocus::OAuthClientId => {
-                self.mcp_editor_state.editing_oauth_client_id.push(c);
-            }
-            McpFieldFocus::OAuthClientSecret => {
-                self.mcp_editor_state.editing_oauth_client_secret.push(c);
-            }
-            McpFieldFocus::OAuthScopes => {
-                self.mcp_editor_state.editing_oauth_scopes.push(c);
-            }
-            McpFieldFocus::OAuthAuthServerUrl => {
-                self.mcp_editor_state.editing_oauth_auth_server_url.push(c);
+            _ => {
+                if let Some((text, cursor)) = self.mcp_editor_state.focused_text() {
+                    insert_at_cursor(text, cursor, c.encode_utf8(&mut [0; 4]));
+                }
             }
         }
     }
 
+    /// Inserts pasted text into the focused text field in one go. Line breaks are dropped
+    /// since every field is single-line and copied tokens often carry a trailing newline.
+    pub fn mcp_paste(&mut self, text: &str) {
+        let pasted: String = text.chars().filter(|c| !matches!(c, '\r' | '\n')).collect();
+        if let Some((text, cursor)) = self.mcp_editor_state.focused_text() {
+            insert_at_cursor(text, cursor, &pasted);
+        }
+    }
+
     pub fn mcp_backspace(&mut self) {
-        match self.mcp_editor_state.focus {
-            McpFieldFocus::Name => {
-                if self.mcp_editor_state.is_new {
-                    let _ = self.mcp_editor_state.editing_name.pop();
-                }
-            }
-            McpFieldFocus::Command => {
-                let _ = self.mcp_editor_state.editing_command.pop();
-            }
-            McpFieldFocus::Args => {
-                let _ = self.mcp_editor_state.editing_args.pop();
-            }
-            McpFieldFocus::Env => {
-                let _ = self.mcp_editor_state.editing_env.pop();
-            }
-            McpFieldFocus::DisabledTools => {
-                let _ = self.mcp_editor_state.editing_disabled_tools.pop();
-            }
-            McpFieldFocus::AutoAllow => {}
-            McpFieldFocus::AuthType => {}
-            McpFieldFocus::BearerToken => {
-                let _ = self.mcp_editor_state.editing_bearer_token.pop();
-            }
-            McpFieldFocus::OAuthClientId => {
-                let _ = self.mcp_editor_state.editing_oauth_client_id.pop();
-            }
-            McpFieldFocus::OAuthClientSecret => {
-                let _ = self.mcp_editor_state.editing_oauth_client_secret.pop();
-            }
-            McpFieldFocus::OAuthScopes => {
-                let _ = self.mcp_editor_state.editing_oauth_scopes.pop();
-            }
-            McpFieldFocus::OAuthAuthServerUrl => {
-                let _ = self.mcp_editor_state.editing_oauth_auth_server_url.pop();
-            }
+        if let Some((text, cursor)) = self.mcp_editor_state.focused_text() {
+            delete_before_cursor(text, cursor);
+        }
+    }
+
+    /// Asks before syncing one agent or all of them, checking first whether that would
+    /// overwrite hand edits.
+    pub fn confirm_sync(&mut self, all: bool) {
+        let indices: Vec<usize> = if all {
+            (0..self.agents.len()).collect()
+        } else {
+            vec![self.selected_agent]
+        };
+        self.hand_edits = indices
+            .into_iter()
+            .filter(|&idx| {
+                self.agents
+                    .get(idx)
+                    .is_some_and(|a| a.status != crate::config::AgentStatus::Ok)
+            })
+            .filter_map(|idx| self.paths.hand_edit_diff(idx).map(|diff| (idx, diff)))
+            .collect();
+        self.detail_scroll = 0;
+        self.mode = if all {
+            AppMode::ConfirmSyncAll
+        } else {
+            AppMode::ConfirmSync
+        };
+    }
+
+    /// Pulls the first hand-edited agent's file back into AGENTS.md instead of syncing.
+    pub fn pull_hand_edits(&mut self) {
+        let Some((idx, _)) = self.hand_edits.first() else {
+            return;
+        };
+        match self.paths.pull_agent_edits(*idx) {
+            Ok(msg) => self.set_status(msg),
+            Err(e) => self.set_status(format!("Error pulling edits: {}", e)),
+        }
+        self.hand_edits.clear();
+        self.refresh();
+    }
+
+    /// Refreshes expired OAuth tokens on a background thread so the TUI never waits on
+    /// the network. `tick` syncs preferences again once new tokens are stored.
+    fn start_oauth_refresh(&mut self) {
+        if self.oauth_refresh.is_some() {
+            return;
         }
+        let prefs = self.paths.preferences.get_merged();
+        if crate::config::expired_oauth_servers(&prefs, &self.credentials).is_empty() {
+            return;
+        }
+
+        let (tx, rx) = std::sync::mpsc::channel();
+        let config_dir = self.paths.config_dir.clone();
+        std::thread::spawn(move || {
+            let _ = tx.send(crate::config::refresh_oauth_tokens(&config_dir, &prefs));
+        });
+        self.oauth_refresh = Some(rx);
     }
 
     pub fn sync(&mut self) -> Result<()> {
+        self.start_oauth_refresh();
         let agent_sync_result = self.paths.sync();
         let global_sync_result = self.paths.sync_global_rules();
         let pref_sync_result = self.paths.sync_preferences();
@@ -914,16 +1561,152 @@ impl App {
         }
     }
 
+    pub fn bootstrap_mooagent_mcp(&mut self) {
+        match self.paths.add_mooagent_mcp() {
+            Ok(Some(path)) => {
+                self.update_mcp_list();
+                if self.sync().is_ok() {
+                    self.set_status(format!(
+                        "Bootstrapped mooagent MCP server ({}) and synced to all agents",
+                        path.display()
+                    ));
+                }
+            }
+            Ok(None) => {
+                self.set_status("mooagent MCP is already configured".to_string());
+            }
+            Err(e) => {
+                self.set_status(format!("Bootstrap Error: {}", e));
+            }
+        }
+    }
+
+    pub fn open_backups(&mut self) {
+        self.detail_scroll = 0;
+        self.backup_selected = 0;
+        self.mode = AppMode::ViewBackups;
+    }
+
+    pub fn backup_next(&mut self) {
+        let count = self.paths.list_backups(self.selected_agent).len();
+        if self.backup_selected + 1 < count {
+            self.backup_selected += 1;
+        }
+    }
+
+    pub fn backup_prev(&mut self) {
+        self.backup_selected = self.backup_selected.saturating_sub(1);
+    }
+
+    /// Copies the `index`-th backup (newest first) over the selected agent's file. The
+    /// current file is backed up first, so a restore can itself be undone.
+    pub fn restore_selected_backup(&mut self, index: usize) {
+        let Some((name, target_path)) = self
+            .agents
+            .get(self.selected_agent)
+            .map(|a| (a.name.clone(), a.target_path.clone()))
+        else {
+            self.set_status("No agent selected".to_string());
+            return;
+        };
+        let backups = self.paths.list_backups(self.selected_agent);
+        let Some(backup) = backups.get(index) else {
+            self.set_status(format!("No backups to restore for {}", name));
+            return;
+        };
+        // Copying onto the link would overwrite AGENTS.md itself
+        if target_path.is_symlink() {
+            self.set_status(format!(
+                "{} is a symlink to AGENTS.md; restore AGENTS.md instead",
+                name
+            ));
+            return;
+        }
+
+        match self.paths.restore_backup(backup, &target_path) {
+            Ok(()) => {
+                let backup_name = backup
+                    .file_name()
+                    .and_then(|n| n.to_str())
+                    .unwrap_or("backup");
+                self.set_status(format!("Restored {} from {}", name, backup_name));
+                self.mode = AppMode::Normal;
+                self.refresh();
+            }
+            Err(e) => self.set_status(format!("Restore Error: {}", e)),
+        }
+    }
+
+    pub fn backup_now(&mut self) {
+        match self.paths.backup_now() {
+            Ok(backups) if backups.is_empty() => {
+                self.set_status("Nothing to back up: no rule files exist yet".to_string());
+            }
+            Ok(backups) => {
+                let names: Vec<String> = backups
+                    .iter()
+                    .filter_map(|p| p.file_name().and_then(|n| n.to_str()).map(String::from))
+                    .collect();
+                self.set_status(format!("Backed up: {}", names.join(", ")));
+            }
+            Err(e) => {
+                self.set_status(format!("Backup Error: {}", e));
+            }
+        }
+    }
+
+    pub fn repoint_mooagent_mcp(&mut self) {
+        if self.stale_mooagent_command.is_none() {
+            self.set_status("mooagent MCP command path is up to date".to_string());
+            return;
+        }
+
+        match self.paths.repoint_mooagent_mcp() {
+            Ok(path) => {
+                self.set_status(format!("Re-pointed mooagent MCP to {}", path.display()));
+                let _ = self.sync_preferences();
+            }
+            Err(e) => {
+                self.set_status(format!("Error re-pointing mooagent MCP: {}", e));
+            }
+        }
+    }
+
     pub fn set_status(&mut self, msg: String) {
         log::info!("{}", msg);
         self.status_message = Some((msg.clone(), Instant::now()));
-        self.status_log.push((msg, Instant::now()));
+        self.needs_redraw = true;
+        self.status_log.push((msg, chrono::Local::now()));
 
-        if self.status_log.len() > 100 {
-            self.status_log.drain(0..1);
+        let capacity = self
+            .paths
+            .preferences
+            .get_merged()
+            .general
+            .status_log_capacity
+            .unwrap_or(DEFAULT_STATUS_LOG_CAPACITY);
+        if self.status_log.len() > capacity {
+            let excess = self.status_log.len() - capacity;
+            self.status_log.drain(0..excess);
         }
     }
 
+    /// Saves the status log next to `audit.jsonl` so it survives the session.
+    pub fn export_status_log(&mut self) {
+        let dir = self
+            .paths
+            .audit_log_file
+            .parent()
+            .map(std::path::Path::to_path_buf)
+            .unwrap_or_else(std::env::temp_dir);
+        match export_status_log(&dir, &self.status_log) {
+            Ok(file) => self.set_status(format!("Exported status log to {}", file.display())),
+            Err(e) => self.set_status(format!("Error exporting status log: {}", e)),
+        }
+    }
+
+    /// Handles file events, finished OAuth refreshes and status expiry, flagging a redraw
+    /// when any of them changes state.
     pub fn tick(&mut self) {
         if let Some(rx) = &self.event_rx {
             let mut changed = false;
@@ -935,10 +1718,67 @@ impl App {
             }
         }
 
+        if let Some(rx) = &self.oauth_refresh {
+            match rx.try_recv() {
+                Ok(refreshed) => {
+                    self.oauth_refresh = None;
+                    if !refreshed.is_empty() {
+                        self.resync_after_oauth_refresh(&refreshed);
+                    }
+                }
+                Err(std::sync::mpsc::TryRecvError::Disconnected) => self.oauth_refresh = None,
+                Err(std::sync::mpsc::TryRecvError::Empty) => {}
+            }
+        }
+
         if let Some((_, time)) = self.status_message
             && time.elapsed() > Duration::from_secs(self.status_message_timeout)
         {
             self.status_message = None;
+            self.needs_redraw = true;
+        }
+    }
+
+    /// Writes the refreshed tokens' Authorization headers into the agents' configs.
+    fn resync_after_oauth_refresh(&mut self, refreshed: &[String]) {
+        let _ = self.credentials.load();
+        match self.paths.sync_preferences() {
+            Ok(msg) => self.set_status(format!(
+                "Refreshed OAuth token(s) for {} | {}",
+                refreshed.join(", "),
+                msg
+            )),
+            Err(e) => self.set_status(format!(
+                "Refreshed OAuth token(s) for {}, but syncing preferences failed: {}",
+                refreshed.join(", "),
+                e
+            )),
+        }
+        self.refresh();
+    }
+
+    /// How long to wait for input before the next tick. A pending status message needs
+    /// frequent ticks to expire on time; otherwise the loop can sleep longer.
+    pub fn poll_interval(&self) -> Duration {
+        if self.status_message.is_some() {
+            ACTIVE_POLL_INTERVAL
+        } else {
+            self.idle_poll_interval
+        }
+    }
+
+    /// Whether the screen shows "… ago" times (the dashboard's Last Synced column or the
+    /// log overlay), which go stale unless redrawn while idle.
+    pub fn shows_relative_times(&self) -> bool {
+        match self.mode {
+            AppMode::Normal => self.show_error_log || self.active_tab == ActiveTab::Dashboard,
+            AppMode::Search => self.active_tab == ActiveTab::Dashboard,
+            AppMode::SectionOutline
+            | AppMode::ConfirmSync
+            | AppMode::ConfirmSyncAll
+            | AppMode::ConfirmDeleteMcp
+            | AppMode::ConfirmAutoSync => true,
+            _ => false,
         }
     }
 
@@ -973,6 +1813,42 @@ impl App {
         }
     }
 
+    /// Switches to the tab under the click, returning whether one was hit.
+    pub fn click_tab(&mut self, column: u16, row: u16) -> bool {
+        let area = self.tab_area.get();
+        if !area.contains(Position::new(column, row)) {
+            return false;
+        }
+
+        match tab_at(&tab_titles(self.prefs_dirty), column - area.x) {
+            Some(index) => {
+                self.active_tab = ActiveTab::ALL[index];
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn click_dashboard(&mut self, column: u16, row: u16) {
+        let layout = self.dashboard_layout.get();
+        let position = Position::new(column, row);
+
+        if layout.agent_table.contains(position) {
+            self.focus = Focus::Agents;
+            // Skip the top border and the header row
+            let first_row = layout.agent_table.y + 2;
+            if row >= first_row
+                && let Some(&idx) = self.filtered_agents.get((row - first_row) as usize)
+            {
+                self.selected_agent = idx;
+            }
+        } else if layout.global_pane.contains(position) {
+            self.focus = Focus::Global;
+        } else if layout.project_pane.contains(position) {
+            self.focus = Focus::Project;
+        }
+    }
+
     pub fn prev_agent(&mut self) {
         if !self.filtered_agents.is_empty() {
             let current_pos = self
@@ -1002,24 +1878,6 @@ impl App {
         }
     }
 
-    pub fn scroll_project_page_down(&mut self) {
-        let line_count = self.project_content.lines().count();
-        self.project_scroll = (self.project_scroll + 10).min(line_count.saturating_sub(1));
-    }
-
-    pub fn scroll_project_page_up(&mut self) {
-        self.project_scroll = self.project_scroll.saturating_sub(10);
-    }
-
-    pub fn scroll_project_home(&mut self) {
-        self.project_scroll = 0;
-    }
-
-    pub fn scroll_project_end(&mut self) {
-        let line_count = self.project_content.lines().count();
-        self.project_scroll = line_count.saturating_sub(1);
-    }
-
     pub fn scroll_global_down(&mut self) {
         let line_count = self.global_content.lines().count();
         if self.global_scroll < line_count.saturating_sub(1) {
@@ -1058,15 +1916,194 @@ impl App {
                     self.selected_agent = self.filtered_agents[self.filtered_agents.len() - 1];
                 }
             }
-            Focus::Global => {
-                self.global_scroll = self.global_content.lines().count().saturating_sub(1);
+            Focus::Global | Focus::Project => self.scroll_end(),
+        }
+    }
+
+    /// The rule pane that page/Home/End keys act on: Global when focused, otherwise Project.
+    fn paged_rule_pane(&self) -> Focus {
+        if self.focus == Focus::Global {
+            Focus::Global
+        } else {
+            Focus::Project
+        }
+    }
+
+    /// Rendered rows per line of the pane's content, and the pane's visible row count.
+    fn rule_pane_rows(&self, pane: Focus) -> (Vec<usize>, usize) {
+        let layout = self.dashboard_layout.get();
+        let (content, area) = if pane == Focus::Global {
+            (&self.global_content, layout.global_pane)
+        } else {
+            (&self.project_content, layout.project_pane)
+        };
+
+        if area.width <= 2 || area.height <= 2 {
+            return (vec![1; content.lines().count()], DEFAULT_PAGE_ROWS);
+        }
+        // Inside the border
+        (
+            wrapped_line_rows(content, (area.width - 2) as usize),
+            (area.height - 2) as usize,
+        )
+    }
+
+    fn rule_pane_scroll(&mut self, pane: Focus) -> &mut usize {
+        if pane == Focus::Global {
+            &mut self.global_scroll
+        } else {
+            &mut self.project_scroll
+        }
+    }
+
+    pub fn scroll_page_down(&mut self, half: bool) {
+        let pane = self.paged_rule_pane();
+        let (rows, height) = self.rule_pane_rows(pane);
+        let page = if half { height / 2 } else { height };
+        let scroll = self.rule_pane_scroll(pane);
+        *scroll = page_down_scroll(*scroll, &rows, page);
+    }
+
+    pub fn scroll_page_up(&mut self, half: bool) {
+        let pane = self.paged_rule_pane();
+        let (rows, height) = self.rule_pane_rows(pane);
+        let page = if half { height / 2 } else { height };
+        let scroll = self.rule_pane_scroll(pane);
+        *scroll = page_up_scroll(*scroll, &rows, page);
+    }
+
+    /// Headings of the focused rules file with their 1-based line numbers.
+    pub fn outline_sections(&self) -> Vec<(String, usize)> {
+        match self.focus {
+            Focus::Global => crate::mcp::list_markdown_sections(&self.global_content),
+            Focus::Project => crate::mcp::list_markdown_sections(&self.project_content),
+            Focus::Agents => Vec::new(),
+        }
+    }
+
+    pub fn open_section_outline(&mut self) {
+        let scroll = match self.focus {
+            Focus::Global => self.global_scroll,
+            Focus::Project => self.project_scroll,
+            Focus::Agents => {
+                self.set_status(
+                    "Focus the Global or Project rules to open the outline".to_string(),
+                );
+                return;
+            }
+        };
+
+        let sections = self.outline_sections();
+        if sections.is_empty() {
+            self.set_status("No markdown headings in this file".to_string());
+            return;
+        }
+
+        // Start on the section the pane is currently showing
+        self.outline_selected = sections
+            .iter()
+            .rposition(|(_, line_num)| line_num - 1 <= scroll)
+            .unwrap_or(0);
+        self.mode = AppMode::SectionOutline;
+    }
+
+    pub fn outline_next(&mut self) {
+        if self.outline_selected + 1 < self.outline_sections().len() {
+            self.outline_selected += 1;
+        }
+    }
+
+    pub fn outline_prev(&mut self) {
+        self.outline_selected = self.outline_selected.saturating_sub(1);
+    }
+
+    pub fn outline_jump(&mut self) {
+        if let Some((_, line_num)) = self.outline_sections().get(self.outline_selected) {
+            let line = line_num - 1;
+            match self.focus {
+                Focus::Global => self.global_scroll = line,
+                Focus::Project => self.project_scroll = line,
+                Focus::Agents => {}
+            }
+        }
+        self.mode = AppMode::Normal;
+    }
+
+    /// Scrolls the focused rules pane to the next (or previous) markdown heading.
+    pub fn jump_to_section(&mut self, forward: bool) {
+        let (content, scroll) = match self.focus {
+            Focus::Global => (&self.global_content, &mut self.global_scroll),
+            Focus::Project => (&self.project_content, &mut self.project_scroll),
+            Focus::Agents => return,
+        };
+
+        // Section line numbers are 1-based
+        let mut heading_lines = crate::mcp::list_markdown_sections(content)
+            .into_iter()
+            .map(|(_, line_num)| line_num - 1);
+        let target = if forward {
+            heading_lines.find(|&line| line > *scroll)
+        } else {
+            heading_lines.rev().find(|&line| line < *scroll)
+        };
+
+        if let Some(line) = target {
+            *scroll = line;
+        }
+    }
+
+    pub fn scroll_home(&mut self) {
+        let pane = self.paged_rule_pane();
+        *self.rule_pane_scroll(pane) = 0;
+    }
+
+    /// Drops the screen areas cached for mouse hit-testing, which are stale after a resize,
+    /// and schedules a scroll re-clamp against the new pane sizes.
+    pub fn handle_resize(&mut self) {
+        self.dashboard_layout.set(DashboardLayout::default());
+        self.tab_area.set(Rect::default());
+        self.clamp_scroll_after_draw = true;
+        self.needs_redraw = true;
+    }
+
+    /// Keeps rule panes from staying scrolled past their content, e.g. after the window
+    /// grew taller. Panes not on screen are left alone.
+    pub fn clamp_rule_scrolls(&mut self) {
+        let layout = self.dashboard_layout.get();
+        for (pane, area) in [
+            (Focus::Global, layout.global_pane),
+            (Focus::Project, layout.project_pane),
+        ] {
+            if area.width <= 2 || area.height <= 2 {
+                continue;
             }
-            Focus::Project => {
-                self.project_scroll = self.project_content.lines().count().saturating_sub(1);
+            let (rows, height) = self.rule_pane_rows(pane);
+            let max = end_scroll(&rows, height);
+            let scroll = self.rule_pane_scroll(pane);
+            if *scroll > max {
+                *scroll = max;
+                self.needs_redraw = true;
             }
         }
     }
 
+    pub fn scroll_end(&mut self) {
+        let pane = self.paged_rule_pane();
+        let (rows, height) = self.rule_pane_rows(pane);
+        *self.rule_pane_scroll(pane) = end_scroll(&rows, height);
+    }
+
+    pub fn save_ui_state(&self) {
+        let state = UiState {
+            active_tab: self.active_tab,
+            focus: self.focus,
+            auto_sync: self.auto_sync,
+        };
+        if let Err(e) = state.save(&self.paths.ui_state_file) {
+            log::warn!("Failed to save UI state: {}", e);
+        }
+    }
+
     pub fn toggle_auto_sync(&mut self) {
         self.auto_sync = !self.auto_sync;
         let status = if self.auto_sync {
@@ -1120,23 +2157,81 @@ impl App {
         }
     }
 
+    /// The search text for the active tab: the MCP tab filters servers, the others agents.
+    fn search_input(&mut self) -> (&mut String, &mut usize) {
+        if self.active_tab == ActiveTab::McpServers {
+            (
+                &mut self.mcp_editor_state.filter,
+                &mut self.mcp_editor_state.filter_cursor,
+            )
+        } else {
+            (&mut self.search_query, &mut self.search_cursor)
+        }
+    }
+
+    fn apply_search(&mut self) {
+        if self.active_tab == ActiveTab::McpServers {
+            self.update_mcp_list();
+        } else {
+            self.update_filter();
+        }
+    }
+
+    pub fn start_search(&mut self) {
+        let (query, cursor) = self.search_input();
+        *cursor = query.chars().count();
+        self.mode = AppMode::Search;
+    }
+
     pub fn add_search_char(&mut self, c: char) {
-        self.search_query.push(c);
-        self.update_filter();
+        let (query, cursor) = self.search_input();
+        insert_at_cursor(query, cursor, c.encode_utf8(&mut [0; 4]));
+        self.apply_search();
     }
 
     pub fn backspace_search(&mut self) {
-        self.search_query.pop();
-        self.update_filter();
+        let (query, cursor) = self.search_input();
+        delete_before_cursor(query, cursor);
+        self.apply_search();
     }
 
     pub fn clear_search(&mut self) {
-        self.search_query.clear();
-        self.update_filter();
+        let (query, cursor) = self.search_input();
+        query.clear();
+        *cursor = 0;
+        self.apply_search();
+    }
+
+    /// The text input of the current dialog and its cursor, if one is open.
+    fn active_input(&mut self) -> Option<(&mut String, &mut usize)> {
+        match self.mode {
+            AppMode::EditMcp => self.mcp_editor_state.focused_text(),
+            AppMode::Search => Some(self.search_input()),
+            AppMode::AddTool => Some((&mut self.new_tool_input, &mut self.new_tool_cursor)),
+            _ => None,
+        }
+    }
+
+    pub fn move_input_cursor(&mut self, movement: CursorMove) {
+        if let Some((text, cursor)) = self.active_input() {
+            move_cursor(text, cursor, movement);
+        }
+    }
+
+    pub fn delete_input_char(&mut self) {
+        if let Some((text, cursor)) = self.active_input() {
+            delete_at_cursor(text, cursor);
+        }
+        if self.mode == AppMode::Search {
+            self.apply_search();
+        }
     }
 
     pub fn toggle_error_log(&mut self) {
         self.show_error_log = !self.show_error_log;
+        if self.show_error_log {
+            self.audit_entries = crate::audit::recent(&self.paths.audit_log_file, 20);
+        }
     }
 
     pub fn next_focus(&mut self) {
@@ -1147,17 +2242,19 @@ impl App {
         };
     }
 
+    /// Moves focus one step left along Agents ↔ Global ↔ Project.
     pub fn focus_left(&mut self) {
         self.focus = match self.focus {
             Focus::Project => Focus::Global,
-            _ => self.focus,
+            Focus::Global | Focus::Agents => Focus::Agents,
         };
     }
 
+    /// Moves focus one step right along Agents ↔ Global ↔ Project.
     pub fn focus_right(&mut self) {
         self.focus = match self.focus {
-            Focus::Global => Focus::Project,
-            _ => self.focus,
+            Focus::Agents => Focus::Global,
+            Focus::Global | Focus::Project => Focus::Project,
         };
     }
 
@@ -1169,6 +2266,7 @@ impl App {
     }
 
     pub fn sync_preferences(&mut self) -> Result<()> {
+        self.start_oauth_refresh();
         match self.paths.sync_preferences() {
             Ok(msg) => {
                 self.set_status(msg);
@@ -1186,7 +2284,8 @@ impl App {
         self.pref_editor_state.focus = match self.pref_editor_state.focus {
             PrefEditorFocus::Presets => PrefEditorFocus::IndividualTools,
             PrefEditorFocus::IndividualTools => PrefEditorFocus::GeneralSettings,
-            PrefEditorFocus::GeneralSettings => PrefEditorFocus::Presets,
+            PrefEditorFocus::GeneralSettings => PrefEditorFocus::MooagentTools,
+            PrefEditorFocus::MooagentTools => PrefEditorFocus::Presets,
         };
     }
 
@@ -1211,10 +2310,51 @@ impl App {
                 }
             }
             PrefEditorFocus::GeneralSettings => {
-                if self.pref_editor_state.selected_general < 2 {
+                // Globally the list ends with two mooagent-only toggles
+                let last = if self.pref_editor_state.general_scope.is_some() {
+                    GeneralSetting::ALL.len() - 1
+                } else {
+                    GeneralSetting::ALL.len() + 1
+                };
+                if self.pref_editor_state.selected_general < last {
                     self.pref_editor_state.selected_general += 1;
                 }
             }
+            PrefEditorFocus::MooagentTools => {
+                if self.pref_editor_state.selected_mooagent_tool
+                    < self
+                        .pref_editor_state
+                        .mooagent_tool_list
+                        .len()
+                        .saturating_sub(1)
+                {
+                    self.pref_editor_state.selected_mooagent_tool += 1;
+                }
+            }
+        }
+    }
+
+    /// Switches the General Settings panel between global settings and each synced
+    /// agent's overrides.
+    pub fn pref_cycle_general_scope(&mut self, forward: bool) {
+        let mut scopes: Vec<Option<String>> = vec![None];
+        scopes.extend(self.paths.preference_agent_names().into_iter().map(Some));
+
+        let current = scopes
+            .iter()
+            .position(|s| *s == self.pref_editor_state.general_scope)
+            .unwrap_or(0);
+        let next = if forward {
+            (current + 1) % scopes.len()
+        } else {
+            (current + scopes.len() - 1) % scopes.len()
+        };
+        self.pref_editor_state.general_scope = scopes.swap_remove(next);
+        if self.pref_editor_state.general_scope.is_some() {
+            self.pref_editor_state.selected_general = self
+                .pref_editor_state
+                .selected_general
+                .min(GeneralSetting::ALL.len() - 1);
         }
     }
 
@@ -1235,6 +2375,11 @@ impl App {
                     self.pref_editor_state.selected_general -= 1;
                 }
             }
+            PrefEditorFocus::MooagentTools => {
+                if self.pref_editor_state.selected_mooagent_tool > 0 {
+                    self.pref_editor_state.selected_mooagent_tool -= 1;
+                }
+            }
         }
     }
 
@@ -1292,34 +2437,106 @@ impl App {
             }
             PrefEditorFocus::GeneralSettings => {
                 let mgr = &mut self.paths.preferences;
+                if let Some(agent) = &self.pref_editor_state.general_scope {
+                    let Some(setting) =
+                        GeneralSetting::ALL.get(self.pref_editor_state.selected_general)
+                    else {
+                        return;
+                    };
+                    let entry = mgr
+                        .global_prefs
+                        .agent_specific
+                        .entry(agent.clone())
+                        .or_default();
+                    let general = entry.general.get_or_insert_with(Default::default);
+                    // Cycle inherit -> on -> off -> inherit
+                    let value = setting.value_mut(general);
+                    *value = match *value {
+                        None => Some(true),
+                        Some(true) => Some(false),
+                        Some(false) => None,
+                    };
+                    if general.is_empty() {
+                        entry.general = None;
+                    }
+                    self.save_global_prefs();
+                    self.refresh();
+                    return;
+                }
                 match self.pref_editor_state.selected_general {
-                    0 => {
-                        let current = mgr.global_prefs.general.auto_accept_tools.unwrap_or(true);
-                        mgr.global_prefs.general.auto_accept_tools = Some(!current);
+                    idx @ 0..=2 => {
+                        // Cycle unset -> on -> off -> unset; unset leaves the agents alone
+                        let value =
+                            GeneralSetting::ALL[idx].value_mut(&mut mgr.global_prefs.general);
+                        *value = match *value {
+                            None => Some(true),
+                            Some(true) => Some(false),
+                            Some(false) => None,
+                        };
                     }
-                    1 => {
-                        let current = mgr.global_prefs.general.enable_logging.unwrap_or(true);
-                        mgr.global_prefs.general.enable_logging = Some(!current);
+                    3 => {
+                        let current = mgr
+                            .global_prefs
+                            .general
+                            .require_confirmation_for_destructive
+                            .unwrap_or(false);
+                        mgr.global_prefs
+                            .general
+                            .require_confirmation_for_destructive = Some(!current);
                     }
-                    2 => {
-                        let current = mgr.global_prefs.general.sandboxed_mode.unwrap_or(true);
-                        mgr.global_prefs.general.sandboxed_mode = Some(!current);
+                    4 => {
+                        let current = mgr.global_prefs.general.backup_before_edit.unwrap_or(true);
+                        mgr.global_prefs.general.backup_before_edit = Some(!current);
                     }
                     _ => {}
                 }
             }
+            PrefEditorFocus::MooagentTools => {
+                let Some(tool_name) = self
+                    .pref_editor_state
+                    .mooagent_tool_list
+                    .get(self.pref_editor_state.selected_mooagent_tool)
+                    .cloned()
+                else {
+                    return;
+                };
+                let Some(disabled) = self
+                    .paths
+                    .preferences
+                    .global_prefs
+                    .mcp_servers
+                    .get_mut("mooagent")
+                    .and_then(|config| config.disabled_tools_mut())
+                else {
+                    self.set_status(
+                        "mooagent MCP is not configured (press I on the dashboard to add it)"
+                            .to_string(),
+                    );
+                    return;
+                };
+
+                if let Some(pos) = disabled.iter().position(|t| *t == tool_name) {
+                    disabled.remove(pos);
+                } else {
+                    disabled.push(tool_name);
+                }
+            }
         }
 
-        let _ = self.paths.preferences.save_global();
+        self.save_global_prefs();
         self.refresh();
     }
 
     pub fn add_tool_char(&mut self, c: char) {
-        self.new_tool_input.push(c);
+        insert_at_cursor(
+            &mut self.new_tool_input,
+            &mut self.new_tool_cursor,
+            c.encode_utf8(&mut [0; 4]),
+        );
     }
 
     pub fn backspace_add_tool(&mut self) {
-        self.new_tool_input.pop();
+        delete_before_cursor(&mut self.new_tool_input, &mut self.new_tool_cursor);
     }
 
     pub fn submit_new_tool(&mut self) {
@@ -1334,7 +2551,12 @@ impl App {
                 self.pref_editor_state
                     .individual_tool_list
                     .push(tool_name.clone());
-                self.pref_editor_state.individual_tool_list.sort();
+                let category = self.pending_tool_category();
+                let prefs = &mut self.paths.preferences.global_prefs;
+                if !crate::preferences::tool_preset_index().contains_key(tool_name.as_str()) {
+                    prefs.tool_categories.insert(tool_name.clone(), category);
+                }
+                sort_tool_list(&mut self.pref_editor_state.individual_tool_list, prefs);
 
                 if let Some(pos) = self
                     .pref_editor_state
@@ -1345,23 +2567,43 @@ impl App {
                     self.pref_editor_state.selected_tool = pos;
                 }
 
+                let warning = crate::preferences::unknown_tool_warning(&tool_name);
                 self.paths
                     .preferences
                     .global_prefs
                     .individual_tools
                     .insert(tool_name, true);
-                let _ = self.paths.preferences.save_global();
+                if let Some(warning) = warning {
+                    self.set_status(warning);
+                }
+                self.save_global_prefs();
             }
         }
-        self.new_tool_input.clear();
-        self.mode = AppMode::Normal;
+        self.cancel_add_tool();
     }
 
     pub fn cancel_add_tool(&mut self) {
         self.new_tool_input.clear();
+        self.new_tool_cursor = 0;
+        self.new_tool_category = None;
         self.mode = AppMode::Normal;
     }
 
+    /// The category the tool being added will be saved with.
+    pub fn pending_tool_category(&self) -> ToolCategory {
+        self.new_tool_category
+            .unwrap_or_else(|| ToolCategory::guess(self.new_tool_input.trim()))
+    }
+
+    /// Cycles the Add Tool category: guessed, LLM tool, program.
+    pub fn cycle_new_tool_category(&mut self) {
+        self.new_tool_category = match self.new_tool_category {
+            None => Some(ToolCategory::Llm),
+            Some(ToolCategory::Llm) => Some(ToolCategory::Program),
+            Some(ToolCategory::Program) => None,
+        };
+    }
+
     pub fn get_selected_mcp_oauth_status(&self) -> Option<(TokenStatus, Option<String>)> {
         if self.mcp_editor_state.server_list.is_empty() {
             return None;
@@ -0,0 +1,212 @@
+use crate::preferences::is_secret_key;
+use anyhow::Result;
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::Path;
+
+/// Argument strings longer than this are logged as a length only (e.g. whole rule files).
+const MAX_LOGGED_STRING: usize = 120;
+
+/// One MCP tool invocation, appended as a JSON line to the audit log.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditEntry {
+    pub timestamp: DateTime<Local>,
+    pub tool: String,
+    pub arguments: Value,
+    pub ok: bool,
+}
+
+/// Command-line flags such as `--api-key` or `--auth-token` whose value is a credential.
+fn is_secret_flag(flag: &str) -> bool {
+    let name = flag.trim_start_matches('-');
+    flag.starts_with('-') && (is_secret_key(name) || name.to_lowercase().contains("key"))
+}
+
+/// Copies `arguments` with secret-looking values masked and long strings shortened.
+/// Header and env maps are masked wholesale since their values are usually credentials.
+/// Strings holding JSON (e.g. `import_config`'s `config`) are redacted like arguments,
+/// and so are `--token=...` style command-line args.
+pub fn redact_arguments(arguments: &Value) -> Value {
+    match arguments {
+        Value::Object(map) => Value::Object(
+            map.iter()
+                .map(|(key, value)| {
+                    let redacted = if is_secret_key(key) {
+                        Value::String("[redacted]".to_string())
+                    } else if key == "headers" || key == "env" {
+                        mask_values(value)
+                    } else {
+                        redact_arguments(value)
+                    };
+                    (key.clone(), redacted)
+                })
+                .collect(),
+        ),
+        Value::Array(items) => Value::Array(
+            items
+                .iter()
+                .enumerate()
+                .map(|(i, item)| {
+                    // The value of a flag given as a separate arg, e.g. ["--token", "..."]
+                    let follows_secret_flag = i > 0
+                        && items[i - 1]
+                            .as_str()
+                            .is_some_and(|prev| !prev.contains('=') && is_secret_flag(prev));
+                    if follows_secret_flag && item.is_string() {
+                        Value::String("[redacted]".to_string())
+                    } else {
+                        redact_arguments(item)
+                    }
+                })
+                .collect(),
+        ),
+        Value::String(s) if s.chars().count() > MAX_LOGGED_STRING => {
+            Value::String(format!("<{} chars>", s.chars().count()))
+        }
+        Value::String(s) => {
+            if let Some((flag, _)) = s.split_once('=')
+                && is_secret_flag(flag)
+            {
+                return Value::String(format!("{}=[redacted]", flag));
+            }
+            match serde_json::from_str::<Value>(s) {
+                Ok(json @ (Value::Object(_) | Value::Array(_))) => {
+                    Value::String(redact_arguments(&json).to_string())
+                }
+                _ => Value::String(s.clone()),
+            }
+        }
+        other => other.clone(),
+    }
+}
+
+/// Keeps the keys of a map (or the `KEY=` part of `KEY=VAL` lists) but hides the values.
+fn mask_values(value: &Value) -> Value {
+    match value {
+        Value::Object(map) => Value::Object(
+            map.keys()
+                .map(|k| (k.clone(), Value::String("[redacted]".to_string())))
+                .collect(),
+        ),
+        Value::String(s) => Value::String(
+            s.split(',')
+                .map(|pair| match pair.split_once('=') {
+                    Some((k, _)) => format!("{}=[redacted]", k.trim()),
+                    None => pair.trim().to_string(),
+                })
+                .collect::<Vec<_>>()
+                .join(","),
+        ),
+        _ => Value::String("[redacted]".to_string()),
+    }
+}
+
+pub fn record(path: &Path, tool: &str, arguments: &Value, ok: bool) -> Result<()> {
+    let entry = AuditEntry {
+        timestamp: Local::now(),
+        tool: tool.to_string(),
+        arguments: redact_arguments(arguments),
+        ok,
+    };
+
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    writeln!(file, "{}", serde_json::to_string(&entry)?)?;
+    Ok(())
+}
+
+/// The last `limit` entries, oldest first. Unparseable lines are skipped.
+pub fn recent(path: &Path, limit: usize) -> Vec<AuditEntry> {
+    let Ok(content) = std::fs::read_to_string(path) else {
+        return Vec::new();
+    };
+    let entries: Vec<AuditEntry> = content
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect();
+    entries[entries.len().saturating_sub(limit)..].to_vec()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_redact_arguments() {
+        let redacted = redact_arguments(&json!({
+            "name": "api",
+            "token": "abc123",
+            "client_secret": "shh",
+            "headers": "X-Api-Key=k1, X-Team=moo",
+            "env": {"GITHUB_TOKEN": "ghp"},
+            "content": "x".repeat(500),
+        }));
+
+        assert_eq!(redacted["name"], "api");
+        assert_eq!(redacted["token"], "[redacted]");
+        assert_eq!(redacted["client_secret"], "[redacted]");
+        assert_eq!(
+            redacted["headers"],
+            "X-Api-Key=[redacted],X-Team=[redacted]"
+        );
+        assert_eq!(redacted["env"], json!({"GITHUB_TOKEN": "[redacted]"}));
+        assert_eq!(redacted["content"], "<500 chars>");
+    }
+
+    #[test]
+    fn test_redact_json_string_arguments() {
+        let config = json!({
+            "mcp_servers": {
+                "api": {"http_url": "https://x.dev", "auth": {"type": "bearer", "token": "sk-1"}}
+            }
+        })
+        .to_string();
+        assert!(config.len() <= MAX_LOGGED_STRING);
+
+        let redacted = redact_arguments(&json!({"config": config, "left": "[1]"}));
+        let config: Value = serde_json::from_str(redacted["config"].as_str().unwrap()).unwrap();
+        assert_eq!(config["mcp_servers"]["api"]["auth"]["token"], "[redacted]");
+        assert_eq!(config["mcp_servers"]["api"]["http_url"], "https://x.dev");
+        assert_eq!(redacted["left"], "[1]");
+    }
+
+    #[test]
+    fn test_redact_secret_command_args() {
+        let redacted = redact_arguments(&json!({
+            "args": ["--api-key=sk-1", "--auth-token", "sk-2", "--port=8080", "--verbose", "x"],
+        }));
+        assert_eq!(
+            redacted["args"],
+            json!([
+                "--api-key=[redacted]",
+                "--auth-token",
+                "[redacted]",
+                "--port=8080",
+                "--verbose",
+                "x"
+            ])
+        );
+    }
+
+    #[test]
+    fn test_record_and_recent() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("audit.jsonl");
+
+        for i in 0..3 {
+            record(&path, &format!("tool{}", i), &json!({}), i != 1).unwrap();
+        }
+
+        let entries = recent(&path, 2);
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].tool, "tool1");
+        assert!(!entries[0].ok);
+        assert_eq!(entries[1].tool, "tool2");
+    }
+}
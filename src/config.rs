@@ -1,11 +1,49 @@
 use crate::credentials::CredentialManager;
-use crate::preferences::PreferenceManager;
+use crate::preferences::{
+    AgentPreferences, ConfigGenerator, GeneralSetting, McpAuth, McpServerConfig, PreferenceManager,
+};
 use anyhow::{Context, Result};
-use chrono::Local;
+use chrono::{DateTime, Local};
 use directories::ProjectDirs;
 use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::fs;
+use std::io::Write;
 use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+/// Size at which rule files start to crowd agent context windows.
+pub const DEFAULT_MAX_RULES_BYTES: u64 = 64 * 1024;
+
+static PROJECT_DIR: OnceLock<PathBuf> = OnceLock::new();
+
+/// Pins the project directory for this process, e.g. from `--project-dir`.
+pub fn set_project_dir(dir: &Path) -> Result<()> {
+    let dir = fs::canonicalize(dir)
+        .with_context(|| format!("Project directory {} not found", dir.display()))?;
+    if !dir.is_dir() {
+        anyhow::bail!("Project path {} is not a directory", dir.display());
+    }
+    let _ = PROJECT_DIR.set(dir);
+    Ok(())
+}
+
+/// The project mooagent manages: `--project-dir`, else `MOOAGENT_PROJECT_DIR`, else the
+/// current directory.
+pub fn project_dir() -> Result<PathBuf> {
+    if let Some(dir) = PROJECT_DIR.get() {
+        return Ok(dir.clone());
+    }
+    match std::env::var_os("MOOAGENT_PROJECT_DIR").filter(|v| !v.is_empty()) {
+        Some(dir) => fs::canonicalize(&dir).with_context(|| {
+            format!(
+                "MOOAGENT_PROJECT_DIR {} not found",
+                Path::new(&dir).display()
+            )
+        }),
+        None => Ok(std::env::current_dir()?),
+    }
+}
 
 pub struct ConfigPaths {
     pub project_agents: PathBuf,
@@ -16,16 +54,22 @@ pub struct ConfigPaths {
     pub project_id: String,
     pub preferences: PreferenceManager,
     pub config_dir: PathBuf,
+    pub ui_state_file: PathBuf,
+    pub sync_state_file: PathBuf,
+    pub managed_entries_file: PathBuf,
+    pub audit_log_file: PathBuf,
 }
 
-#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize)]
+#[serde(rename_all = "lowercase")]
 pub enum AgentStatus {
     Ok,
     Missing,
     Drift,
 }
 
-#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default, Serialize)]
+#[serde(rename_all = "snake_case")]
 pub enum SyncState {
     #[default]
     Ok,
@@ -86,12 +130,235 @@ pub struct AgentDefinition {
     pub target_path: PathBuf,
     pub strategy: SyncStrategy,
     pub global_file: Option<PathBuf>,
+    /// Name of the `[[agents]]` entry whose glob `path` this target was expanded from.
+    pub group: Option<String>,
+    /// Text put before / after AGENTS.md in a merge target, e.g. a banner or front matter.
+    pub header: Option<String>,
+    pub footer: Option<String>,
+}
+
+impl AgentDefinition {
+    /// The agent as `.mooagent.toml` names it, shared by every target of a glob entry.
+    pub fn base_name(&self) -> &str {
+        self.group.as_deref().unwrap_or(&self.name)
+    }
+}
+
+/// Expands an `[[agents]]` entry whose `path` is a glob into one definition per target.
+/// With a literal file name (`packages/*/AGENTS.md`) the pattern picks directories and
+/// each gets that file, existing or not; otherwise it matches existing files. Targets are
+/// named `name (dir)` relative to the project, and only the first keeps `global_file` so
+/// the global rules are written once. Only `path` as written in `.mooagent.toml` is
+/// treated as a pattern, so a project directory named e.g. `app[v2]` still works.
+pub fn expand_agent_targets(
+    project_dir: &Path,
+    path: &str,
+    definition: AgentDefinition,
+) -> Result<Vec<AgentDefinition>> {
+    if !is_glob(path) {
+        return Ok(vec![definition]);
+    }
+
+    let user_path = PathBuf::from(shellexpand::tilde(path).to_string());
+    let pattern_path = if user_path.is_absolute() {
+        user_path
+    } else {
+        PathBuf::from(glob::Pattern::escape(&project_dir.to_string_lossy())).join(user_path)
+    };
+    let pattern = pattern_path.to_string_lossy().to_string();
+
+    let file_name = pattern_path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .filter(|n| !is_glob(n));
+    let mut targets: Vec<PathBuf> = match &file_name {
+        Some(file_name) => {
+            let parent = pattern_path.parent().unwrap_or(Path::new("."));
+            glob::glob(&parent.to_string_lossy())
+                .with_context(|| format!("Invalid agent path pattern {}", path))?
+                .filter_map(|entry| entry.ok())
+                .filter(|dir| dir.is_dir())
+                .map(|dir| dir.join(file_name))
+                .collect()
+        }
+        None => glob::glob(&pattern)
+            .with_context(|| format!("Invalid agent path pattern {}", path))?
+            .filter_map(|entry| entry.ok())
+            .filter(|file| !file.is_dir())
+            .collect(),
+    };
+    targets.sort();
+    if targets.is_empty() {
+        log::warn!("Agent '{}' path {} matches nothing", definition.name, path);
+    }
+
+    Ok(targets
+        .into_iter()
+        .enumerate()
+        .map(|(i, target_path)| {
+            let location = target_path.parent().unwrap_or(&target_path);
+            let label = pathdiff::diff_paths(location, project_dir)
+                .filter(|rel| !rel.as_os_str().is_empty())
+                .unwrap_or_else(|| location.to_path_buf());
+            AgentDefinition {
+                name: format!("{} ({})", definition.name, label.display()),
+                target_path,
+                strategy: definition.strategy,
+                global_file: if i == 0 {
+                    definition.global_file.clone()
+                } else {
+                    None
+                },
+                group: Some(definition.name.clone()),
+                header: definition.header.clone(),
+                footer: definition.footer.clone(),
+            }
+        })
+        .collect())
+}
+
+/// Puts `header` and `footer` around `content`, each separated from it by a blank line.
+pub fn wrap_content(content: &str, header: Option<&str>, footer: Option<&str>) -> String {
+    let mut wrapped = String::new();
+    if let Some(header) = header {
+        wrapped.push_str(header.trim_end_matches('\n'));
+        wrapped.push_str("\n\n");
+    }
+    wrapped.push_str(content);
+    if let Some(footer) = footer {
+        if !wrapped.ends_with('\n') {
+            wrapped.push('\n');
+        }
+        wrapped.push('\n');
+        wrapped.push_str(footer.trim_end_matches('\n'));
+        wrapped.push('\n');
+    }
+    wrapped
+}
+
+/// Enabled OAuth servers whose stored token has expired but can be refreshed.
+pub fn expired_oauth_servers(
+    prefs: &AgentPreferences,
+    credentials: &CredentialManager,
+) -> Vec<String> {
+    let mut names: Vec<String> = prefs
+        .mcp_servers
+        .iter()
+        .filter(|(name, _)| !prefs.disabled_mcp_servers.contains(name))
+        .filter(|(_, config)| config.requires_oauth())
+        .filter(|(_, config)| {
+            config.url().is_some_and(|url| {
+                credentials
+                    .get_token(url)
+                    .is_some_and(|token| token.is_expired())
+                    && credentials.refresh_token(url).is_some()
+            })
+        })
+        .map(|(name, _)| name.clone())
+        .collect();
+    names.sort();
+    names
+}
+
+/// Refreshes the tokens `expired_oauth_servers` lists, so the next sync keeps their
+/// Authorization header. The network calls run without the config lock, which is only
+/// taken to store the new tokens. Failures are logged and the header is left out as
+/// before. Returns the servers that got a new token.
+pub fn refresh_oauth_tokens(config_dir: &Path, prefs: &AgentPreferences) -> Vec<String> {
+    let mut credentials = CredentialManager::new(config_dir);
+    let _ = credentials.load();
+    let names = expired_oauth_servers(prefs, &credentials);
+    if names.is_empty() {
+        return names;
+    }
+    let rt = match tokio::runtime::Runtime::new() {
+        Ok(rt) => rt,
+        Err(e) => {
+            log::warn!("Could not refresh OAuth tokens: {}", e);
+            return Vec::new();
+        }
+    };
+
+    let mut tokens = Vec::new();
+    for name in names {
+        let config = &prefs.mcp_servers[&name];
+        let (
+            Some(url),
+            Some(McpAuth::OAuth {
+                client_id,
+                client_secret,
+                auth_server_url,
+                ..
+            }),
+        ) = (config.url(), config.auth())
+        else {
+            continue;
+        };
+        let Some(refresh_token) = credentials.refresh_token(url) else {
+            continue;
+        };
+
+        match rt.block_on(crate::oauth::refresh_oauth_token(
+            url,
+            client_id,
+            client_secret.as_deref(),
+            refresh_token,
+            auth_server_url.as_deref(),
+        )) {
+            Ok(token) => tokens.push((name, url.to_string(), token)),
+            Err(e) => log::warn!("Failed to refresh OAuth token for '{}': {}", name, e),
+        }
+    }
+    if tokens.is_empty() {
+        return Vec::new();
+    }
+
+    let _lock = match crate::lock::acquire(config_dir) {
+        Ok(lock) => lock,
+        Err(e) => {
+            log::warn!("Could not store refreshed OAuth tokens: {}", e);
+            return Vec::new();
+        }
+    };
+    // Another instance may have written the store while the refreshes ran
+    let _ = credentials.load();
+    let mut refreshed = Vec::new();
+    for (name, url, token) in tokens {
+        match credentials.store_token(&url, token) {
+            Ok(()) => {
+                log::info!("Refreshed expired OAuth token for '{}'", name);
+                refreshed.push(name);
+            }
+            Err(e) => log::warn!("Failed to store OAuth token for '{}': {}", name, e),
+        }
+    }
+    refreshed
+}
+
+/// Reverses `wrap_content`: strips `header` and `footer` when the content still has them.
+pub fn unwrap_content(content: &str, header: Option<&str>, footer: Option<&str>) -> String {
+    let mut content = content;
+    if let Some(header) = header {
+        let header = format!("{}\n\n", header.trim_end_matches('\n'));
+        content = content.strip_prefix(header.as_str()).unwrap_or(content);
+    }
+    if let Some(footer) = footer {
+        let footer = format!("\n{}\n", footer.trim_end_matches('\n'));
+        if let Some(body) = content.strip_suffix(footer.as_str()) {
+            content = body;
+        }
+    }
+    content.to_string()
+}
+
+fn is_glob(path: &str) -> bool {
+    path.contains(['*', '?', '['])
 }
 
 #[derive(Debug, Deserialize)]
 struct ExternalConfig {
-    #[serde(default)]
-    agents: Vec<ExternalAgent>,
+    /// `None` when the file has no `agents` key, as opposed to an explicit `agents = []`.
+    agents: Option<Vec<ExternalAgent>>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -100,6 +367,60 @@ struct ExternalAgent {
     path: String,
     strategy: Option<SyncStrategy>,
     global_file: Option<String>,
+    header: Option<String>,
+    footer: Option<String>,
+}
+
+/// A preferences file that `sync_preferences` would create or modify.
+pub struct PreferenceFileChange {
+    pub agent: String,
+    pub path: PathBuf,
+    pub created: bool,
+    pub changed_keys: Vec<String>,
+}
+
+/// A generated preferences file that doesn't match what's on disk.
+pub struct PreferenceDrift {
+    pub agent: String,
+    pub path: PathBuf,
+    pub missing: bool,
+}
+
+impl PreferenceDrift {
+    pub fn reason(&self) -> &'static str {
+        if self.missing {
+            "missing file"
+        } else {
+            "content mismatch"
+        }
+    }
+}
+
+/// Where a target that is a symlink points, and whether anything is there.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SymlinkInfo {
+    pub points_to: PathBuf,
+    pub resolves: bool,
+}
+
+impl SymlinkInfo {
+    /// `None` when `path` is missing or not a symlink.
+    pub fn read(path: &Path) -> Option<Self> {
+        let points_to = fs::read_link(path).ok()?;
+        let resolved = path.parent().unwrap_or(Path::new(".")).join(&points_to);
+        Some(Self {
+            resolves: resolved.exists(),
+            points_to,
+        })
+    }
+
+    pub fn describe(&self) -> String {
+        if self.resolves {
+            format!("-> {}", self.points_to.display())
+        } else {
+            format!("-> {} (broken symlink)", self.points_to.display())
+        }
+    }
 }
 
 pub struct AgentInfo {
@@ -108,19 +429,67 @@ pub struct AgentInfo {
     pub status: AgentStatus,
     pub strategy: SyncStrategy,
     pub sync_status: AgentSyncStatus,
+    pub last_synced: Option<DateTime<Local>>,
+    pub link: Option<SymlinkInfo>,
+}
+
+/// The `[[agents]]` of `.mooagent.toml`, or `None` when the file or its `agents` key is
+/// missing and the built-in agents apply.
+fn read_external_agents(config_file: &Path) -> Result<Option<Vec<ExternalAgent>>> {
+    if !config_file.exists() {
+        return Ok(None);
+    }
+    let content = fs::read_to_string(config_file)?;
+    let external: ExternalConfig =
+        toml::from_str(&content).context("Failed to parse .mooagent.toml")?;
+    Ok(external.agents)
+}
+
+/// Explains why the built-in Claude/Gemini/OpenCode/Cursor agents are managed, or `None` when
+/// `.mooagent.toml` lists the agents itself (an empty `agents = []` manages none).
+pub fn default_agents_reason(config_file: &Path) -> Option<&'static str> {
+    if !config_file.exists() {
+        Some("Using default agents (Cursor if ~/.cursor exists): no .mooagent.toml in this project")
+    } else if matches!(read_external_agents(config_file), Ok(None)) {
+        Some(
+            "Using default agents (Cursor if ~/.cursor exists): .mooagent.toml has no [[agents]] (agents = [] manages none)",
+        )
+    } else {
+        None
+    }
+}
+
+impl AgentInfo {
+    pub fn has_broken_link(&self) -> bool {
+        self.link.as_ref().is_some_and(|l| !l.resolves)
+    }
 }
 
 impl ConfigPaths {
     pub fn new() -> Result<Self> {
         let project_dirs = ProjectDirs::from("", "", "mooagent")
             .context("Could not determine config directory")?;
-        let global_config_dir = project_dirs.config_dir();
-        let backup_dir = project_dirs.data_dir().join("backups");
+        Self::load(
+            &project_dir()?,
+            project_dirs.config_dir(),
+            project_dirs.data_dir(),
+        )
+    }
+
+    /// Reads `.mooagent.toml` and the preferences again, for the same project and
+    /// directories.
+    pub fn reload(&self) -> Result<Self> {
+        let cwd = self.project_agents.parent().unwrap_or(Path::new("."));
+        let data_dir = self.sync_state_file.parent().unwrap_or(Path::new("."));
+        Self::load(cwd, &self.config_dir, data_dir)
+    }
+
+    fn load(cwd: &Path, global_config_dir: &Path, data_dir: &Path) -> Result<Self> {
+        let backup_dir = data_dir.join("backups");
 
         fs::create_dir_all(global_config_dir)?;
         fs::create_dir_all(&backup_dir)?;
 
-        let cwd = std::env::current_dir()?;
         let config_file = cwd.join(".mooagent.toml");
 
         let project_id = cwd
@@ -130,32 +499,39 @@ impl ConfigPaths {
             .unwrap_or_else(|| "unknown".to_string());
 
         let mut agent_configs = Vec::new();
+        let external_agents = read_external_agents(&config_file)?;
+        let use_defaults = external_agents.is_none();
+
+        if let Some(external_agents) = external_agents {
+            for ea in external_agents {
+                let global_file = ea.global_file.map(|p| resolve_config_path(cwd, &p));
+                let strategy = ea.strategy.unwrap_or(SyncStrategy::Merge);
+                if strategy == SyncStrategy::Symlink && (ea.header.is_some() || ea.footer.is_some())
+                {
+                    log::warn!(
+                        "Agent '{}' is a symlink, so its header and footer are ignored",
+                        ea.name
+                    );
+                }
 
-        if config_file.exists() {
-            let content = fs::read_to_string(&config_file)?;
-            let external: ExternalConfig =
-                toml::from_str(&content).context("Failed to parse .mooagent.toml")?;
-
-            for ea in external.agents {
-                let global_file = ea.global_file.map(|p| {
-                    let path = PathBuf::from(shellexpand::tilde(&p).to_string());
-                    if path.is_absolute() {
-                        path
-                    } else {
-                        cwd.join(path)
-                    }
-                });
-
-                agent_configs.push(AgentDefinition {
-                    name: ea.name,
-                    target_path: cwd.join(ea.path),
-                    strategy: ea.strategy.unwrap_or(SyncStrategy::Merge),
-                    global_file,
-                });
+                agent_configs.extend(expand_agent_targets(
+                    cwd,
+                    &ea.path,
+                    AgentDefinition {
+                        name: ea.name,
+                        target_path: resolve_config_path(cwd, &ea.path),
+                        strategy,
+                        global_file,
+                        group: None,
+                        header: ea.header,
+                        footer: ea.footer,
+                    },
+                )?);
             }
         }
 
-        if agent_configs.is_empty() {
+        if use_defaults {
+            log::info!("No agents listed in .mooagent.toml; using the default agents");
             let home = dirs::home_dir().unwrap_or_else(|| PathBuf::from("."));
 
             agent_configs.push(AgentDefinition {
@@ -163,26 +539,49 @@ impl ConfigPaths {
                 target_path: cwd.join("CLAUDE.md"),
                 strategy: SyncStrategy::Merge,
                 global_file: Some(home.join(".claude/CLAUDE.md")),
+                group: None,
+                header: None,
+                footer: None,
             });
             agent_configs.push(AgentDefinition {
                 name: "Gemini".to_string(),
                 target_path: cwd.join("GEMINI.md"),
                 strategy: SyncStrategy::Merge,
                 global_file: Some(home.join(".gemini/GEMINI.md")),
+                group: None,
+                header: None,
+                footer: None,
             });
             agent_configs.push(AgentDefinition {
                 name: "OpenCode".to_string(),
                 target_path: cwd.join(".opencode").join("rules.md"),
                 strategy: SyncStrategy::Merge,
                 global_file: Some(home.join(".config/opencode/AGENTS.md")),
+                group: None,
+                header: None,
+                footer: None,
             });
+            // Only when Cursor is installed, as for its MCP config. It loads rule files
+            // with `alwaysApply` frontmatter into every chat.
+            if home.join(".cursor").is_dir() {
+                agent_configs.push(AgentDefinition {
+                    name: "Cursor".to_string(),
+                    target_path: cwd.join(".cursor").join("rules").join("mooagent.mdc"),
+                    strategy: SyncStrategy::Merge,
+                    // Cursor keeps user rules in its settings rather than in a file
+                    global_file: None,
+                    group: None,
+                    header: Some("---\nalwaysApply: true\n---".to_string()),
+                    footer: None,
+                });
+            }
         }
 
         let mut preferences = PreferenceManager::new(global_config_dir);
         let _ = preferences.load_global();
         let _ = preferences.load_project(&config_file);
 
-        Ok(Self {
+        let paths = Self {
             project_agents: cwd.join("AGENTS.md"),
             config_file,
             agent_configs,
@@ -191,7 +590,49 @@ impl ConfigPaths {
             project_id,
             preferences,
             config_dir: global_config_dir.to_path_buf(),
-        })
+            ui_state_file: data_dir.join("ui_state.json"),
+            sync_state_file: data_dir.join("sync_state.json"),
+            managed_entries_file: data_dir.join("managed_entries.json"),
+            audit_log_file: data_dir.join("audit.jsonl"),
+        };
+        for conflict in paths.target_conflicts() {
+            log::warn!("{}", conflict);
+        }
+        Ok(paths)
+    }
+
+    /// Warnings for agents sharing a target file. Each sync would overwrite the other's
+    /// result, e.g. a merge replaced by a symlink and back, so the file never settles.
+    pub fn target_conflicts(&self) -> Vec<String> {
+        let mut by_target: Vec<(&Path, Vec<&AgentDefinition>)> = Vec::new();
+        for agent in &self.agent_configs {
+            match by_target
+                .iter_mut()
+                .find(|(target, _)| *target == agent.target_path)
+            {
+                Some((_, agents)) => agents.push(agent),
+                None => by_target.push((&agent.target_path, vec![agent])),
+            }
+        }
+
+        by_target
+            .into_iter()
+            .filter(|(_, agents)| agents.len() > 1)
+            .map(|(target, agents)| {
+                let names: Vec<String> = agents
+                    .iter()
+                    .map(|a| {
+                        let strategy = format!("{:?}", a.strategy).to_lowercase();
+                        format!("{} ({})", a.name, strategy)
+                    })
+                    .collect();
+                format!(
+                    "Agents {} share the target {}; give each its own path in .mooagent.toml",
+                    names.join(", "),
+                    target.display()
+                )
+            })
+            .collect()
     }
 
     pub fn ensure_files_exist(&self) -> Result<()> {
@@ -214,6 +655,7 @@ impl ConfigPaths {
 
     pub fn sync_global_rules(&self) -> Result<()> {
         log::info!("Syncing global rules to all agent files");
+        let _lock = crate::lock::acquire(&self.config_dir)?;
 
         if !self.global_rules_primary.exists() {
             anyhow::bail!(
@@ -233,7 +675,7 @@ impl ConfigPaths {
 
                 if needs_sync {
                     self.backup_if_needed(global_file)?;
-                    fs::write(global_file, &primary_content)?;
+                    write_atomic(global_file, &primary_content)?;
                     log::info!("Synced global rules to {}", global_file.display());
                 }
             }
@@ -270,13 +712,24 @@ impl ConfigPaths {
             .unwrap_or_else(|_| "Error reading project rules".to_string())
     }
 
-    pub fn get_merged_content(&self, _agent_def: &AgentDefinition) -> String {
-        self.read_project_content()
+    /// What the agent's target should contain. Merge targets wrap AGENTS.md in the
+    /// agent's `header` and `footer`; a symlink can only mirror AGENTS.md as it is.
+    pub fn get_merged_content(&self, agent_def: &AgentDefinition) -> String {
+        let content = self.read_project_content();
+        if agent_def.strategy == SyncStrategy::Symlink {
+            return content;
+        }
+        wrap_content(
+            &content,
+            agent_def.header.as_deref(),
+            agent_def.footer.as_deref(),
+        )
     }
 
     pub fn get_agents(&self) -> Vec<AgentInfo> {
         let global_rules_drifted = self.check_global_rules_drift();
-        let prefs_drifted = self.check_preference_drift();
+        let prefs_drifted = self.preference_drift_details();
+        let sync_times = self.load_sync_times();
 
         self.agent_configs
             .iter()
@@ -299,7 +752,9 @@ impl ConfigPaths {
                     SyncState::Ok
                 };
 
-                let prefs_state = if prefs_drifted {
+                let agent_prefs_drifted = prefs_drifted.iter().any(|d| d.agent == def.base_name());
+
+                let prefs_state = if agent_prefs_drifted {
                     SyncState::Drift
                 } else {
                     SyncState::Ok
@@ -307,7 +762,7 @@ impl ConfigPaths {
 
                 let mcp_state = if self.preferences.global_prefs.mcp_servers.is_empty() {
                     SyncState::NotApplicable
-                } else if prefs_drifted {
+                } else if agent_prefs_drifted {
                     SyncState::Drift
                 } else {
                     SyncState::Ok
@@ -330,56 +785,81 @@ impl ConfigPaths {
                     status: sync_status.overall(),
                     strategy: def.strategy,
                     sync_status,
+                    last_synced: sync_times.get(&def.name).copied(),
+                    link: SymlinkInfo::read(&def.target_path),
                 }
             })
             .collect()
     }
 
+    /// Last sync time of each agent in this project, keyed by agent name.
+    pub fn load_sync_times(&self) -> HashMap<String, DateTime<Local>> {
+        load_state::<SyncTimes>(&self.sync_state_file)
+            .remove(&self.project_id)
+            .unwrap_or_default()
+    }
+
+    /// Failures are logged rather than returned so a sync never fails over bookkeeping.
+    fn record_synced(&self, agent_names: &[&str]) {
+        if agent_names.is_empty() {
+            return;
+        }
+
+        let mut state = load_state::<SyncTimes>(&self.sync_state_file);
+        let now = Local::now();
+        let times = state.entry(self.project_id.clone()).or_default();
+        for name in agent_names {
+            times.insert(name.to_string(), now);
+        }
+
+        let result = serde_json::to_string_pretty(&state)
+            .map_err(anyhow::Error::from)
+            .and_then(|content| write_atomic(&self.sync_state_file, &content));
+        if let Err(e) = result {
+            log::warn!("Failed to record sync time: {}", e);
+        }
+    }
+
+    /// Agents are synced independently: one unwritable target (e.g. a read-only
+    /// mount) is reported in the summary instead of aborting the remaining agents.
     pub fn sync(&self) -> Result<String> {
+        let _lock = crate::lock::acquire(&self.config_dir)?;
         self.ensure_files_exist()?;
         let agents = self.get_agents();
-        let mut synced_count = 0;
+        let mut synced = Vec::new();
+        let mut failed = Vec::new();
 
         for (idx, agent) in agents.iter().enumerate() {
             if agent.status != AgentStatus::Ok {
-                let agent_def = &self.agent_configs[idx];
-                let merged_content = self.get_merged_content(agent_def);
-
-                self.backup_if_needed(&agent.target_path)?;
-
-                if let Some(parent) = agent.target_path.parent() {
-                    fs::create_dir_all(parent)?;
-                }
-
-                match agent.strategy {
-                    SyncStrategy::Merge => {
-                        fs::write(&agent.target_path, &merged_content)?;
-                    }
-                    SyncStrategy::Symlink => {
-                        let target_dir = agent.target_path.parent().unwrap_or(Path::new("."));
-                        let relative_source =
-                            pathdiff::diff_paths(&self.project_agents, target_dir)
-                                .unwrap_or_else(|| self.project_agents.clone());
-
-                        #[cfg(unix)]
-                        std::os::unix::fs::symlink(&relative_source, &agent.target_path)?;
-
-                        #[cfg(windows)]
-                        std::os::windows::fs::symlink_file(&relative_source, &agent.target_path)?;
+                let merged_content = self.get_merged_content(&self.agent_configs[idx]);
+                match self.write_agent_target(agent, &merged_content) {
+                    Ok(()) => synced.push(agent.name.as_str()),
+                    Err(e) => {
+                        log::warn!("Failed to sync {}: {:#}", agent.name, e);
+                        failed.push(format!("{} ({:#})", agent.name, e));
                     }
                 }
-                synced_count += 1;
             }
         }
 
-        if synced_count == 0 {
+        self.record_synced(&synced);
+
+        if !failed.is_empty() {
+            Ok(format!(
+                "Synced {} of {} agent(s); failed: {}",
+                synced.len(),
+                synced.len() + failed.len(),
+                failed.join(", ")
+            ))
+        } else if synced.is_empty() {
             Ok("All agents already in sync.".to_string())
         } else {
-            Ok(format!("Successfully synced {} agent(s).", synced_count))
+            Ok(format!("Successfully synced {} agent(s).", synced.len()))
         }
     }
 
     pub fn sync_agent(&self, agent_index: usize) -> Result<String> {
+        let _lock = crate::lock::acquire(&self.config_dir)?;
         self.ensure_files_exist()?;
 
         if agent_index >= self.agent_configs.len() {
@@ -395,6 +875,15 @@ impl ConfigPaths {
             return Ok(format!("{} already in sync", agent.name));
         }
 
+        self.write_agent_target(agent, &merged_content)
+            .with_context(|| format!("Failed to sync {}", agent.name))?;
+
+        self.record_synced(&[&agent.name]);
+
+        Ok(format!("Successfully synced {}", agent.name))
+    }
+
+    fn write_agent_target(&self, agent: &AgentInfo, merged_content: &str) -> Result<()> {
         self.backup_if_needed(&agent.target_path)?;
 
         if let Some(parent) = agent.target_path.parent() {
@@ -403,13 +892,19 @@ impl ConfigPaths {
 
         match agent.strategy {
             SyncStrategy::Merge => {
-                fs::write(&agent.target_path, &merged_content)?;
+                write_atomic(&agent.target_path, merged_content)?;
+                self.record_synced_content(&agent.name, merged_content);
             }
             SyncStrategy::Symlink => {
                 let target_dir = agent.target_path.parent().unwrap_or(Path::new("."));
                 let relative_source = pathdiff::diff_paths(&self.project_agents, target_dir)
                     .unwrap_or_else(|| self.project_agents.clone());
 
+                // Replace whatever is there, including a dangling link
+                if fs::symlink_metadata(&agent.target_path).is_ok() {
+                    fs::remove_file(&agent.target_path)?;
+                }
+
                 #[cfg(unix)]
                 std::os::unix::fs::symlink(&relative_source, &agent.target_path)?;
 
@@ -418,17 +913,13 @@ impl ConfigPaths {
             }
         }
 
-        Ok(format!("Successfully synced {}", agent.name))
+        Ok(())
     }
 
-    pub fn sync_preferences(&self) -> Result<String> {
-        let merged_prefs = self.preferences.get_merged();
+    fn preference_generators(&self) -> Result<Vec<Box<dyn ConfigGenerator>>> {
         let home = dirs::home_dir().context("Could not determine home directory")?;
 
-        let mut credentials = CredentialManager::new(&self.config_dir);
-        let _ = credentials.load();
-
-        let generators: Vec<Box<dyn crate::preferences::ConfigGenerator>> = vec![
+        let mut generators: Vec<Box<dyn ConfigGenerator>> = vec![
             Box::new(crate::preferences::ClaudeConfigGenerator {
                 config_dir: home.join(".claude"),
                 user_config_path: home.join(".claude.json"),
@@ -441,10 +932,188 @@ impl ConfigPaths {
             }),
         ];
 
+        // Opt-in: only manage Continue's and Cursor's configs once they have been set up
+        let continue_dir = home.join(".continue");
+        if continue_dir.is_dir() {
+            generators.push(Box::new(crate::preferences::ContinueConfigGenerator {
+                config_dir: continue_dir,
+            }));
+        }
+        let cursor_dir = home.join(".cursor");
+        if cursor_dir.is_dir() {
+            generators.push(Box::new(crate::preferences::CursorConfigGenerator {
+                config_dir: cursor_dir,
+            }));
+        }
+
+        Ok(generators)
+    }
+
+    /// What each synced agent would get for the named global MCP server, secrets redacted.
+    pub fn preview_mcp_server(
+        &self,
+        name: &str,
+    ) -> Vec<(String, Result<Option<serde_json::Value>>)> {
+        let Some(config) = self.preferences.global_prefs.mcp_servers.get(name) else {
+            return Vec::new();
+        };
+        let config = config.redacted();
+        self.preference_generators()
+            .unwrap_or_default()
+            .iter()
+            .map(|g| (g.agent_name().to_string(), g.preview_server(name, &config)))
+            .collect()
+    }
+
+    /// Agents whose preference files mooagent currently syncs.
+    pub fn preference_agent_names(&self) -> Vec<String> {
+        self.preference_generators()
+            .unwrap_or_default()
+            .iter()
+            .map(|g| g.agent_name().to_string())
+            .collect()
+    }
+
+    /// Agents whose generated settings honor `setting`, among those mooagent currently syncs.
+    pub fn general_setting_agents(&self, setting: GeneralSetting) -> Vec<String> {
+        self.preference_generators()
+            .unwrap_or_default()
+            .iter()
+            .filter(|g| g.supported_general_settings().contains(&setting))
+            .map(|g| g.agent_name().to_string())
+            .collect()
+    }
+
+    pub fn has_mooagent_mcp(&self) -> bool {
+        self.preferences
+            .global_prefs
+            .mcp_servers
+            .contains_key("mooagent")
+    }
+
+    /// Registers this mooagent binary as the `mooagent` MCP server in global preferences.
+    /// Returns `None` if it is already configured.
+    pub fn add_mooagent_mcp(&mut self) -> Result<Option<PathBuf>> {
+        if self.has_mooagent_mcp() {
+            return Ok(None);
+        }
+
+        let mooagent_path = resolve_mooagent_binary();
+        if !mooagent_path.exists() {
+            anyhow::bail!(
+                "mooagent binary not found. Run 'just install' first to install to ~/.local/bin/"
+            );
+        }
+
+        let existing = self.preferences.global_prefs.mcp_servers.get("mooagent");
+        let description = existing.and_then(|c| c.description()).map(String::from);
+        let tags = existing.map(|c| c.tags().to_vec()).unwrap_or_default();
+        self.preferences.global_prefs.mcp_servers.insert(
+            "mooagent".to_string(),
+            McpServerConfig::Stdio {
+                command: mooagent_path.to_string_lossy().to_string(),
+                args: vec!["--mcp".to_string()],
+                env: std::collections::HashMap::new(),
+                disabled_tools: Vec::new(),
+                auto_allow: false,
+                description,
+                tags,
+            },
+        );
+        self.preferences.save_global()?;
+
+        Ok(Some(mooagent_path))
+    }
+
+    /// Warnings for GLOBAL_RULES.md and AGENTS.md files above `general.max_rules_bytes`.
+    pub fn oversized_rules(&self) -> Vec<String> {
+        let limit = self
+            .preferences
+            .get_merged()
+            .general
+            .max_rules_bytes
+            .unwrap_or(DEFAULT_MAX_RULES_BYTES);
+        if limit == 0 {
+            return Vec::new();
+        }
+
+        [
+            ("GLOBAL_RULES.md", &self.global_rules_primary),
+            ("AGENTS.md", &self.project_agents),
+        ]
+        .into_iter()
+        .filter_map(|(label, path)| {
+            let size = fs::metadata(path).ok()?.len();
+            (size > limit).then(|| {
+                format!(
+                    "{} is {:.1} KiB, over the {:.1} KiB limit",
+                    label,
+                    size as f64 / 1024.0,
+                    limit as f64 / 1024.0
+                )
+            })
+        })
+        .collect()
+    }
+
+    /// Returns the configured `mooagent` MCP command if it no longer resolves to a binary.
+    pub fn stale_mooagent_command(&self) -> Option<String> {
+        match self.preferences.global_prefs.mcp_servers.get("mooagent")? {
+            McpServerConfig::Stdio { command, .. } if !command_exists(command) => {
+                Some(command.clone())
+            }
+            _ => None,
+        }
+    }
+
+    /// Points the `mooagent` MCP server at the currently running binary.
+    pub fn repoint_mooagent_mcp(&mut self) -> Result<PathBuf> {
+        let current_exe = std::env::current_exe().context("Could not determine current binary")?;
+
+        let Some(McpServerConfig::Stdio { command, .. }) = self
+            .preferences
+            .global_prefs
+            .mcp_servers
+            .get_mut("mooagent")
+        else {
+            anyhow::bail!("No local mooagent MCP server is configured. Bootstrap it first.");
+        };
+        *command = current_exe.to_string_lossy().to_string();
+        self.preferences.save_global()?;
+
+        Ok(current_exe)
+    }
+
+    pub fn sync_preferences(&mut self) -> Result<String> {
+        let _lock = crate::lock::acquire(&self.config_dir)?;
+
+        let mut credentials = CredentialManager::new(&self.config_dir);
+        let _ = credentials.load();
+
+        let (global_migrated, project_migrated) = self
+            .preferences
+            .migrate_inline_bearer_tokens(&mut credentials)?;
+        if global_migrated {
+            self.preferences.save_global()?;
+        }
+        if project_migrated {
+            self.preferences.save_project(&self.config_file)?;
+        }
+
+        let merged_prefs = self.preferences.get_merged();
+        let generators = self.preference_generators()?;
+        let previous_managed: ManagedEntries = load_state(&self.managed_entries_file);
+        let mut managed = previous_managed.clone();
+
         let mut synced_count = 0;
 
         for generator in generators {
-            let files = generator.generate(&merged_prefs, Some(&credentials))?;
+            let files = self.generate_reconciled(
+                generator.as_ref(),
+                &merged_prefs,
+                &credentials,
+                &mut managed,
+            )?;
             for (path, content) in files {
                 let needs_sync = if path.exists() {
                     fs::read_to_string(&path).unwrap_or_default() != content
@@ -459,7 +1128,7 @@ impl ConfigPaths {
                         fs::create_dir_all(parent)?;
                     }
 
-                    fs::write(&path, &content)?;
+                    write_atomic(&path, &content)?;
                     synced_count += 1;
                     log::info!(
                         "[{}] Synced config to {}",
@@ -470,6 +1139,13 @@ impl ConfigPaths {
             }
         }
 
+        if managed != previous_managed {
+            write_atomic(
+                &self.managed_entries_file,
+                serde_json::to_string_pretty(&managed)?,
+            )?;
+        }
+
         if synced_count == 0 {
             Ok("Preferences already in sync.".to_string())
         } else {
@@ -477,68 +1153,183 @@ impl ConfigPaths {
         }
     }
 
-    pub fn check_preference_drift(&self) -> bool {
+    /// Refreshes expired OAuth tokens; see `refresh_oauth_tokens`. `mooagent sync` and the
+    /// MCP `sync` tool run it before syncing preferences, the TUI in the background.
+    pub fn refresh_expired_oauth_tokens(&self) -> Vec<String> {
+        refresh_oauth_tokens(&self.config_dir, &self.preferences.get_merged())
+    }
+
+    /// Generates `generator`'s files, removing entries mooagent wrote on an earlier sync
+    /// for servers that are no longer configured. Entries the user added directly are
+    /// never in `managed`, so they are left alone. `managed` is updated to what is
+    /// written now.
+    pub fn generate_reconciled(
+        &self,
+        generator: &dyn ConfigGenerator,
+        prefs: &AgentPreferences,
+        credentials: &CredentialManager,
+        managed: &mut ManagedEntries,
+    ) -> Result<Vec<(PathBuf, String)>> {
+        let mut files = generator.generate(prefs, Some(credentials))?;
+
+        for (path, object_key) in generator.managed_objects() {
+            let Some((_, content)) = files.iter_mut().find(|(p, _)| *p == path) else {
+                continue;
+            };
+            let mut value: serde_json::Value = serde_json::from_str(content)?;
+            let Some(entries) = value.get_mut(object_key).and_then(|v| v.as_object_mut()) else {
+                continue;
+            };
+
+            let file_entries = managed.entry(path.display().to_string()).or_default();
+            let previous = file_entries.remove(object_key).unwrap_or_default();
+            let current: BTreeSet<String> = prefs
+                .mcp_servers
+                .iter()
+                .filter(|(name, config)| !config.is_unknown() && entries.contains_key(*name))
+                .map(|(name, _)| name.clone())
+                .collect();
+
+            let mut pruned = false;
+            for name in previous.difference(&current) {
+                if entries.remove(name).is_some() {
+                    log::info!(
+                        "[{}] Removing '{}' from {}, no longer configured in mooagent",
+                        generator.agent_name(),
+                        name,
+                        path.display()
+                    );
+                    pruned = true;
+                }
+            }
+            if pruned {
+                *content = serde_json::to_string_pretty(&value)?;
+            }
+
+            file_entries.insert(object_key.to_string(), current);
+        }
+
+        Ok(files)
+    }
+
+    pub fn preference_drift_details(&self) -> Vec<PreferenceDrift> {
         let merged_prefs = self.preferences.get_merged();
-        let home = match dirs::home_dir() {
-            Some(h) => h,
-            None => return false,
-        };
 
         let mut credentials = CredentialManager::new(&self.config_dir);
         let _ = credentials.load();
 
-        let generators: Vec<Box<dyn crate::preferences::ConfigGenerator>> = vec![
-            Box::new(crate::preferences::ClaudeConfigGenerator {
-                config_dir: home.join(".claude"),
-                user_config_path: home.join(".claude.json"),
-            }),
-            Box::new(crate::preferences::GeminiConfigGenerator {
-                config_dir: home.join(".gemini"),
-            }),
-            Box::new(crate::preferences::OpenCodeConfigGenerator {
-                config_dir: home.join(".config/opencode"),
-            }),
-        ];
+        let Ok(generators) = self.preference_generators() else {
+            return Vec::new();
+        };
+
+        let mut managed: ManagedEntries = load_state(&self.managed_entries_file);
 
+        let mut drifted = Vec::new();
         for generator in generators {
-            if let Ok(files) = generator.generate(&merged_prefs, Some(&credentials)) {
+            if let Ok(files) = self.generate_reconciled(
+                generator.as_ref(),
+                &merged_prefs,
+                &credentials,
+                &mut managed,
+            ) {
                 for (path, content) in files {
-                    if path.exists() {
-                        let existing = fs::read_to_string(&path).unwrap_or_default();
-                        if !json_equal(&existing, &content) {
-                            return true;
-                        }
-                    } else {
-                        return true;
+                    let missing = !path.exists();
+                    if missing
+                        || !json_equal(&fs::read_to_string(&path).unwrap_or_default(), &content)
+                    {
+                        drifted.push(PreferenceDrift {
+                            agent: generator.agent_name().to_string(),
+                            path,
+                            missing,
+                        });
                     }
                 }
             }
         }
-        false
+        drifted
+    }
+
+    pub fn preview_preferences(&self) -> Result<Vec<PreferenceFileChange>> {
+        let merged_prefs = self.preferences.get_merged();
+
+        let mut credentials = CredentialManager::new(&self.config_dir);
+        let _ = credentials.load();
+
+        let mut managed: ManagedEntries = load_state(&self.managed_entries_file);
+        let mut changes = Vec::new();
+
+        for generator in self.preference_generators()? {
+            for (path, content) in self.generate_reconciled(
+                generator.as_ref(),
+                &merged_prefs,
+                &credentials,
+                &mut managed,
+            )? {
+                let change = if path.exists() {
+                    let existing = fs::read_to_string(&path).unwrap_or_default();
+                    if json_equal(&existing, &content) {
+                        continue;
+                    }
+                    PreferenceFileChange {
+                        agent: generator.agent_name().to_string(),
+                        path,
+                        created: false,
+                        changed_keys: json_changed_keys(&existing, &content),
+                    }
+                } else {
+                    PreferenceFileChange {
+                        agent: generator.agent_name().to_string(),
+                        path,
+                        created: true,
+                        changed_keys: json_changed_keys("{}", &content),
+                    }
+                };
+                changes.push(change);
+            }
+        }
+
+        Ok(changes)
+    }
+
+    /// Backup file names for `target_path` start with this prefix, followed by a timestamp.
+    fn backup_prefix(&self, target_path: &Path) -> String {
+        let filename = target_path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .unwrap_or("unknown");
+
+        let is_global = target_path.starts_with(dirs::home_dir().unwrap_or_default())
+            && target_path != self.project_agents;
+
+        if is_global {
+            format!("global_{}", filename)
+        } else {
+            format!("{}_{}", self.project_id, filename)
+        }
     }
 
-    fn backup_if_needed(&self, target_path: &Path) -> Result<()> {
+    pub fn backup_if_needed(&self, target_path: &Path) -> Result<Option<PathBuf>> {
         if target_path.exists() {
             let timestamp = Local::now().format("%Y%m%d_%H%M%S");
-            let filename = target_path
-                .file_name()
-                .and_then(|n| n.to_str())
-                .unwrap_or("unknown");
-
-            let is_global = target_path.starts_with(dirs::home_dir().unwrap_or_default())
-                && target_path != self.project_agents;
-
-            let backup_name = if is_global {
-                format!("global_{}.{}", filename, timestamp)
-            } else {
-                format!("{}_{}.{}", self.project_id, filename, timestamp)
-            };
+            let backup_name = format!("{}.{}", self.backup_prefix(target_path), timestamp);
 
             let backup = self.backup_dir.join(backup_name);
+            ensure_dir(&self.backup_dir)?;
             fs::copy(target_path, &backup)?;
             log::info!("Created backup: {}", backup.display());
+            return Ok(Some(backup));
         }
-        Ok(())
+        Ok(None)
+    }
+
+    /// Snapshots the global and project rule files without syncing.
+    /// Returns the backups created; files that don't exist yet are skipped.
+    pub fn backup_now(&self) -> Result<Vec<PathBuf>> {
+        let mut created = Vec::new();
+        for path in [&self.global_rules_primary, &self.project_agents] {
+            created.extend(self.backup_if_needed(path)?);
+        }
+        Ok(created)
     }
 
     pub fn list_backups(&self, agent_index: usize) -> Vec<PathBuf> {
@@ -547,23 +1338,12 @@ impl ConfigPaths {
             return Vec::new();
         }
 
-        let agent = &agents[agent_index];
-        let filename = agent
-            .target_path
-            .file_name()
-            .and_then(|n| n.to_str())
-            .unwrap_or("");
-
-        let is_global = agent
-            .target_path
-            .starts_with(dirs::home_dir().unwrap_or_default())
-            && agent.target_path != self.project_agents;
+        self.list_backups_for(&agents[agent_index].target_path)
+    }
 
-        let prefix = if is_global {
-            format!("global_{}", filename)
-        } else {
-            format!("{}_{}", self.project_id, filename)
-        };
+    /// Backups of `target_path`, newest first.
+    pub fn list_backups_for(&self, target_path: &Path) -> Vec<PathBuf> {
+        let prefix = format!("{}.", self.backup_prefix(target_path));
 
         let mut backups = Vec::new();
 
@@ -582,12 +1362,12 @@ impl ConfigPaths {
         backups
     }
 
-    #[allow(dead_code)]
     pub fn restore_backup(&self, backup_path: &Path, target_path: &Path) -> Result<()> {
         if !backup_path.exists() {
             anyhow::bail!("Backup file does not exist");
         }
 
+        let _lock = crate::lock::acquire(&self.config_dir)?;
         self.backup_if_needed(target_path)?;
 
         fs::copy(backup_path, target_path)?;
@@ -596,6 +1376,82 @@ impl ConfigPaths {
         Ok(())
     }
 
+    /// Remembers a hash of what sync wrote to a merge target, so later changes to the
+    /// file itself can be told apart from changes to AGENTS.md.
+    fn record_synced_content(&self, agent_name: &str, content: &str) {
+        let path = self.synced_hashes_file();
+        let mut state = load_state::<SyncedHashes>(&path);
+        state
+            .entry(self.project_id.clone())
+            .or_default()
+            .insert(agent_name.to_string(), content_hash(content));
+
+        let result = serde_json::to_string_pretty(&state)
+            .map_err(anyhow::Error::from)
+            .and_then(|content| write_atomic(&path, &content));
+        if let Err(e) = result {
+            log::warn!("Failed to record synced content: {}", e);
+        }
+    }
+
+    /// Kept next to the sync times; one hash per project and agent.
+    fn synced_hashes_file(&self) -> PathBuf {
+        self.sync_state_file.with_file_name("synced_hashes.json")
+    }
+
+    /// A unified diff from the expected content to the agent's file when the file was
+    /// edited by hand since mooagent last synced it, i.e. syncing would lose those edits.
+    /// Agents never synced by this version have no record and are not flagged.
+    pub fn hand_edit_diff(&self, agent_index: usize) -> Option<String> {
+        let agent_def = self.agent_configs.get(agent_index)?;
+        if agent_def.strategy != SyncStrategy::Merge || agent_def.target_path.is_symlink() {
+            return None;
+        }
+        let actual = fs::read_to_string(&agent_def.target_path).ok()?;
+        let recorded = load_state::<SyncedHashes>(&self.synced_hashes_file())
+            .remove(&self.project_id)?
+            .remove(&agent_def.name)?;
+        let expected = self.get_merged_content(agent_def);
+        if actual == expected || content_hash(&actual) == recorded {
+            return None;
+        }
+
+        Some(
+            similar::TextDiff::from_lines(&expected, &actual)
+                .unified_diff()
+                .header("synced", &agent_def.target_path.display().to_string())
+                .to_string(),
+        )
+    }
+
+    /// Copies a hand-edited agent file back into AGENTS.md, minus the agent's header and
+    /// footer, so the edits survive the next sync and reach the other agents.
+    pub fn pull_agent_edits(&self, agent_index: usize) -> Result<String> {
+        let _lock = crate::lock::acquire(&self.config_dir)?;
+        let agent_def = self
+            .agent_configs
+            .get(agent_index)
+            .context("Invalid agent index")?;
+        let actual = fs::read_to_string(&agent_def.target_path)
+            .with_context(|| format!("Failed to read {}", agent_def.target_path.display()))?;
+        let content = unwrap_content(
+            &actual,
+            agent_def.header.as_deref(),
+            agent_def.footer.as_deref(),
+        );
+
+        self.backup_if_needed(&self.project_agents)?;
+        write_atomic(&self.project_agents, content)?;
+        self.record_synced_content(&agent_def.name, &actual);
+        self.record_synced(&[&agent_def.name]);
+        Ok(format!(
+            "Pulled edits from {} into AGENTS.md; sync to update the other agents",
+            agent_def.name
+        ))
+    }
+
+    /// Unified diff from the expected merged content to the agent file as it is on disk,
+    /// or a note saying why the file can't be diffed (unreadable or not UTF-8).
     pub fn get_diff(&self, agent_index: usize) -> Option<String> {
         if agent_index >= self.agent_configs.len() {
             return None;
@@ -611,12 +1467,23 @@ impl ConfigPaths {
             return None;
         }
 
-        let actual_content = fs::read_to_string(&agent.target_path).ok()?;
+        let actual_content = match fs::read_to_string(&agent.target_path) {
+            Ok(content) => content,
+            Err(e) => {
+                return Some(format!(
+                    "Cannot diff {}: {}",
+                    agent.target_path.display(),
+                    e
+                ));
+            }
+        };
 
-        Some(format!(
-            "Expected:\n{}\n\nActual:\n{}",
-            expected_content, actual_content
-        ))
+        Some(
+            similar::TextDiff::from_lines(&expected_content, &actual_content)
+                .unified_diff()
+                .header("expected", &agent.target_path.display().to_string())
+                .to_string(),
+        )
     }
 
     #[allow(dead_code)]
@@ -644,6 +1511,153 @@ impl ConfigPaths {
     }
 }
 
+/// Sync times keyed by project id, then agent name.
+type SyncTimes = HashMap<String, HashMap<String, DateTime<Local>>>;
+
+/// Hash of the content last synced to each merge target, keyed by project id, then agent
+/// name.
+type SyncedHashes = HashMap<String, HashMap<String, String>>;
+
+fn content_hash(content: &str) -> String {
+    use sha2::{Digest, Sha256};
+    format!("{:x}", Sha256::digest(content.as_bytes()))
+}
+
+/// Names of the entries mooagent wrote, keyed by agent file path, then object key.
+type ManagedEntries = BTreeMap<String, BTreeMap<String, BTreeSet<String>>>;
+
+/// Missing or unreadable state files read as empty.
+fn load_state<T: serde::de::DeserializeOwned + Default>(path: &Path) -> T {
+    fs::read_to_string(path)
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+/// Creates `dir` if it is missing, e.g. after the config directory was deleted while the
+/// TUI was running.
+pub fn ensure_dir(dir: &Path) -> Result<()> {
+    fs::create_dir_all(dir).with_context(|| format!("Could not create directory {}", dir.display()))
+}
+
+/// Writes `contents` to `path` by way of a temp file in the same directory that is
+/// renamed into place, so a crash mid-write never leaves a truncated file behind.
+pub fn write_atomic(path: &Path, contents: impl AsRef<[u8]>) -> Result<()> {
+    write_atomic_with(path, |file| file.write_all(contents.as_ref()))
+}
+
+pub fn write_atomic_with<F>(path: &Path, write: F) -> Result<()>
+where
+    F: FnOnce(&mut fs::File) -> std::io::Result<()>,
+{
+    // Write through symlinks (e.g. dotfile-managed configs) instead of replacing them
+    let target = if path.is_symlink() {
+        fs::canonicalize(path)?
+    } else {
+        path.to_path_buf()
+    };
+
+    let dir = target
+        .parent()
+        .filter(|p| !p.as_os_str().is_empty())
+        .unwrap_or(Path::new("."));
+    ensure_dir(dir)?;
+
+    let mut tmp = tempfile::NamedTempFile::new_in(dir)
+        .with_context(|| format!("Failed to create temp file in {}", dir.display()))?;
+    write(tmp.as_file_mut()).with_context(|| format!("Failed to write {}", target.display()))?;
+    tmp.as_file().sync_all()?;
+
+    if let Ok(meta) = fs::metadata(&target) {
+        fs::set_permissions(tmp.path(), meta.permissions())?;
+    }
+
+    tmp.persist(&target)
+        .with_context(|| format!("Failed to replace {}", target.display()))?;
+    Ok(())
+}
+
+/// Prefers the installed `~/.local/bin/mooagent`, falling back to the running binary.
+fn resolve_mooagent_binary() -> PathBuf {
+    dirs::home_dir()
+        .map(|h| h.join(".local/bin/mooagent"))
+        .filter(|p| p.exists())
+        .unwrap_or_else(|| std::env::current_exe().unwrap_or_default())
+}
+
+/// Checks a command path directly, or looks it up in `PATH` if it is a bare name.
+pub fn command_exists(command: &str) -> bool {
+    let path = Path::new(command);
+    if path.components().count() > 1 {
+        return path.exists();
+    }
+
+    std::env::var_os("PATH")
+        .map(|paths| std::env::split_paths(&paths).any(|dir| dir.join(command).is_file()))
+        .unwrap_or(false)
+}
+
+/// Lists the dotted JSON keys (up to two levels deep) whose values differ between
+/// `old` and `new`. Non-JSON content is reported as a single `<file>` entry.
+pub fn json_changed_keys(old: &str, new: &str) -> Vec<String> {
+    let parsed_old: Result<serde_json::Value, _> = serde_json::from_str(old);
+    let parsed_new: Result<serde_json::Value, _> = serde_json::from_str(new);
+
+    let (Ok(old_val), Ok(new_val)) = (parsed_old, parsed_new) else {
+        return if old == new {
+            Vec::new()
+        } else {
+            vec!["<file>".to_string()]
+        };
+    };
+
+    let mut keys = Vec::new();
+    collect_changed_keys(&old_val, &new_val, "", 2, &mut keys);
+    keys.sort();
+    keys
+}
+
+fn collect_changed_keys(
+    old: &serde_json::Value,
+    new: &serde_json::Value,
+    prefix: &str,
+    depth: usize,
+    keys: &mut Vec<String>,
+) {
+    if old == new {
+        return;
+    }
+
+    match (old, new) {
+        (serde_json::Value::Object(old_map), serde_json::Value::Object(new_map)) if depth > 0 => {
+            let mut names: Vec<&String> = old_map.keys().chain(new_map.keys()).collect();
+            names.sort();
+            names.dedup();
+
+            for name in names {
+                let path = if prefix.is_empty() {
+                    name.clone()
+                } else {
+                    format!("{}.{}", prefix, name)
+                };
+                let null = serde_json::Value::Null;
+                collect_changed_keys(
+                    old_map.get(name).unwrap_or(&null),
+                    new_map.get(name).unwrap_or(&null),
+                    &path,
+                    depth - 1,
+                    keys,
+                );
+            }
+        }
+        _ => keys.push(if prefix.is_empty() {
+            "<root>".to_string()
+        } else {
+            prefix.to_string()
+        }),
+    }
+}
+
 fn json_equal(a: &str, b: &str) -> bool {
     let parsed_a: Result<serde_json::Value, _> = serde_json::from_str(a);
     let parsed_b: Result<serde_json::Value, _> = serde_json::from_str(b);
@@ -654,13 +1668,25 @@ fn json_equal(a: &str, b: &str) -> bool {
     }
 }
 
+/// Resolves a path from .mooagent.toml: `~` expands to the home directory, absolute
+/// paths are kept and anything else is relative to the project directory.
+pub fn resolve_config_path(project_dir: &Path, path: &str) -> PathBuf {
+    let path = PathBuf::from(shellexpand::tilde(path).to_string());
+    if path.is_absolute() {
+        path
+    } else {
+        project_dir.join(path)
+    }
+}
+
 pub fn get_agent_status(
     target: &Path,
     source: &Path,
     expected_content: &str,
     strategy: SyncStrategy,
 ) -> AgentStatus {
-    if !target.exists() {
+    // A dangling symlink is still there, just broken, so it counts as drift
+    if fs::symlink_metadata(target).is_err() {
         return AgentStatus::Missing;
     }
 
@@ -85,10 +85,16 @@ impl CredentialManager {
             let content =
                 fs::read_to_string(&self.store_path).context("Failed to read token store")?;
             self.store = serde_json::from_str(&content).context("Failed to parse token store")?;
+        } else {
+            self.store = TokenStore::default();
         }
         Ok(())
     }
 
+    pub fn store_path(&self) -> &Path {
+        &self.store_path
+    }
+
     pub fn save(&self) -> Result<()> {
         if let Some(parent) = self.store_path.parent() {
             fs::create_dir_all(parent)?;
@@ -106,6 +112,25 @@ impl CredentialManager {
         self.store.get_valid(server_url)
     }
 
+    pub fn refresh_token(&self, server_url: &str) -> Option<&str> {
+        self.store
+            .get(server_url)
+            .and_then(|token| token.refresh_token.as_deref())
+    }
+
+    pub fn store_bearer_token(&mut self, key: &str, token: &str) -> Result<()> {
+        self.store_token(
+            key,
+            StoredToken {
+                access_token: token.to_string(),
+                refresh_token: None,
+                expires_at: None,
+                token_type: "Bearer".to_string(),
+                scopes: vec![],
+            },
+        )
+    }
+
     pub fn store_token(&mut self, server_url: &str, token: StoredToken) -> Result<()> {
         self.store.insert(server_url, token);
         self.save()
@@ -222,4 +247,32 @@ mod tests {
         assert!(store.get("https://api.example.com/mcp").is_some());
         assert!(store.get("HTTPS://API.EXAMPLE.COM/MCP/").is_some());
     }
+
+    #[test]
+    fn test_refresh_token_lookup() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut credentials = CredentialManager::new(dir.path());
+        credentials
+            .store_token(
+                "https://api.example.com/mcp",
+                StoredToken {
+                    access_token: "test".into(),
+                    refresh_token: Some("refresh".into()),
+                    expires_at: Some(Utc::now() - Duration::hours(1)),
+                    token_type: "Bearer".into(),
+                    scopes: vec![],
+                },
+            )
+            .unwrap();
+
+        assert_eq!(
+            credentials.token_status("https://api.example.com/mcp"),
+            TokenStatus::Expired
+        );
+        assert_eq!(
+            credentials.refresh_token("https://api.example.com/mcp/"),
+            Some("refresh")
+        );
+        assert_eq!(credentials.refresh_token("https://other.example.com"), None);
+    }
 }
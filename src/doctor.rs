@@ -0,0 +1,234 @@
+use crate::config::{AgentStatus, ConfigPaths};
+use std::path::Path;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CheckStatus {
+    Pass,
+    Warn,
+    Fail,
+}
+
+impl CheckStatus {
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            CheckStatus::Pass => "✅",
+            CheckStatus::Warn => "⚠️",
+            CheckStatus::Fail => "❌",
+        }
+    }
+}
+
+/// One line of the `doctor` checklist, with a remediation hint when it didn't pass.
+#[derive(Debug, Clone)]
+pub struct Check {
+    pub status: CheckStatus,
+    pub label: String,
+    pub hint: Option<String>,
+}
+
+impl Check {
+    fn pass(label: String) -> Self {
+        Self {
+            status: CheckStatus::Pass,
+            label,
+            hint: None,
+        }
+    }
+
+    fn problem(status: CheckStatus, label: String, hint: impl Into<String>) -> Self {
+        Self {
+            status,
+            label,
+            hint: Some(hint.into()),
+        }
+    }
+}
+
+/// Runs every setup check. Remote MCP servers are probed, so this can take up to
+/// `timeout_secs` per server.
+pub fn run(paths: &ConfigPaths, timeout_secs: u64) -> Vec<Check> {
+    run_with_progress(paths, timeout_secs, |_, _, _| {})
+}
+
+/// Like `run`, calling `on_server_tested(done, total, name)` after each MCP server probe.
+pub fn run_with_progress(
+    paths: &ConfigPaths,
+    timeout_secs: u64,
+    mut on_server_tested: impl FnMut(usize, usize, &str),
+) -> Vec<Check> {
+    let mut checks = Vec::new();
+
+    match dirs::home_dir() {
+        Some(home) => checks.push(Check::pass(format!("Home directory: {}", home.display()))),
+        None => checks.push(Check::problem(
+            CheckStatus::Fail,
+            "Home directory could not be determined".to_string(),
+            "Set $HOME so agent config paths can be resolved",
+        )),
+    }
+
+    for (label, dir) in [
+        ("Config directory", &paths.config_dir),
+        ("Backup directory", &paths.backup_dir),
+    ] {
+        checks.push(match check_writable(dir) {
+            Ok(()) => Check::pass(format!("{} is writable: {}", label, dir.display())),
+            Err(e) => Check::problem(
+                CheckStatus::Fail,
+                format!("{} is not writable: {} ({})", label, dir.display(), e),
+                format!("Check ownership and permissions of {}", dir.display()),
+            ),
+        });
+    }
+
+    for conflict in paths.target_conflicts() {
+        checks.push(Check::problem(
+            CheckStatus::Fail,
+            conflict,
+            "Syncing would keep rewriting the shared file",
+        ));
+    }
+
+    for agent in paths.get_agents() {
+        let dir = agent.target_path.parent().unwrap_or(Path::new("."));
+        let check = if let Some(link) = agent.link.as_ref().filter(|l| !l.resolves) {
+            Check::problem(
+                CheckStatus::Warn,
+                format!(
+                    "{}: {} is a broken symlink to {}",
+                    agent.name,
+                    agent.target_path.display(),
+                    link.points_to.display()
+                ),
+                "Run sync to re-create the link",
+            )
+        } else if !dir.is_dir() {
+            Check::problem(
+                CheckStatus::Warn,
+                format!("{}: directory {} does not exist", agent.name, dir.display()),
+                format!(
+                    "Install {} or remove it from .mooagent.toml; sync creates the directory otherwise",
+                    agent.name
+                ),
+            )
+        } else {
+            match agent.status {
+                AgentStatus::Ok => Check::pass(format!("{}: rules in sync", agent.name)),
+                AgentStatus::Missing => Check::problem(
+                    CheckStatus::Warn,
+                    format!("{}: {} is missing", agent.name, agent.target_path.display()),
+                    "Run sync (s in the TUI, or the sync tool)",
+                ),
+                AgentStatus::Drift => Check::problem(
+                    CheckStatus::Warn,
+                    format!(
+                        "{}: {} has drifted",
+                        agent.name,
+                        agent.target_path.display()
+                    ),
+                    "Review the diff (d in the TUI), then sync",
+                ),
+            }
+        };
+        checks.push(check);
+    }
+
+    let drift = paths.preference_drift_details();
+    if drift.is_empty() {
+        checks.push(Check::pass(
+            "Agent preference files match preferences".to_string(),
+        ));
+    }
+    for entry in drift {
+        checks.push(Check::problem(
+            CheckStatus::Warn,
+            format!(
+                "{} preferences {}: {}",
+                entry.agent,
+                entry.reason(),
+                entry.path.display()
+            ),
+            "Sync preferences (s on the Preferences tab, or the sync tool)",
+        ));
+    }
+
+    if !paths.has_mooagent_mcp() {
+        checks.push(Check::problem(
+            CheckStatus::Warn,
+            "mooagent MCP server is not bootstrapped".to_string(),
+            "Press I on the dashboard or call the bootstrap tool",
+        ));
+    } else if let Some(command) = paths.stale_mooagent_command() {
+        checks.push(Check::problem(
+            CheckStatus::Fail,
+            format!(
+                "mooagent MCP server points at a missing binary: {}",
+                command
+            ),
+            "Press r on the MCP Servers tab to re-point it at this binary",
+        ));
+    } else {
+        checks.push(Check::pass(
+            "mooagent MCP server is bootstrapped".to_string(),
+        ));
+    }
+
+    let merged = paths.preferences.get_merged();
+    let mut names: Vec<&String> = merged
+        .mcp_servers
+        .keys()
+        .filter(|name| !merged.disabled_mcp_servers.contains(*name))
+        .collect();
+    names.sort();
+    let total = names.len();
+    for (i, name) in names.into_iter().enumerate() {
+        let check =
+            match crate::mcp::check_mcp_server(name, &merged.mcp_servers[name], timeout_secs) {
+                Ok((CheckStatus::Pass, message)) => Check::pass(message),
+                Ok((status, message)) => Check::problem(
+                    status,
+                    message,
+                    "Fix the command or URL on the MCP Servers tab",
+                ),
+                Err(e) => Check::problem(
+                    CheckStatus::Fail,
+                    format!("Could not test MCP server '{}': {}", name, e),
+                    "Retry with the test_mcp_server tool",
+                ),
+            };
+        checks.push(check);
+        on_server_tested(i + 1, total, name);
+    }
+
+    checks
+}
+
+/// Creates and removes a scratch file to prove the directory accepts writes.
+fn check_writable(dir: &Path) -> anyhow::Result<()> {
+    crate::config::ensure_dir(dir)?;
+    tempfile::NamedTempFile::new_in(dir)?;
+    Ok(())
+}
+
+pub fn has_failures(checks: &[Check]) -> bool {
+    checks.iter().any(|c| c.status == CheckStatus::Fail)
+}
+
+pub fn format_report(checks: &[Check]) -> String {
+    let mut out = String::from("mooagent doctor\n\n");
+    for check in checks {
+        out.push_str(&format!("{} {}\n", check.status.symbol(), check.label));
+        if let Some(hint) = &check.hint {
+            out.push_str(&format!("   → {}\n", hint));
+        }
+    }
+
+    let count = |status| checks.iter().filter(|c| c.status == status).count();
+    out.push_str(&format!(
+        "\n{} passed, {} warning(s), {} failed\n",
+        count(CheckStatus::Pass),
+        count(CheckStatus::Warn),
+        count(CheckStatus::Fail)
+    ));
+    out
+}
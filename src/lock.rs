@@ -0,0 +1,167 @@
+use anyhow::{Context, Result};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fs::{self, File, OpenOptions, TryLockError};
+use std::io::{Seek, Write};
+use std::marker::PhantomData;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+const LOCK_FILE: &str = "mooagent.lock";
+const RETRY_ATTEMPTS: u32 = 20;
+const RETRY_DELAY: Duration = Duration::from_millis(100);
+
+thread_local! {
+    // The locked file and its depth per lock path, so nested acquisitions on one thread
+    // don't deadlock while other threads (such as a timed-out MCP tool still running)
+    // still have to wait
+    static HELD: RefCell<HashMap<PathBuf, (File, usize)>> = RefCell::default();
+}
+
+/// Advisory lock guarding writes to the mooagent config dir and agent configs.
+/// Released when dropped, on the thread that acquired it.
+pub struct ConfigLock {
+    path: PathBuf,
+    _not_send: PhantomData<*const ()>,
+}
+
+impl ConfigLock {
+    fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            _not_send: PhantomData,
+        }
+    }
+}
+
+pub fn acquire(config_dir: &Path) -> Result<ConfigLock> {
+    acquire_with(config_dir, RETRY_ATTEMPTS, RETRY_DELAY)
+}
+
+/// Takes an OS file lock, which the system drops when its holder exits, so a crashed
+/// instance never leaves a lock behind. The file itself stays in place: deleting it
+/// would let another instance lock a new file while a third still holds the old one.
+fn acquire_with(config_dir: &Path, attempts: u32, delay: Duration) -> Result<ConfigLock> {
+    let path = config_dir.join(LOCK_FILE);
+
+    let reentered =
+        HELD.with_borrow_mut(|held| held.get_mut(&path).map(|(_, depth)| *depth += 1).is_some());
+    if reentered {
+        return Ok(ConfigLock::new(path));
+    }
+
+    fs::create_dir_all(config_dir)?;
+    let mut file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(&path)
+        .with_context(|| format!("Failed to open lock file {}", path.display()))?;
+
+    for attempt in 0..attempts {
+        match file.try_lock() {
+            Ok(()) => {
+                // The holder's PID, only to help whoever finds the file
+                let _ = file.set_len(0);
+                let _ = file.rewind();
+                let _ = writeln!(file, "{}", std::process::id());
+                HELD.with_borrow_mut(|held| held.insert(path.clone(), (file, 1)));
+                return Ok(ConfigLock::new(path));
+            }
+            Err(TryLockError::WouldBlock) => {
+                if attempt + 1 < attempts {
+                    std::thread::sleep(delay);
+                }
+            }
+            Err(TryLockError::Error(e)) => {
+                return Err(e).with_context(|| format!("Failed to lock {}", path.display()));
+            }
+        }
+    }
+
+    anyhow::bail!(
+        "Another mooagent instance is busy (lock held: {}). Try again shortly.",
+        path.display()
+    )
+}
+
+impl Drop for ConfigLock {
+    fn drop(&mut self) {
+        HELD.with_borrow_mut(|held| {
+            if let Some((_, depth)) = held.get_mut(&self.path) {
+                *depth -= 1;
+                if *depth == 0 {
+                    // Closing the file releases the lock
+                    held.remove(&self.path);
+                }
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn locked_elsewhere(dir: &Path) -> bool {
+        let path = dir.to_path_buf();
+        std::thread::spawn(move || acquire_with(&path, 2, Duration::from_millis(1)).is_err())
+            .join()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_lock_is_reentrant_and_released_on_drop() {
+        let dir = tempdir().unwrap();
+
+        let outer = acquire(dir.path()).unwrap();
+        let inner = acquire(dir.path()).unwrap();
+        assert!(locked_elsewhere(dir.path()));
+
+        drop(inner);
+        assert!(locked_elsewhere(dir.path()));
+
+        drop(outer);
+        assert!(!locked_elsewhere(dir.path()));
+    }
+
+    #[test]
+    fn test_lock_held_by_other_instance() {
+        let dir = tempdir().unwrap();
+        let other = File::create(dir.path().join(LOCK_FILE)).unwrap();
+        other.lock().unwrap();
+
+        let err = acquire_with(dir.path(), 2, Duration::from_millis(1))
+            .err()
+            .unwrap();
+        assert!(err.to_string().contains("Another mooagent instance is busy"));
+
+        drop(other);
+        assert!(acquire_with(dir.path(), 2, Duration::from_millis(1)).is_ok());
+    }
+
+    #[test]
+    fn test_lock_is_not_reentrant_across_threads() {
+        let dir = tempdir().unwrap();
+        let _held = acquire(dir.path()).unwrap();
+        assert!(locked_elsewhere(dir.path()));
+    }
+
+    #[test]
+    fn test_leftover_lock_file_does_not_block() {
+        let dir = tempdir().unwrap();
+        // As left behind by an instance that exited, or by an older version
+        fs::write(dir.path().join(LOCK_FILE), "12345\n").unwrap();
+
+        let lock = acquire_with(dir.path(), 2, Duration::from_millis(1)).unwrap();
+        assert_eq!(
+            fs::read_to_string(dir.path().join(LOCK_FILE))
+                .unwrap()
+                .trim(),
+            std::process::id().to_string()
+        );
+        drop(lock);
+    }
+}
@@ -0,0 +1,107 @@
+use anyhow::Result;
+use log::{LevelFilter, Log, Metadata, Record};
+use serde_json::json;
+use simplelog::{Config, WriteLogger};
+use std::io::Write;
+use std::sync::Mutex;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LogFormat {
+    #[default]
+    Text,
+    Json,
+}
+
+impl std::str::FromStr for LogFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "text" => Ok(LogFormat::Text),
+            "json" => Ok(LogFormat::Json),
+            other => anyhow::bail!("Unknown log format '{}' (expected 'text' or 'json')", other),
+        }
+    }
+}
+
+/// Writes one JSON object per line, for log collectors that expect structured records.
+struct JsonLogger<W> {
+    level: LevelFilter,
+    file: Mutex<W>,
+}
+
+impl<W: Write + Send> Log for JsonLogger<W> {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= self.level
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        if let Ok(mut file) = self.file.lock() {
+            let _ = writeln!(file, "{}", json_record(record));
+        }
+    }
+
+    fn flush(&self) {
+        if let Ok(mut file) = self.file.lock() {
+            let _ = file.flush();
+        }
+    }
+}
+
+fn json_record(record: &Record) -> serde_json::Value {
+    json!({
+        "timestamp": chrono::Local::now().to_rfc3339(),
+        "level": record.level().to_string(),
+        "target": record.target(),
+        "message": record.args().to_string(),
+    })
+}
+
+/// `file` is the log file, or stderr for the headless commands.
+pub fn init<W: Write + Send + 'static>(
+    format: LogFormat,
+    level: LevelFilter,
+    file: W,
+) -> Result<()> {
+    match format {
+        LogFormat::Text => WriteLogger::init(level, Config::default(), file)?,
+        LogFormat::Json => {
+            log::set_boxed_logger(Box::new(JsonLogger {
+                level,
+                file: Mutex::new(file),
+            }))?;
+            log::set_max_level(level);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_json_record() {
+        let value = json_record(
+            &Record::builder()
+                .args(format_args!("Synced {} agents", 3))
+                .level(log::Level::Warn)
+                .target("mooagent::mcp")
+                .build(),
+        );
+        assert_eq!(value["level"], "WARN");
+        assert_eq!(value["target"], "mooagent::mcp");
+        assert_eq!(value["message"], "Synced 3 agents");
+        assert!(value["timestamp"].is_string());
+    }
+
+    #[test]
+    fn test_parse_log_format() {
+        assert_eq!("json".parse::<LogFormat>().unwrap(), LogFormat::Json);
+        assert_eq!("Text".parse::<LogFormat>().unwrap(), LogFormat::Text);
+        assert!("xml".parse::<LogFormat>().is_err());
+    }
+}
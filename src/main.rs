@@ -1,8 +1,13 @@
 mod app;
+mod audit;
 mod config;
 mod credentials;
+mod doctor;
 mod http;
+mod lock;
+mod logging;
 mod mcp;
+mod mcp_http;
 mod oauth;
 mod preferences;
 mod ui;
@@ -10,53 +15,296 @@ mod ui;
 #[cfg(test)]
 mod tests;
 
-use crate::app::{ActiveTab, App, AppMode, OAuthFlowConfig, PrefEditorFocus};
+use crate::app::{ActiveTab, App, AppMode, CursorMove, OAuthFlowConfig, PrefEditorFocus};
+use crate::logging::LogFormat;
 use anyhow::Result;
 use crossterm::{
     event::{
-        self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyModifiers, MouseEventKind,
+        self, DisableBracketedPaste, DisableMouseCapture, EnableBracketedPaste, EnableMouseCapture,
+        Event, KeyCode, KeyModifiers, MouseButton, MouseEventKind,
     },
     execute,
     terminal::{EnterAlternateScreen, LeaveAlternateScreen, disable_raw_mode, enable_raw_mode},
 };
+use log::LevelFilter;
 use notify::{RecursiveMode, Watcher};
 use ratatui::{Terminal, backend::CrosstermBackend};
-use simplelog::*;
-use std::{fs::File, io, path::Path, sync::mpsc};
+use std::{
+    fs::File,
+    io,
+    path::{Path, PathBuf},
+    sync::mpsc,
+};
+
+/// Terminal features the TUI takes over; both can be turned off for multiplexers,
+/// scrollback or native mouse selection.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct TuiOptions {
+    alt_screen: bool,
+    mouse: bool,
+}
+
+/// Whether a boolean environment variable is switched on (set, non-empty and not `0`).
+fn env_flag(value: Option<&str>) -> bool {
+    value.is_some_and(|v| !v.is_empty() && v != "0")
+}
+
+/// Restores the terminal before the previous hook prints the panic. `Tui`'s `Drop` only
+/// runs while unwinding, after the message went to the alternate screen and got wiped.
+fn install_panic_hook(options: TuiOptions) {
+    let previous = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        let _ = disable_raw_mode();
+        let mut stdout = std::io::stdout();
+        if options.alt_screen {
+            let _ = execute!(stdout, LeaveAlternateScreen);
+        }
+        if options.mouse {
+            let _ = execute!(stdout, DisableMouseCapture);
+        }
+        let _ = execute!(stdout, DisableBracketedPaste, crossterm::cursor::Show);
+        log::error!("mooagent panicked: {}", info);
+        previous(info);
+    }));
+}
 
 struct Tui<B: ratatui::backend::Backend + std::io::Write> {
     terminal: Terminal<B>,
+    options: TuiOptions,
 }
 
 impl<B: ratatui::backend::Backend + std::io::Write> Tui<B> {
-    fn new(backend: B) -> Result<Self>
+    fn new(backend: B, options: TuiOptions) -> Result<Self>
     where
         B::Error: Send + Sync + 'static,
     {
         enable_raw_mode()?;
-        execute!(std::io::stdout(), EnterAlternateScreen, EnableMouseCapture)?;
-        let terminal = Terminal::new(backend)?;
-        Ok(Self { terminal })
+        let mut stdout = std::io::stdout();
+        if options.alt_screen {
+            execute!(stdout, EnterAlternateScreen)?;
+        }
+        if options.mouse {
+            execute!(stdout, EnableMouseCapture)?;
+        }
+        execute!(stdout, EnableBracketedPaste)?;
+        let mut terminal = Terminal::new(backend)?;
+        if !options.alt_screen {
+            // Start from a blank screen so earlier output scrolls up instead of showing through
+            terminal.clear()?;
+        }
+        Ok(Self { terminal, options })
     }
 }
 
 impl<B: ratatui::backend::Backend + std::io::Write> Drop for Tui<B> {
     fn drop(&mut self) {
         let _ = disable_raw_mode();
-        let _ = execute!(
-            self.terminal.backend_mut(),
-            LeaveAlternateScreen,
-            DisableMouseCapture
-        );
+        let backend = self.terminal.backend_mut();
+        if self.options.alt_screen {
+            let _ = execute!(backend, LeaveAlternateScreen);
+        }
+        if self.options.mouse {
+            let _ = execute!(backend, DisableMouseCapture);
+        }
+        let _ = execute!(backend, DisableBracketedPaste);
         let _ = self.terminal.show_cursor();
+        if !self.options.alt_screen {
+            // Leave the shell prompt below the last frame rather than on top of it
+            let _ = writeln!(self.terminal.backend_mut());
+        }
+    }
+}
+
+const USAGE: &str = "\
+Usage: mooagent [OPTIONS]
+       mooagent sync [--dry-run] [--project-dir <DIR>]
+
+Manage agent rules, preferences and MCP servers across coding agents.
+Without options, launches the interactive TUI in the current project.
+
+Commands:
+  sync                     Sync rules, global rules and preferences without the TUI and exit
+                           (stops at the first failing step and exits non-zero); --dry-run
+                           only prints what would change. Accepts --project-dir and
+                           --log-format (warnings go to stderr)
+
+Options:
+      --mcp                Run as an MCP server over stdio
+      --mcp-http           Run as an MCP server over HTTP on localhost (POST /mcp, GET /health)
+      --port <PORT>        Port for --mcp-http (default: 8765)
+      --log-format <FMT>   Log file format: text (default) or json [env: MOOAGENT_LOG_FORMAT]
+      --project-dir <DIR>  Project to manage instead of the current directory
+                           [env: MOOAGENT_PROJECT_DIR]
+      --no-altscreen       Draw the TUI in the main screen buffer so it stays in scrollback
+                           [env: MOOAGENT_NO_ALTSCREEN]
+      --no-mouse           Leave the mouse to the terminal for native text selection
+                           [env: MOOAGENT_NO_MOUSE]
+      --keys               Print the TUI keybindings as markdown and exit
+      --doctor             Check the setup for common problems and exit (non-zero on failures)
+  -h, --help               Print this help and exit
+  -V, --version            Print version and exit";
+
+fn usage_error(message: &str) -> ! {
+    eprintln!("{}\n\n{}", message, USAGE);
+    std::process::exit(2);
+}
+
+/// `mooagent sync`: the same steps as the `sync` MCP tool, stopping at the first that
+/// fails. Returns whether everything synced; `main` turns `false` into exit code 1.
+fn run_sync(
+    paths: &mut config::ConfigPaths,
+    dry_run: bool,
+    out: &mut impl io::Write,
+    err: &mut impl io::Write,
+) -> Result<bool> {
+    if dry_run {
+        writeln!(out, "{}", mcp::sync_preview(paths)?)?;
+        return Ok(true);
+    }
+
+    // `sync` reports agents it couldn't write in its message rather than as an error
+    match paths.sync() {
+        Ok(message)
+            if paths
+                .get_agents()
+                .iter()
+                .all(|a| a.status == config::AgentStatus::Ok) =>
+        {
+            writeln!(out, "{}", message)?
+        }
+        Ok(message) => {
+            writeln!(err, "Rules sync failed: {}", message)?;
+            return Ok(false);
+        }
+        Err(e) => {
+            writeln!(err, "Rules sync failed: {:#}", e)?;
+            return Ok(false);
+        }
+    }
+    if let Err(e) = paths.sync_global_rules() {
+        writeln!(err, "Global rules sync failed: {:#}", e)?;
+        return Ok(false);
+    }
+    writeln!(out, "Global rules synced.")?;
+    let refreshed = paths.refresh_expired_oauth_tokens();
+    match paths.sync_preferences() {
+        Ok(message) => writeln!(out, "{}{}", message, mcp::refreshed_tokens_note(&refreshed))?,
+        Err(e) => {
+            writeln!(err, "Preferences sync failed: {:#}", e)?;
+            return Ok(false);
+        }
     }
+    Ok(true)
 }
 
 fn main() -> Result<()> {
     let args: Vec<String> = std::env::args().collect();
 
-    if args.iter().any(|a| a == "--mcp") {
-        return mcp::run_mcp_server();
+    if args.iter().any(|a| a == "--help" || a == "-h") {
+        println!("{}", USAGE);
+        return Ok(());
+    }
+
+    if args.iter().any(|a| a == "--version" || a == "-V") {
+        println!("mooagent {}", env!("CARGO_PKG_VERSION"));
+        return Ok(());
+    }
+
+    if args.iter().any(|a| a == "--keys") {
+        print!("{}", ui::keybindings_markdown());
+        return Ok(());
+    }
+
+    let mut doctor = false;
+    let mut mcp_mode = false;
+    let mut mcp_http_mode = false;
+    let mut port = mcp_http::DEFAULT_PORT;
+    let mut log_format = std::env::var("MOOAGENT_LOG_FORMAT").ok();
+    let mut tui_options = TuiOptions {
+        alt_screen: !env_flag(std::env::var("MOOAGENT_NO_ALTSCREEN").ok().as_deref()),
+        mouse: !env_flag(std::env::var("MOOAGENT_NO_MOUSE").ok().as_deref()),
+    };
+    let mut sync_command = false;
+    let mut dry_run = false;
+    // Options that only make sense without `sync`, so it can reject them
+    let mut non_sync_option = None;
+    let mut rest = args.iter().skip(1);
+    while let Some(arg) = rest.next() {
+        if matches!(
+            arg.as_str(),
+            "--doctor" | "--mcp" | "--mcp-http" | "--port" | "--no-altscreen" | "--no-mouse"
+        ) {
+            non_sync_option.get_or_insert(arg.as_str());
+        }
+        match arg.as_str() {
+            "sync" => sync_command = true,
+            "--dry-run" => dry_run = true,
+            "--doctor" => doctor = true,
+            "--mcp" => mcp_mode = true,
+            "--mcp-http" => mcp_http_mode = true,
+            "--no-altscreen" => tui_options.alt_screen = false,
+            "--no-mouse" => tui_options.mouse = false,
+            "--port" => {
+                port = rest
+                    .next()
+                    .and_then(|p| p.parse().ok())
+                    .unwrap_or_else(|| usage_error("--port requires a port number"));
+            }
+            "--log-format" => log_format = Some(rest.next().cloned().unwrap_or_default()),
+            "--project-dir" => {
+                let dir = rest
+                    .next()
+                    .unwrap_or_else(|| usage_error("--project-dir requires a path"));
+                if let Err(e) = config::set_project_dir(Path::new(dir)) {
+                    usage_error(&e.to_string());
+                }
+            }
+            _ => usage_error(&format!("Unknown option: {}", arg)),
+        }
+    }
+
+    if dry_run && !sync_command {
+        usage_error("--dry-run can only be used with sync");
+    }
+    if let Some(option) = non_sync_option.filter(|_| sync_command) {
+        usage_error(&format!("{} can't be used with sync", option));
+    }
+
+    let log_format = match log_format {
+        Some(value) => value
+            .parse::<LogFormat>()
+            .unwrap_or_else(|e| usage_error(&e.to_string())),
+        None => LogFormat::default(),
+    };
+
+    if sync_command || doctor {
+        // The headless commands have no log file; show warnings such as agents that
+        // failed to sync where the caller sees them
+        let _ = logging::init(log_format, LevelFilter::Warn, io::stderr());
+    }
+
+    if sync_command {
+        let mut paths = config::ConfigPaths::new()?;
+        if !run_sync(&mut paths, dry_run, &mut io::stdout(), &mut io::stderr())? {
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+
+    if doctor {
+        let paths = config::ConfigPaths::new()?;
+        let timeout_secs = paths
+            .preferences
+            .get_merged()
+            .general
+            .mcp_test_timeout_secs
+            .unwrap_or(mcp::DEFAULT_TEST_TIMEOUT_SECS);
+        let checks = doctor::run(&paths, timeout_secs);
+        print!("{}", doctor::format_report(&checks));
+        if doctor::has_failures(&checks) {
+            std::process::exit(1);
+        }
+        return Ok(());
     }
 
     let log_dir = directories::ProjectDirs::from("", "", "mooagent")
@@ -64,29 +312,51 @@ fn main() -> Result<()> {
         .unwrap_or_else(std::env::temp_dir);
 
     std::fs::create_dir_all(&log_dir)?;
+
+    if mcp_mode || mcp_http_mode {
+        // Several agents may each run a server, so append rather than truncate
+        let log_file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(log_dir.join("mcp.log"))?;
+        let _ = logging::init(log_format, LevelFilter::Info, log_file);
+
+        if mcp_http_mode {
+            return mcp_http::run_mcp_http_server(port);
+        }
+        return mcp::run_mcp_server();
+    }
+
     let log_file = log_dir.join("mooagent.log");
 
-    let _ = WriteLogger::init(
-        LevelFilter::Info,
-        Config::default(),
-        File::create(log_file)?,
-    );
+    let _ = logging::init(log_format, LevelFilter::Info, File::create(log_file)?);
 
     log::info!("Starting MooAgent");
 
+    install_panic_hook(tui_options);
     let backend = CrosstermBackend::new(io::stdout());
-    let mut tui = Tui::new(backend)?;
+    let mut tui = Tui::new(backend, tui_options)?;
 
     let (tx, rx) = mpsc::channel();
 
     let mut app = App::new(Some(rx))?;
 
-    let mut watcher = notify::recommended_watcher(move |res| match res {
-        Ok(_) => {
-            let _ = tx.send(());
-        }
-        Err(e) => eprintln!("watch error: {:?}", e),
-    })?;
+    // The config dir is watched for the credentials store, which may not exist yet
+    let config_dir = app.paths.config_dir.clone();
+    let config_files = vec![
+        app.credentials.store_path().to_path_buf(),
+        app.paths.preferences.global_path.clone(),
+        app.paths.global_rules_primary.clone(),
+    ];
+    let mut watcher =
+        notify::recommended_watcher(move |res: notify::Result<notify::Event>| match res {
+            Ok(event) => {
+                if watched_change(&event.paths, &config_dir, &config_files) {
+                    let _ = tx.send(());
+                }
+            }
+            Err(e) => log::error!("watch error: {:?}", e),
+        })?;
 
     if app.paths.project_agents.exists() {
         watcher.watch(&app.paths.project_agents, RecursiveMode::NonRecursive)?;
@@ -107,6 +377,8 @@ fn main() -> Result<()> {
         )?;
     }
 
+    watcher.watch(&app.paths.config_dir, RecursiveMode::NonRecursive)?;
+
     for agent_def in &app.paths.agent_configs {
         if let Some(global_file) = &agent_def.global_file
             && global_file.exists()
@@ -116,14 +388,26 @@ fn main() -> Result<()> {
     }
 
     let res = run_app(&mut tui.terminal, &mut app);
+    app.save_ui_state();
+    drop(tui);
 
     if let Err(err) = res {
-        println!("{:?}", err);
+        log::error!("{:?}", err);
+        eprintln!("{:?}", err);
     }
 
     Ok(())
 }
 
+/// Whether a watcher event concerns a file the TUI shows. Other files in the config
+/// dir, such as the lock taken on every write, are left out so they don't trigger reloads.
+fn watched_change(paths: &[PathBuf], config_dir: &Path, config_files: &[PathBuf]) -> bool {
+    paths.is_empty()
+        || paths
+            .iter()
+            .any(|p| p.parent() != Some(config_dir) || config_files.contains(p))
+}
+
 fn run_app<B: ratatui::backend::Backend + std::io::Write>(
     terminal: &mut Terminal<B>,
 
@@ -132,12 +416,21 @@ fn run_app<B: ratatui::backend::Backend + std::io::Write>(
 where
     B::Error: std::error::Error + Send + Sync + 'static,
 {
+    // Only redraw after input, state changes, a resize or, while relative times are shown,
+    // an idle poll, so an idle TUI otherwise stays quiet
     loop {
         app.tick();
 
-        terminal.draw(|f| ui::render(f, app))?;
+        if app.needs_redraw {
+            terminal.draw(|f| ui::render(f, app))?;
+            app.needs_redraw = false;
+            if std::mem::take(&mut app.clamp_scroll_after_draw) {
+                app.clamp_rule_scrolls();
+            }
+        }
 
-        if event::poll(std::time::Duration::from_millis(100))? {
+        if event::poll(app.poll_interval())? {
+            app.needs_redraw = true;
             match event::read()? {
                 Event::Key(key) => match app.mode {
                     AppMode::EditMcp => match key.code {
@@ -145,17 +438,37 @@ where
                         KeyCode::Enter => app.mcp_submit(),
                         KeyCode::Tab => app.mcp_next_field(),
                         KeyCode::Backspace => app.mcp_backspace(),
+                        KeyCode::Delete => app.delete_input_char(),
+                        KeyCode::Left => app.move_input_cursor(CursorMove::Left),
+                        KeyCode::Right => app.move_input_cursor(CursorMove::Right),
+                        KeyCode::Home => app.move_input_cursor(CursorMove::Home),
+                        KeyCode::End => app.move_input_cursor(CursorMove::End),
                         KeyCode::Char(c) => app.mcp_input_char(c),
                         _ => {}
                     },
                     AppMode::AddTool => match key.code {
                         KeyCode::Esc => app.cancel_add_tool(),
                         KeyCode::Enter => app.submit_new_tool(),
+                        KeyCode::Tab => app.cycle_new_tool_category(),
                         KeyCode::Backspace => app.backspace_add_tool(),
+                        KeyCode::Delete => app.delete_input_char(),
+                        KeyCode::Left => app.move_input_cursor(CursorMove::Left),
+                        KeyCode::Right => app.move_input_cursor(CursorMove::Right),
+                        KeyCode::Home => app.move_input_cursor(CursorMove::Home),
+                        KeyCode::End => app.move_input_cursor(CursorMove::End),
                         KeyCode::Char(c) => app.add_tool_char(c),
                         _ => {}
                     },
-                    AppMode::Help | AppMode::ViewDiff | AppMode::ViewBackups => match key.code {
+                    AppMode::ViewBackups => match key.code {
+                        KeyCode::Char('q') | KeyCode::Esc => {
+                            app.mode = AppMode::Normal;
+                        }
+                        KeyCode::Char('j') | KeyCode::Down => app.backup_next(),
+                        KeyCode::Char('k') | KeyCode::Up => app.backup_prev(),
+                        KeyCode::Char('r') => app.restore_selected_backup(app.backup_selected),
+                        _ => {}
+                    },
+                    AppMode::Help | AppMode::ViewDiff => match key.code {
                         KeyCode::Char('q') | KeyCode::Esc => {
                             app.mode = AppMode::Normal;
                         }
@@ -167,6 +480,21 @@ where
                         }
                         _ => {}
                     },
+                    AppMode::SectionOutline => match key.code {
+                        KeyCode::Char('q') | KeyCode::Esc => {
+                            app.mode = AppMode::Normal;
+                        }
+                        KeyCode::Char('j') | KeyCode::Down => {
+                            app.outline_next();
+                        }
+                        KeyCode::Char('k') | KeyCode::Up => {
+                            app.outline_prev();
+                        }
+                        KeyCode::Enter => {
+                            app.outline_jump();
+                        }
+                        _ => {}
+                    },
                     AppMode::Search => match key.code {
                         KeyCode::Esc => {
                             app.clear_search();
@@ -178,6 +506,11 @@ where
                         KeyCode::Backspace => {
                             app.backspace_search();
                         }
+                        KeyCode::Delete => app.delete_input_char(),
+                        KeyCode::Left => app.move_input_cursor(CursorMove::Left),
+                        KeyCode::Right => app.move_input_cursor(CursorMove::Right),
+                        KeyCode::Home => app.move_input_cursor(CursorMove::Home),
+                        KeyCode::End => app.move_input_cursor(CursorMove::End),
                         KeyCode::Char(c) => {
                             app.add_search_char(c);
                         }
@@ -188,6 +521,12 @@ where
                             let _ = app.sync_selected();
                             app.mode = AppMode::Normal;
                         }
+                        KeyCode::Char('p') if !app.hand_edits.is_empty() => {
+                            app.pull_hand_edits();
+                            app.mode = AppMode::Normal;
+                        }
+                        KeyCode::Char('j') | KeyCode::Down => app.scroll_detail_down(),
+                        KeyCode::Char('k') | KeyCode::Up => app.scroll_detail_up(),
                         KeyCode::Char('n') | KeyCode::Char('N') | KeyCode::Esc => {
                             app.mode = AppMode::Normal;
                         }
@@ -198,6 +537,12 @@ where
                             let _ = app.sync();
                             app.mode = AppMode::Normal;
                         }
+                        KeyCode::Char('p') if !app.hand_edits.is_empty() => {
+                            app.pull_hand_edits();
+                            app.mode = AppMode::Normal;
+                        }
+                        KeyCode::Char('j') | KeyCode::Down => app.scroll_detail_down(),
+                        KeyCode::Char('k') | KeyCode::Up => app.scroll_detail_up(),
                         KeyCode::Char('n') | KeyCode::Char('N') | KeyCode::Esc => {
                             app.mode = AppMode::Normal;
                         }
@@ -249,28 +594,51 @@ where
                         }
                     }
                 },
-                Event::Mouse(mouse) => {
-                    if app.mode == AppMode::Normal
-                        && !app.show_error_log
-                        && app.active_tab == ActiveTab::Dashboard
+                Event::Resize(_, _) => app.handle_resize(),
+                Event::Paste(text) => match app.mode {
+                    AppMode::EditMcp => app.mcp_paste(&text),
+                    AppMode::AddTool => text
+                        .chars()
+                        .filter(|c| !c.is_control())
+                        .for_each(|c| app.add_tool_char(c)),
+                    AppMode::Search => text
+                        .chars()
+                        .filter(|c| !c.is_control())
+                        .for_each(|c| app.add_search_char(c)),
+                    _ => {}
+                },
+                Event::Mouse(mouse) if app.mode == AppMode::Normal && !app.show_error_log => {
+                    if mouse.kind == MouseEventKind::Down(MouseButton::Left)
+                        && app.click_tab(mouse.column, mouse.row)
                     {
-                        match mouse.kind {
-                            MouseEventKind::ScrollDown => match app.focus {
-                                crate::app::Focus::Agents => app.next_agent(),
-                                crate::app::Focus::Global => app.scroll_global_down(),
-                                crate::app::Focus::Project => app.scroll_project_down(),
-                            },
-                            MouseEventKind::ScrollUp => match app.focus {
-                                crate::app::Focus::Agents => app.prev_agent(),
-                                crate::app::Focus::Global => app.scroll_global_up(),
-                                crate::app::Focus::Project => app.scroll_project_up(),
-                            },
-                            _ => {}
+                        continue;
+                    }
+                    if app.active_tab != ActiveTab::Dashboard {
+                        continue;
+                    }
+
+                    match mouse.kind {
+                        MouseEventKind::ScrollDown => match app.focus {
+                            crate::app::Focus::Agents => app.next_agent(),
+                            crate::app::Focus::Global => app.scroll_global_down(),
+                            crate::app::Focus::Project => app.scroll_project_down(),
+                        },
+                        MouseEventKind::ScrollUp => match app.focus {
+                            crate::app::Focus::Agents => app.prev_agent(),
+                            crate::app::Focus::Global => app.scroll_global_up(),
+                            crate::app::Focus::Project => app.scroll_project_up(),
+                        },
+                        MouseEventKind::Down(MouseButton::Left) => {
+                            app.click_dashboard(mouse.column, mouse.row);
                         }
+                        _ => {}
                     }
                 }
                 _ => {}
             }
+        } else if app.shows_relative_times() {
+            // "… ago" times advance even when nothing else changes
+            app.needs_redraw = true;
         }
 
         if app.should_quit {
@@ -321,6 +689,21 @@ where
         KeyCode::Char('o') => {
             handle_oauth_action(app, terminal)?;
         }
+        KeyCode::Char('r') => {
+            app.repoint_mooagent_mcp();
+        }
+        KeyCode::Char('p') => {
+            app.mcp_editor_state.show_preview = !app.mcp_editor_state.show_preview;
+        }
+        KeyCode::Char('/') => {
+            app.start_search();
+        }
+        KeyCode::Char('A') => {
+            app.mcp_toggle_all_enabled(false);
+        }
+        KeyCode::Char('R') => {
+            app.mcp_toggle_all_enabled(true);
+        }
         _ => {}
     }
     Ok(())
@@ -367,7 +750,8 @@ where
             execute!(
                 terminal.backend_mut(),
                 LeaveAlternateScreen,
-                DisableMouseCapture
+                DisableMouseCapture,
+                DisableBracketedPaste
             )?;
             terminal.show_cursor()?;
 
@@ -384,7 +768,8 @@ where
             execute!(
                 terminal.backend_mut(),
                 EnterAlternateScreen,
-                EnableMouseCapture
+                EnableMouseCapture,
+                EnableBracketedPaste
             )?;
             terminal.clear()?;
 
@@ -425,9 +810,22 @@ fn handle_preferences_input(app: &mut App, key: event::KeyEvent) -> Result<()> {
         KeyCode::Char('s') => {
             let _ = app.sync_preferences();
         }
+        KeyCode::Char('w') => {
+            app.save_prefs();
+        }
         KeyCode::Char('a') if app.pref_editor_state.focus == PrefEditorFocus::IndividualTools => {
             app.mode = AppMode::AddTool;
         }
+        KeyCode::Char('l') | KeyCode::Right
+            if app.pref_editor_state.focus == PrefEditorFocus::GeneralSettings =>
+        {
+            app.pref_cycle_general_scope(true);
+        }
+        KeyCode::Char('h') | KeyCode::Left
+            if app.pref_editor_state.focus == PrefEditorFocus::GeneralSettings =>
+        {
+            app.pref_cycle_general_scope(false);
+        }
         _ => {}
     }
     Ok(())
@@ -446,6 +844,7 @@ where
             KeyCode::Char('v') | KeyCode::Esc | KeyCode::Char('q') => {
                 app.toggle_error_log();
             }
+            KeyCode::Char('e') => app.export_status_log(),
             _ => {}
         }
     } else {
@@ -460,11 +859,11 @@ where
             }
 
             KeyCode::Char('s') => {
-                app.mode = AppMode::ConfirmSyncAll;
+                app.confirm_sync(true);
             }
 
             KeyCode::Enter => {
-                app.mode = AppMode::ConfirmSync;
+                app.confirm_sync(false);
             }
 
             KeyCode::Char('d') if !key.modifiers.contains(KeyModifiers::CONTROL) => {
@@ -473,8 +872,15 @@ where
             }
 
             KeyCode::Char('b') => {
-                app.detail_scroll = 0;
-                app.mode = AppMode::ViewBackups;
+                app.open_backups();
+            }
+
+            KeyCode::Char('B') => {
+                app.backup_now();
+            }
+
+            KeyCode::Char('I') => {
+                app.bootstrap_mooagent_mcp();
             }
 
             KeyCode::Char('e') if key.modifiers.contains(KeyModifiers::CONTROL) => {
@@ -512,11 +918,8 @@ where
                             app.prev_agent();
                         }
                     }
-                    crate::app::Focus::Global => {
-                        app.global_scroll = app.global_scroll.saturating_sub(10);
-                    }
-                    crate::app::Focus::Project => {
-                        app.scroll_project_page_up();
+                    crate::app::Focus::Global | crate::app::Focus::Project => {
+                        app.scroll_page_up(true);
                     }
                 }
             }
@@ -528,11 +931,8 @@ where
                             app.next_agent();
                         }
                     }
-                    crate::app::Focus::Global => {
-                        app.global_scroll += 10;
-                    }
-                    crate::app::Focus::Project => {
-                        app.scroll_project_page_down();
+                    crate::app::Focus::Global | crate::app::Focus::Project => {
+                        app.scroll_page_down(true);
                     }
                 }
             }
@@ -546,7 +946,7 @@ where
             }
 
             KeyCode::Char('/') => {
-                app.mode = AppMode::Search;
+                app.start_search();
             }
 
             KeyCode::Char('v') => {
@@ -581,20 +981,32 @@ where
                 app.focus_right();
             }
 
+            KeyCode::Char('o') => {
+                app.open_section_outline();
+            }
+
+            KeyCode::Char('{') => {
+                app.jump_to_section(false);
+            }
+
+            KeyCode::Char('}') => {
+                app.jump_to_section(true);
+            }
+
             KeyCode::PageUp => {
-                app.scroll_project_page_up();
+                app.scroll_page_up(false);
             }
 
             KeyCode::PageDown => {
-                app.scroll_project_page_down();
+                app.scroll_page_down(false);
             }
 
             KeyCode::Home => {
-                app.scroll_project_home();
+                app.scroll_home();
             }
 
             KeyCode::End => {
-                app.scroll_project_end();
+                app.scroll_end();
             }
 
             KeyCode::Esc => {
@@ -621,7 +1033,8 @@ where
     execute!(
         terminal.backend_mut(),
         LeaveAlternateScreen,
-        DisableMouseCapture
+        DisableMouseCapture,
+        DisableBracketedPaste
     )?;
 
     terminal.show_cursor()?;
@@ -655,7 +1068,8 @@ where
     execute!(
         terminal.backend_mut(),
         EnterAlternateScreen,
-        EnableMouseCapture
+        EnableMouseCapture,
+        EnableBracketedPaste
     )?;
 
     terminal.clear()?;
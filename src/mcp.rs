@@ -1,13 +1,26 @@
-use crate::config::ConfigPaths;
+use crate::config::{ConfigPaths, SyncState, write_atomic};
 use crate::credentials::{CredentialManager, TokenStatus};
+use crate::doctor::CheckStatus;
 use crate::oauth;
-use crate::preferences::{McpAuth, McpServerConfig};
-use anyhow::Result;
-use chrono::Local;
+use crate::preferences::{
+    AgentPreferences, GeneralPreferences, McpAuth, McpServerConfig, expand_tools,
+};
+use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
 use serde_json::{Value, json};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::io::{BufRead, BufReader, Write};
+use std::sync::{LazyLock, Mutex};
+use std::time::{Duration, Instant, SystemTime};
+
+const CONFIRMATION_TTL: Duration = Duration::from_secs(300);
+
+/// A call the destructive-tool interlock refused: tool, arguments and when it was refused.
+type PendingConfirmation = (String, Value, Instant);
+
+// Confirmation tokens handed out by the interlock, with the call each one confirms
+static PENDING_CONFIRMATIONS: LazyLock<Mutex<HashMap<String, PendingConfirmation>>> =
+    LazyLock::new(Mutex::default);
 
 #[derive(Debug, Serialize, Deserialize)]
 struct JsonRpcRequest {
@@ -34,37 +47,218 @@ struct JsonRpcError {
     message: String,
 }
 
+/// Configuration shared by the tool calls of one server session. It is loaded on the first
+/// call and reloaded only when `.mooagent.toml` or `preferences.toml` change on disk.
+#[derive(Default)]
+pub struct McpState {
+    paths: Option<ConfigPaths>,
+    stamp: Vec<Option<(SystemTime, u64)>>,
+    notifier: Option<Box<dyn FnMut(Value) + Send>>,
+    // A timed-out call that may still be writing, by tool name
+    orphaned: Option<(String, std::thread::JoinHandle<()>)>,
+}
+
+impl McpState {
+    pub fn new(paths: ConfigPaths) -> Self {
+        Self {
+            stamp: config_stamp(&paths),
+            paths: Some(paths),
+            notifier: None,
+            orphaned: None,
+        }
+    }
+
+    /// Sends notifications such as `notifications/progress` to the client while a tool runs.
+    /// Without a notifier they are dropped and the client only sees the final result.
+    pub fn with_notifier(mut self, notifier: impl FnMut(Value) + Send + 'static) -> Self {
+        self.notifier = Some(Box::new(notifier));
+        self
+    }
+
+    fn notify(&mut self, notification: Value) {
+        if let Some(notifier) = self.notifier.as_mut() {
+            notifier(notification);
+        }
+    }
+
+    /// Whether the files behind the cached config changed since it was loaded.
+    pub fn is_stale(&self) -> bool {
+        self.paths
+            .as_ref()
+            .is_none_or(|paths| config_stamp(paths) != self.stamp)
+    }
+
+    /// Lends the config out for one tool call, reloading it first if it is stale. Until
+    /// it is handed back with `restore`, the next call loads a fresh copy.
+    fn take_paths(&mut self) -> Result<ConfigPaths> {
+        if self.is_stale() {
+            log::info!("Reloading mooagent configuration");
+            let paths = ConfigPaths::new()?;
+            self.stamp = config_stamp(&paths);
+            self.paths = Some(paths);
+        }
+        self.paths
+            .take()
+            .context("mooagent configuration is not loaded")
+    }
+
+    fn restore(&mut self, paths: ConfigPaths) {
+        self.paths = Some(paths);
+    }
+
+    /// Refuses tools that write while a timed-out call is still running, so the two
+    /// can't both write the same files. Read-only tools are still served.
+    fn check_orphaned_writer(&mut self, name: &str) -> Result<()> {
+        let Some((tool, handle)) = &self.orphaned else {
+            return Ok(());
+        };
+        if handle.is_finished() {
+            self.orphaned = None;
+            return Ok(());
+        }
+        if is_read_only(name) {
+            return Ok(());
+        }
+        anyhow::bail!(
+            "A timed-out '{}' call is still running; try '{}' again once it finishes",
+            tool,
+            name
+        )
+    }
+}
+
+pub const DEFAULT_TOOL_TIMEOUT_SECS: u64 = 120;
+pub const DEFAULT_OAUTH_LOGIN_TIMEOUT_SECS: u64 = 300;
+
+/// A tool call that ran past its timeout, reported as JSON-RPC error -32000.
+#[derive(Debug)]
+pub struct ToolTimeout {
+    pub tool: String,
+    pub secs: u64,
+}
+
+impl std::fmt::Display for ToolTimeout {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Tool '{}' timed out after {}s (raise general.{} in preferences.toml if it needs longer)",
+            self.tool,
+            self.secs,
+            if self.tool == "oauth_login" {
+                "oauth_login_timeout_secs"
+            } else {
+                "mcp_tool_timeout_secs"
+            }
+        )
+    }
+}
+
+impl std::error::Error for ToolTimeout {}
+
+/// Reports how far a long tool call has got. Calls without a `progressToken` in `_meta`
+/// get a reporter that does nothing.
+#[derive(Clone, Default)]
+pub struct Progress {
+    token: Option<Value>,
+    tx: Option<std::sync::mpsc::Sender<ToolEvent>>,
+}
+
+impl Progress {
+    pub fn new(token: Value, tx: std::sync::mpsc::Sender<ToolEvent>) -> Self {
+        Self {
+            token: Some(token),
+            tx: Some(tx),
+        }
+    }
+
+    pub fn report(&self, progress: usize, total: usize, message: &str) {
+        if let (Some(token), Some(tx)) = (&self.token, &self.tx) {
+            let _ = tx.send(ToolEvent::Progress(progress_notification(
+                token, progress, total, message,
+            )));
+        }
+    }
+}
+
+pub fn progress_notification(token: &Value, progress: usize, total: usize, message: &str) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "method": "notifications/progress",
+        "params": {
+            "progressToken": token,
+            "progress": progress,
+            "total": total,
+            "message": message
+        }
+    })
+}
+
+/// What the worker thread of a tool call sends back to the request loop.
+pub enum ToolEvent {
+    Progress(Value),
+    Done(Box<ConfigPaths>, Result<String>),
+}
+
+/// How long the server waits for `tool` before answering with a timeout error.
+pub fn tool_timeout(general: &GeneralPreferences, tool: &str) -> std::time::Duration {
+    let secs = if tool == "oauth_login" {
+        general
+            .oauth_login_timeout_secs
+            .unwrap_or(DEFAULT_OAUTH_LOGIN_TIMEOUT_SECS)
+    } else {
+        general
+            .mcp_tool_timeout_secs
+            .unwrap_or(DEFAULT_TOOL_TIMEOUT_SECS)
+    };
+    std::time::Duration::from_secs(secs)
+}
+
+/// Modification time and size of each file `ConfigPaths::new` reads.
+fn config_stamp(paths: &ConfigPaths) -> Vec<Option<(SystemTime, u64)>> {
+    [&paths.config_file, &paths.preferences.global_path]
+        .into_iter()
+        .map(|path| {
+            let meta = std::fs::metadata(path).ok()?;
+            Some((meta.modified().ok()?, meta.len()))
+        })
+        .collect()
+}
+
 pub fn run_mcp_server() -> Result<()> {
+    log::info!("Starting MooAgent MCP server");
+
+    // stdout carries the JSON-RPC stream, so panic output must only reach stderr and the log
+    std::panic::set_hook(Box::new(|info| {
+        log::error!("MCP server panicked: {}", info);
+        eprintln!("mooagent MCP server panicked: {}", info);
+    }));
+
     let stdin = std::io::stdin();
     let mut stdout = std::io::stdout();
     let reader = BufReader::new(stdin.lock());
+    // A broken config is reported by the first tool call instead of stopping the server
+    let state = match ConfigPaths::new() {
+        Ok(paths) => McpState::new(paths),
+        Err(e) => {
+            log::warn!("Failed to load configuration: {}", e);
+            McpState::default()
+        }
+    };
+    let mut state = state.with_notifier(|notification| {
+        let mut stdout = std::io::stdout();
+        if let Err(e) = writeln!(stdout, "{}", notification).and_then(|_| stdout.flush()) {
+            log::warn!("Failed to send notification: {}", e);
+        }
+    });
 
     for line in reader.lines() {
         let line = line?;
-        if line.is_empty() {
+        if line.trim().is_empty() {
             continue;
         }
 
-        let request: JsonRpcRequest = match serde_json::from_str(&line) {
-            Ok(r) => r,
-            Err(e) => {
-                let error_response = JsonRpcResponse {
-                    jsonrpc: "2.0".to_string(),
-                    id: Value::Null,
-                    result: None,
-                    error: Some(JsonRpcError {
-                        code: -32700,
-                        message: format!("Parse error: {}", e),
-                    }),
-                };
-                writeln!(stdout, "{}", serde_json::to_string(&error_response)?)?;
-                stdout.flush()?;
-                continue;
-            }
-        };
-
-        if let Some(response) = handle_request(&request) {
-            writeln!(stdout, "{}", serde_json::to_string(&response)?)?;
+        if let Some(response) = handle_message(&mut state, &line) {
+            writeln!(stdout, "{}", response)?;
             stdout.flush()?;
         }
     }
@@ -72,7 +266,67 @@ pub fn run_mcp_server() -> Result<()> {
     Ok(())
 }
 
-fn handle_request(request: &JsonRpcRequest) -> Option<JsonRpcResponse> {
+/// Handles one line of input, either a single request or a JSON-RPC batch array.
+/// Returns the serialized response, or `None` when nothing should be sent back.
+pub fn handle_message(state: &mut McpState, line: &str) -> Option<String> {
+    let message: Value = match serde_json::from_str(line) {
+        Ok(v) => v,
+        Err(e) => {
+            log::warn!("Failed to parse request: {}", e);
+            let response = error_response(Value::Null, -32700, format!("Parse error: {}", e));
+            return serde_json::to_string(&response).ok();
+        }
+    };
+
+    match message {
+        Value::Array(items) if items.is_empty() => {
+            let response = error_response(
+                Value::Null,
+                -32600,
+                "Invalid Request: empty batch".to_string(),
+            );
+            serde_json::to_string(&response).ok()
+        }
+        Value::Array(items) => {
+            let responses: Vec<JsonRpcResponse> = items
+                .into_iter()
+                .filter_map(|v| handle_value(state, v))
+                .collect();
+            if responses.is_empty() {
+                None
+            } else {
+                serde_json::to_string(&responses).ok()
+            }
+        }
+        single => handle_value(state, single).and_then(|r| serde_json::to_string(&r).ok()),
+    }
+}
+
+fn handle_value(state: &mut McpState, value: Value) -> Option<JsonRpcResponse> {
+    let id = value.get("id").cloned().unwrap_or(Value::Null);
+    match serde_json::from_value::<JsonRpcRequest>(value) {
+        Ok(request) => handle_request(state, &request),
+        Err(e) => {
+            log::warn!("Invalid request: {}", e);
+            Some(error_response(
+                id,
+                -32600,
+                format!("Invalid Request: {}", e),
+            ))
+        }
+    }
+}
+
+fn error_response(id: Value, code: i32, message: String) -> JsonRpcResponse {
+    JsonRpcResponse {
+        jsonrpc: "2.0".to_string(),
+        id,
+        result: None,
+        error: Some(JsonRpcError { code, message }),
+    }
+}
+
+fn handle_request(state: &mut McpState, request: &JsonRpcRequest) -> Option<JsonRpcResponse> {
     let id = request.id.clone().unwrap_or(Value::Null);
 
     match request.method.as_str() {
@@ -110,9 +364,18 @@ fn handle_request(request: &JsonRpcRequest) -> Option<JsonRpcResponse> {
                 .get("arguments")
                 .cloned()
                 .unwrap_or(json!({}));
+            let progress_token = request
+                .params
+                .get("_meta")
+                .and_then(|meta| meta.get("progressToken"))
+                .cloned();
+
+            if let Some(name) = tool_name {
+                log::info!("Calling tool '{}'", name);
+            }
 
             match tool_name {
-                Some(name) => match call_tool(name, arguments) {
+                Some(name) => match call_tool(state, name, arguments, progress_token) {
                     Ok(result) => Some(JsonRpcResponse {
                         jsonrpc: "2.0".to_string(),
                         id,
@@ -124,18 +387,25 @@ fn handle_request(request: &JsonRpcRequest) -> Option<JsonRpcResponse> {
                         })),
                         error: None,
                     }),
-                    Err(e) => Some(JsonRpcResponse {
-                        jsonrpc: "2.0".to_string(),
-                        id,
-                        result: Some(json!({
-                            "content": [{
-                                "type": "text",
-                                "text": format!("Error: {}", e)
-                            }],
-                            "isError": true
-                        })),
-                        error: None,
-                    }),
+                    Err(e) if e.is::<ToolTimeout>() => {
+                        log::warn!("{}", e);
+                        Some(error_response(id, -32000, e.to_string()))
+                    }
+                    Err(e) => {
+                        log::warn!("Tool '{}' failed: {}", name, e);
+                        Some(JsonRpcResponse {
+                            jsonrpc: "2.0".to_string(),
+                            id,
+                            result: Some(json!({
+                                "content": [{
+                                    "type": "text",
+                                    "text": format!("Error: {}", e)
+                                }],
+                                "isError": true
+                            })),
+                            error: None,
+                        })
+                    }
                 },
                 None => Some(JsonRpcResponse {
                     jsonrpc: "2.0".to_string(),
@@ -226,6 +496,13 @@ fn format_auth_status(
         McpAuth::Bearer { .. } => {
             result.push_str("  Auth: Bearer token (static)\n");
         }
+        McpAuth::BearerRef { key } => {
+            if credentials.get_token(key).is_some() {
+                result.push_str("  Auth: Bearer token (stored)\n");
+            } else {
+                result.push_str("  Auth: Bearer token (missing from credential store)\n");
+            }
+        }
         McpAuth::OAuth {
             client_id, scopes, ..
         } => {
@@ -249,11 +526,123 @@ fn format_auth_status(
     }
 }
 
+fn format_header_names(result: &mut String, headers: &HashMap<String, String>) {
+    if headers.is_empty() {
+        return;
+    }
+    let mut names: Vec<&str> = headers.keys().map(|k| k.as_str()).collect();
+    names.sort_unstable();
+    result.push_str(&format!("  Headers: {}\n", names.join(", ")));
+}
+
+/// Moves a project's override of server `old` and its disabled-list entries to `new`,
+/// returning whether anything changed.
+fn rename_project_server(project: &mut AgentPreferences, old: &str, new: &str) -> bool {
+    let mut renamed = false;
+    for name in project
+        .disabled_mcp_servers
+        .iter_mut()
+        .filter(|name| *name == old)
+    {
+        *name = new.to_string();
+        renamed = true;
+    }
+    if let Some(config) = project.mcp_servers.remove(old) {
+        project.mcp_servers.insert(new.to_string(), config);
+        renamed = true;
+    }
+    renamed
+}
+
+/// Tools that overwrite whole files or configs rather than editing them in place.
+pub fn is_destructive(name: &str, arguments: &Value) -> bool {
+    match name {
+        "set_global_rules" | "set_project_rules" => true,
+        "import_config" => arguments.get("replace").and_then(|v| v.as_bool()) == Some(true),
+        _ => false,
+    }
+}
+
+/// Refuses a destructive call unless `confirm` carries the token an earlier, identical call
+/// was given, so the client has to make a second call rather than confirm up front.
+/// Returns the message for a refused call, with a fresh token.
+fn require_confirmation(name: &str, arguments: &Value) -> Option<String> {
+    let mut call = arguments.clone();
+    let token = call
+        .as_object_mut()
+        .and_then(|args| args.remove("confirm"))
+        .and_then(|confirm| confirm.as_str().map(String::from));
+
+    let mut pending = PENDING_CONFIRMATIONS
+        .lock()
+        .unwrap_or_else(|e| e.into_inner());
+    pending.retain(|_, (_, _, issued)| issued.elapsed() < CONFIRMATION_TTL);
+    if let Some(token) = &token
+        && pending
+            .get(token)
+            .is_some_and(|(tool, args, _)| tool == name && *args == call)
+    {
+        pending.remove(token);
+        return None;
+    }
+
+    let new_token: String =
+        rand::Rng::sample_iter(rand::thread_rng(), &rand::distributions::Alphanumeric)
+            .take(16)
+            .map(char::from)
+            .collect();
+    pending.insert(new_token.clone(), (name.to_string(), call, Instant::now()));
+    let rejected = if token.is_some() {
+        "The confirmation token is unknown, expired, already used or for other arguments.\n\n"
+    } else {
+        ""
+    };
+    Some(format!(
+        "⚠️ '{}' overwrites existing content and requires confirmation.\n\n{}Call '{}' again with the same arguments plus \"confirm\": \"{}\" within {} minutes to proceed.",
+        name,
+        rejected,
+        name,
+        new_token,
+        CONFIRMATION_TTL.as_secs() / 60
+    ))
+}
+
+/// Tools that never write config or rules, so they can run beside a timed-out writer.
+fn is_read_only(name: &str) -> bool {
+    matches!(
+        name,
+        "mcp_list"
+            | "read_global_rules"
+            | "read_project_rules"
+            | "list_sections_global_rules"
+            | "list_sections_project_rules"
+            | "rules_stats"
+            | "sync_preview"
+            | "preferences_preview"
+            | "list_backups"
+            | "sync_status_json"
+            | "get_status"
+            | "test_mcp_server"
+            | "doctor"
+            | "oauth_status"
+            | "get_effective_config"
+            | "diff_config"
+    )
+}
+
+/// Names of the tools this server exposes, in listing order.
+pub fn tool_names() -> Vec<String> {
+    get_tools_list()
+        .iter()
+        .filter_map(|tool| tool["name"].as_str().map(String::from))
+        .collect()
+}
+
 fn get_tools_list() -> Vec<Value> {
     vec![
         json!({
             "name": "mcp_list",
-            "description": "List all configured MCP servers. These are global and sync to all agents (Claude, Gemini, OpenCode).",
+            "description": "List all configured MCP servers. These are global and sync to all agents (Claude, Gemini, OpenCode, Cursor).",
             "inputSchema": {
                 "type": "object",
                 "properties": {},
@@ -283,6 +672,19 @@ fn get_tools_list() -> Vec<Value> {
                         "type": "object",
                         "description": "Environment variables (only for local servers)"
                     },
+                    "headers": {
+                        "type": "object",
+                        "description": "Extra HTTP headers such as X-Api-Key (only for remote servers)"
+                    },
+                    "description": {
+                        "type": "string",
+                        "description": "Optional note on what the server is for (not synced to agents)"
+                    },
+                    "tags": {
+                        "type": "array",
+                        "items": { "type": "string" },
+                        "description": "Optional labels for grouping servers, e.g. work (not synced to agents)"
+                    },
                     "auth": {
                         "type": "object",
                         "description": "Authentication configuration for remote servers",
@@ -333,9 +735,27 @@ fn get_tools_list() -> Vec<Value> {
                 "required": ["name"]
             }
         }),
+        json!({
+            "name": "mcp_rename",
+            "description": "Rename an MCP server, keeping its whole config (env, auth, disabled tools, auto-allow). Its URL is unchanged, so stored OAuth/bearer credentials (keyed by URL) still apply.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "old_name": {
+                        "type": "string",
+                        "description": "Current name of the MCP server"
+                    },
+                    "new_name": {
+                        "type": "string",
+                        "description": "New name for the MCP server"
+                    }
+                },
+                "required": ["old_name", "new_name"]
+            }
+        }),
         json!({
             "name": "edit_global_rules",
-            "description": "Append content to GLOBAL_RULES.md. This file is synced to all agents' global config files.",
+            "description": "Append content to GLOBAL_RULES.md. This file is synced to all agents' global config files. Creates a backup first unless general.backup_before_edit is off.",
             "inputSchema": {
                 "type": "object",
                 "properties": {
@@ -349,7 +769,7 @@ fn get_tools_list() -> Vec<Value> {
         }),
         json!({
             "name": "edit_project_rules",
-            "description": "Append content to AGENTS.md in the current project. This file is synced to all agents' project-specific config files.",
+            "description": "Append content to AGENTS.md in the current project. This file is synced to all agents' project-specific config files. Creates a backup first unless general.backup_before_edit is off.",
             "inputSchema": {
                 "type": "object",
                 "properties": {
@@ -388,6 +808,10 @@ fn get_tools_list() -> Vec<Value> {
                     "content": {
                         "type": "string",
                         "description": "The new content to write to GLOBAL_RULES.md"
+                    },
+                    "confirm": {
+                        "type": "string",
+                        "description": "Token returned by the first call when general.require_confirmation_for_destructive is enabled"
                     }
                 },
                 "required": ["content"]
@@ -402,6 +826,10 @@ fn get_tools_list() -> Vec<Value> {
                     "content": {
                         "type": "string",
                         "description": "The new content to write to AGENTS.md"
+                    },
+                    "confirm": {
+                        "type": "string",
+                        "description": "Token returned by the first call when general.require_confirmation_for_destructive is enabled"
                     }
                 },
                 "required": ["content"]
@@ -409,7 +837,7 @@ fn get_tools_list() -> Vec<Value> {
         }),
         json!({
             "name": "edit_section_global_rules",
-            "description": "Edit a specific markdown section in GLOBAL_RULES.md by heading. Supports replace, append, prepend, or delete operations.",
+            "description": "Edit a specific markdown section in GLOBAL_RULES.md by heading. Supports replace, append, prepend, clear (keep heading, drop body), delete, or insert (create a new section, positioned with after/before, default end of file). Returns the section as it reads after the edit.",
             "inputSchema": {
                 "type": "object",
                 "properties": {
@@ -419,12 +847,24 @@ fn get_tools_list() -> Vec<Value> {
                     },
                     "content": {
                         "type": "string",
-                        "description": "The new content for the section (ignored for delete action)"
+                        "description": "The new content for the section (ignored for clear and delete actions)"
                     },
                     "action": {
                         "type": "string",
-                        "enum": ["replace", "append", "prepend", "delete"],
-                        "description": "The action to perform: replace (default), append, prepend, or delete"
+                        "enum": ["replace", "append", "prepend", "clear", "delete", "insert"],
+                        "description": "The action to perform: replace (default), append, prepend, clear, delete, or insert"
+                    },
+                    "after": {
+                        "type": "string",
+                        "description": "For insert: heading of the section to place the new one after"
+                    },
+                    "before": {
+                        "type": "string",
+                        "description": "For insert: heading of the section to place the new one before"
+                    },
+                    "recursive": {
+                        "type": "boolean",
+                        "description": "Include subsections in the edited section (default: true). When false, only the text between the heading and its first subheading is affected"
                     }
                 },
                 "required": ["section_heading"]
@@ -432,7 +872,7 @@ fn get_tools_list() -> Vec<Value> {
         }),
         json!({
             "name": "edit_section_project_rules",
-            "description": "Edit a specific markdown section in AGENTS.md by heading. Supports replace, append, prepend, or delete operations.",
+            "description": "Edit a specific markdown section in AGENTS.md by heading. Supports replace, append, prepend, clear (keep heading, drop body), delete, or insert (create a new section, positioned with after/before, default end of file). Returns the section as it reads after the edit.",
             "inputSchema": {
                 "type": "object",
                 "properties": {
@@ -442,12 +882,24 @@ fn get_tools_list() -> Vec<Value> {
                     },
                     "content": {
                         "type": "string",
-                        "description": "The new content for the section (ignored for delete action)"
+                        "description": "The new content for the section (ignored for clear and delete actions)"
                     },
                     "action": {
                         "type": "string",
-                        "enum": ["replace", "append", "prepend", "delete"],
-                        "description": "The action to perform: replace (default), append, prepend, or delete"
+                        "enum": ["replace", "append", "prepend", "clear", "delete", "insert"],
+                        "description": "The action to perform: replace (default), append, prepend, clear, delete, or insert"
+                    },
+                    "after": {
+                        "type": "string",
+                        "description": "For insert: heading of the section to place the new one after"
+                    },
+                    "before": {
+                        "type": "string",
+                        "description": "For insert: heading of the section to place the new one before"
+                    },
+                    "recursive": {
+                        "type": "boolean",
+                        "description": "Include subsections in the edited section (default: true). When false, only the text between the heading and its first subheading is affected"
                     }
                 },
                 "required": ["section_heading"]
@@ -471,6 +923,15 @@ fn get_tools_list() -> Vec<Value> {
                 "required": []
             }
         }),
+        json!({
+            "name": "rules_stats",
+            "description": "Report line, character, estimated token (chars/4), and section counts for GLOBAL_RULES.md and AGENTS.md. Useful for keeping rule files within model context budgets.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {},
+                "required": []
+            }
+        }),
         json!({
             "name": "sync",
             "description": "Sync all configurations to all agents. This includes rules and MCP servers.",
@@ -489,6 +950,39 @@ fn get_tools_list() -> Vec<Value> {
                 "required": []
             }
         }),
+        json!({
+            "name": "preferences_preview",
+            "description": "Preview how sync would change each agent's preferences files (settings, permissions, MCP servers) without writing anything. Reports the changed JSON keys per file.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {},
+                "required": []
+            }
+        }),
+        json!({
+            "name": "backup_now",
+            "description": "Back up the global rules and project AGENTS.md right now, without syncing. Returns the backup file paths.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {},
+                "required": []
+            }
+        }),
+        json!({
+            "name": "list_backups",
+            "description": "List timestamped backups of the global rules or project AGENTS.md, newest first.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "target": {
+                        "type": "string",
+                        "enum": ["global", "project"],
+                        "description": "Which rules file to list backups for"
+                    }
+                },
+                "required": ["target"]
+            }
+        }),
         json!({
             "name": "get_status",
             "description": "Get sync status for all agents.",
@@ -498,6 +992,24 @@ fn get_tools_list() -> Vec<Value> {
                 "required": []
             }
         }),
+        json!({
+            "name": "refresh",
+            "description": "Reload .mooagent.toml and preferences from disk, e.g. after editing them in the TUI. The server already reloads when it notices those files change; this forces it.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {},
+                "required": []
+            }
+        }),
+        json!({
+            "name": "sync_status_json",
+            "description": "Get sync status as JSON for programmatic polling: per-agent status (ok/drift/missing) and target path, agents with global rules drift, preference and MCP drift flags, and the MCP server count.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {},
+                "required": []
+            }
+        }),
         json!({
             "name": "bootstrap",
             "description": "Bootstrap mooagent MCP server to all agents. This adds mooagent itself as an MCP server and syncs, so all agents can use mooagent tools.",
@@ -516,11 +1028,29 @@ fn get_tools_list() -> Vec<Value> {
                     "name": {
                         "type": "string",
                         "description": "Name of the MCP server to test"
+                    },
+                    "timeout_secs": {
+                        "type": "integer",
+                        "description": "Request timeout for remote servers (default: general.mcp_test_timeout_secs, or 10)"
                     }
                 },
                 "required": ["name"]
             }
         }),
+        json!({
+            "name": "doctor",
+            "description": "Diagnose common setup problems: home and config directories, agent directories and drift, preference drift, the mooagent bootstrap, and reachability of every enabled MCP server. Returns a checklist with remediation hints.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "timeout_secs": {
+                        "type": "integer",
+                        "description": "Request timeout per remote server (default: general.mcp_test_timeout_secs, or 10)"
+                    }
+                },
+                "required": []
+            }
+        }),
         json!({
             "name": "oauth_status",
             "description": "Get OAuth authentication status for an MCP server.",
@@ -549,6 +1079,20 @@ fn get_tools_list() -> Vec<Value> {
                 "required": ["name"]
             }
         }),
+        json!({
+            "name": "oauth_refresh",
+            "description": "Refresh an expired OAuth token for an MCP server using its stored refresh token, without a browser login.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "name": {
+                        "type": "string",
+                        "description": "Name of the MCP server to refresh"
+                    }
+                },
+                "required": ["name"]
+            }
+        }),
         json!({
             "name": "oauth_logout",
             "description": "Remove stored OAuth token for an MCP server.",
@@ -565,13 +1109,45 @@ fn get_tools_list() -> Vec<Value> {
         }),
         json!({
             "name": "export_config",
-            "description": "Export all mooagent configuration (MCP servers, preferences, tool permissions) to JSON. Useful for backup or sharing.",
+            "description": "Export all mooagent configuration (MCP servers, preferences, tool permissions) to JSON. Useful for backup or sharing. Tokens, client secrets, header values and secret-looking env values are redacted unless include_secrets is true.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "include_secrets": {
+                        "type": "boolean",
+                        "description": "Keep secrets in the export, e.g. for a private backup (default: false)"
+                    }
+                },
+                "required": []
+            }
+        }),
+        json!({
+            "name": "get_effective_config",
+            "description": "Show what will actually be synced in the current project: global preferences merged with the project's .mooagent.toml. Returns the enabled MCP servers (secrets redacted), the expanded tool list, general settings with each agent's overrides applied, and tool permissions as JSON.",
             "inputSchema": {
                 "type": "object",
                 "properties": {},
                 "required": []
             }
         }),
+        json!({
+            "name": "diff_config",
+            "description": "Compare two mooagent configurations and list added, removed and changed MCP servers, presets and tool settings. Each side is an exported config JSON, or 'global' / 'project' for the current preferences.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "from": {
+                        "type": "string",
+                        "description": "Config JSON (as produced by export_config), 'global' or 'project'"
+                    },
+                    "to": {
+                        "type": "string",
+                        "description": "Config JSON (as produced by export_config), 'global' or 'project'"
+                    }
+                },
+                "required": ["from", "to"]
+            }
+        }),
         json!({
             "name": "import_config",
             "description": "Import mooagent configuration from JSON. Merges with existing config (use 'replace: true' to overwrite).",
@@ -585,6 +1161,10 @@ fn get_tools_list() -> Vec<Value> {
                     "replace": {
                         "type": "boolean",
                         "description": "If true, replace existing config instead of merging (default: false)"
+                    },
+                    "confirm": {
+                        "type": "string",
+                        "description": "Token returned by the first call with replace when general.require_confirmation_for_destructive is enabled"
                     }
                 },
                 "required": ["config"]
@@ -593,38 +1173,372 @@ fn get_tools_list() -> Vec<Value> {
     ]
 }
 
-fn edit_markdown_section(content: &str, heading: &str, new_content: &str, action: &str) -> Result<String> {
-    let lines: Vec<&str> = content.lines().collect();
-    let heading_level = heading.chars().take_while(|&c| c == '#').count();
-
-    if heading_level == 0 {
-        anyhow::bail!("Invalid heading format: must start with #");
+/// Level and text of an ATX heading line, ignoring an optional closing `#` sequence.
+fn parse_heading(line: &str) -> Option<(usize, &str)> {
+    let trimmed = line.trim();
+    let level = trimmed.chars().take_while(|&c| c == '#').count();
+    let rest = &trimmed[level..];
+    if !(1..=6).contains(&level) || !(rest.is_empty() || rest.starts_with(char::is_whitespace)) {
+        return None;
     }
+    Some((level, rest.trim().trim_end_matches('#').trim_end()))
+}
+
+/// `parse_heading` for each line, except that lines in ``` or ~~~ code fences (such as
+/// shell comments) are never headings.
+fn headings<'a>(lines: &[&'a str]) -> Vec<Option<(usize, &'a str)>> {
+    let mut fence: Option<&str> = None;
+    lines
+        .iter()
+        .map(|line| {
+            let trimmed = line.trim_start();
+            let marker = ["```", "~~~"].into_iter().find(|m| trimmed.starts_with(m));
+            match (fence, marker) {
+                (None, Some(marker)) => {
+                    fence = Some(marker);
+                    None
+                }
+                (Some(open), Some(marker)) if open == marker => {
+                    fence = None;
+                    None
+                }
+                (Some(_), _) => None,
+                (None, None) => parse_heading(line),
+            }
+        })
+        .collect()
+}
+
+/// Line range `[start, end)` of the section under `heading`. With `recursive` the range
+/// runs to the next heading of the same or higher level, taking subsections with it;
+/// otherwise it stops at the first heading of any level.
+/// The heading must match a whole heading at the same level; `## Build` does not match
+/// `## Build System`. Text is compared case-insensitively.
+fn find_section(lines: &[&str], heading: &str, recursive: bool) -> Result<Option<(usize, usize)>> {
+    let Some((heading_level, heading_text)) = parse_heading(heading) else {
+        anyhow::bail!("Invalid heading format: must start with # followed by a space");
+    };
+    let heading_text = heading_text.to_lowercase();
 
     let mut section_start: Option<usize> = None;
     let mut section_end: Option<usize> = None;
 
-    for (idx, line) in lines.iter().enumerate() {
-        let trimmed = line.trim();
-        if trimmed == heading || trimmed.starts_with(&format!("{} ", heading)) {
-            section_start = Some(idx);
+    for (idx, parsed) in headings(lines).into_iter().enumerate() {
+        let Some((level, text)) = parsed else {
+            continue;
+        };
+        if section_start.is_none() {
+            if level == heading_level && text.to_lowercase() == heading_text {
+                section_start = Some(idx);
+            }
             continue;
         }
 
-        if section_start.is_some() && section_end.is_none() {
-            let line_level = trimmed.chars().take_while(|&c| c == '#').count();
-            if line_level > 0 && line_level <= heading_level {
-                section_end = Some(idx);
-                break;
+        if !recursive || level <= heading_level {
+            section_end = Some(idx);
+            break;
+        }
+    }
+
+    Ok(section_start.map(|start| (start, section_end.unwrap_or(lines.len()))))
+}
+
+/// The text of the section under `heading`, including the heading line.
+pub fn read_markdown_section(
+    content: &str,
+    heading: &str,
+    recursive: bool,
+) -> Result<Option<String>> {
+    let lines: Vec<&str> = content.lines().collect();
+    Ok(find_section(&lines, heading, recursive)?
+        .map(|(start, end)| lines[start..end].join("\n").trim_end().to_string()))
+}
+
+/// Response for the edit_section tools: what happened plus the section as it now reads,
+/// so the caller can verify the edit without re-reading the whole file.
+fn section_edit_message(updated: &str, heading: &str, action: &str, recursive: bool) -> String {
+    let verb = match action {
+        "delete" => "deleted",
+        "clear" => "cleared",
+        "insert" => "inserted",
+        "append" => "appended",
+        "prepend" => "prepended",
+        _ => "replaced",
+    };
+    let mut message = format!(
+        "Section '{}' {}. Run 'sync' to propagate to all agents.",
+        heading, verb
+    );
+    let section = if action == "delete" {
+        None
+    } else {
+        read_markdown_section(updated, heading, recursive || action == "insert")
+            .ok()
+            .flatten()
+    };
+    if let Some(section) = section {
+        message.push_str("\n\nResulting section:\n");
+        message.push_str(&section);
+    }
+    message
+}
+
+/// Checks that a local server's command exists or a remote server answers, as used by
+/// `test_mcp_server` and `doctor`.
+pub fn check_mcp_server(
+    name: &str,
+    server: &McpServerConfig,
+    timeout_secs: u64,
+) -> Result<(CheckStatus, String)> {
+    let result = match server {
+        // Our own entry can be exercised for real, proving the bootstrap works end to end
+        McpServerConfig::Stdio {
+            command, args, env, ..
+        } if name == "mooagent" && crate::config::command_exists(command) => {
+            match stdio_handshake(command, args, env, timeout_secs) {
+                Ok(server_info) => (
+                    CheckStatus::Pass,
+                    format!(
+                        "Local server '{}': '{} {}' answered initialize ({})",
+                        name,
+                        command,
+                        args.join(" "),
+                        server_info
+                    ),
+                ),
+                Err(e) => (
+                    CheckStatus::Fail,
+                    format!(
+                        "Local server '{}': command '{}' found but the MCP handshake failed: {}",
+                        name, command, e
+                    ),
+                ),
             }
         }
+        McpServerConfig::Stdio { command, .. } => {
+            if crate::config::command_exists(command) {
+                (
+                    CheckStatus::Pass,
+                    format!(
+                        "Local server '{}': command '{}' found in PATH",
+                        name, command
+                    ),
+                )
+            } else {
+                (
+                    CheckStatus::Fail,
+                    format!(
+                        "Local server '{}': command '{}' NOT found in PATH",
+                        name, command
+                    ),
+                )
+            }
+        }
+        McpServerConfig::Sse { url, .. } | McpServerConfig::Http { http_url: url, .. } => {
+            let rt = tokio::runtime::Runtime::new()?;
+            match rt.block_on(probe_remote(url, timeout_secs)) {
+                Ok(probe) => {
+                    let via = if probe.final_url.trim_end_matches('/') == url.trim_end_matches('/')
+                    {
+                        probe.method.to_string()
+                    } else {
+                        format!("{}, redirected to {}", probe.method, probe.final_url)
+                    };
+                    if probe.status.is_success() {
+                        (
+                            CheckStatus::Pass,
+                            format!(
+                                "Remote server '{}' ({}) is reachable (HTTP {} via {})",
+                                name,
+                                url,
+                                probe.status.as_u16(),
+                                via
+                            ),
+                        )
+                    } else {
+                        (
+                            CheckStatus::Warn,
+                            format!(
+                                "Remote server '{}' ({}) responded with HTTP {} via {}",
+                                name,
+                                url,
+                                probe.status.as_u16(),
+                                via
+                            ),
+                        )
+                    }
+                }
+                Err(msg) => (
+                    CheckStatus::Fail,
+                    format!("Remote server '{}' ({}): {}", name, url, msg),
+                ),
+            }
+        }
+        McpServerConfig::Unknown(_) => (
+            CheckStatus::Fail,
+            format!(
+                "Server '{}' has an unrecognized config; fix it in preferences.toml",
+                name
+            ),
+        ),
+    };
+    Ok(result)
+}
+
+/// Starts a stdio MCP server, sends `initialize` and returns the `serverInfo` name and
+/// version it answers with. The child is killed afterwards, answered or not.
+pub fn stdio_handshake(
+    command: &str,
+    args: &[String],
+    env: &HashMap<String, String>,
+    timeout_secs: u64,
+) -> Result<String> {
+    use anyhow::Context;
+
+    let mut child = std::process::Command::new(command)
+        .args(args)
+        .envs(env)
+        .stdin(std::process::Stdio::piped())
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::null())
+        .spawn()
+        .with_context(|| format!("Could not start '{}'", command))?;
+
+    let request = json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": "initialize",
+        "params": {
+            "protocolVersion": "2024-11-05",
+            "capabilities": {},
+            "clientInfo": {
+                "name": "mooagent-test",
+                "version": env!("CARGO_PKG_VERSION")
+            }
+        }
+    });
+    let mut stdin = child.stdin.take().context("Child has no stdin")?;
+    let stdout = child.stdout.take().context("Child has no stdout")?;
+
+    let (tx, rx) = std::sync::mpsc::channel();
+    std::thread::spawn(move || {
+        let mut line = String::new();
+        let result = BufReader::new(stdout).read_line(&mut line).map(|_| line);
+        let _ = tx.send(result);
+    });
+    let sent = writeln!(stdin, "{}", request).and_then(|_| stdin.flush());
+    let reply = rx.recv_timeout(std::time::Duration::from_secs(timeout_secs));
+
+    let _ = child.kill();
+    let _ = child.wait();
+
+    let line = match reply {
+        Ok(line) => line.context("Could not read the initialize response")?,
+        Err(_) => {
+            sent.context("Could not send initialize")?;
+            anyhow::bail!("no response to initialize within {}s", timeout_secs)
+        }
+    };
+    if line.trim().is_empty() {
+        anyhow::bail!("exited without answering initialize");
+    }
+
+    let response: Value =
+        serde_json::from_str(line.trim()).context("Invalid initialize response")?;
+    if let Some(error) = response.get("error") {
+        anyhow::bail!("initialize returned an error: {}", error);
+    }
+    let info = &response["result"]["serverInfo"];
+    Ok(format!(
+        "{} {}",
+        info["name"].as_str().unwrap_or("unknown"),
+        info["version"].as_str().unwrap_or("?")
+    ))
+}
+
+/// Where `insert` places a new section.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SectionPosition<'a> {
+    End,
+    /// After the named section and all of its subsections.
+    After(&'a str),
+    /// Directly above the named section's heading.
+    Before(&'a str),
+}
+
+fn section_position(arguments: &Value) -> Result<SectionPosition<'_>> {
+    let after = arguments.get("after").and_then(|v| v.as_str());
+    let before = arguments.get("before").and_then(|v| v.as_str());
+    match (after, before) {
+        (Some(_), Some(_)) => anyhow::bail!("Pass only one of 'after' or 'before'"),
+        (Some(heading), None) => Ok(SectionPosition::After(heading)),
+        (None, Some(heading)) => Ok(SectionPosition::Before(heading)),
+        (None, None) => Ok(SectionPosition::End),
+    }
+}
+
+/// Adds a new section, separated from its neighbours by blank lines.
+pub fn insert_markdown_section(
+    content: &str,
+    heading: &str,
+    new_content: &str,
+    position: SectionPosition,
+) -> Result<String> {
+    let lines: Vec<&str> = content.lines().collect();
+    if find_section(&lines, heading, true)?.is_some() {
+        anyhow::bail!(
+            "Section '{}' already exists; use replace or append instead",
+            heading
+        );
     }
 
-    let Some(start) = section_start else {
-        anyhow::bail!("Section '{}' not found", heading);
+    let index = match position {
+        SectionPosition::End => lines.len(),
+        SectionPosition::After(anchor) | SectionPosition::Before(anchor) => {
+            let (start, end) = find_section(&lines, anchor, true)?
+                .ok_or_else(|| anyhow::anyhow!("Section '{}' not found", anchor))?;
+            if matches!(position, SectionPosition::After(_)) {
+                end
+            } else {
+                start
+            }
+        }
     };
 
-    let end = section_end.unwrap_or(lines.len());
+    // Trailing blank lines of the preceding section move below the new one
+    let mut split = index;
+    while split > 0 && lines[split - 1].trim().is_empty() {
+        split -= 1;
+    }
+
+    let mut result: Vec<&str> = lines[..split].to_vec();
+    if !result.is_empty() {
+        result.push("");
+    }
+    result.push(heading);
+    if !new_content.is_empty() {
+        result.extend(new_content.lines());
+    }
+    if index < lines.len() {
+        result.push("");
+        result.extend(lines[index..].iter());
+    }
+
+    Ok(result.join("\n"))
+}
+
+/// Applies `action` to the section under `heading`. When `recursive` is false only the
+/// text directly under the heading is affected and subsections are left in place.
+pub fn edit_markdown_section(
+    content: &str,
+    heading: &str,
+    new_content: &str,
+    action: &str,
+    recursive: bool,
+) -> Result<String> {
+    let lines: Vec<&str> = content.lines().collect();
+    let (start, end) = find_section(&lines, heading, recursive)?
+        .ok_or_else(|| anyhow::anyhow!("Section '{}' not found", heading))?;
 
     let before: Vec<&str> = lines[..start].to_vec();
     let section_content: Vec<&str> = lines[start..end].to_vec();
@@ -632,16 +1546,24 @@ fn edit_markdown_section(content: &str, heading: &str, new_content: &str, action
 
     let new_section = match action {
         "delete" => String::new(),
-        "replace" => format!("{}\n{}", heading, new_content),
+        // The heading line is kept as written, not as the case-insensitive `heading`
+        "clear" => format!("{}\n", section_content[0]),
+        "replace" => format!("{}\n{}", section_content[0], new_content),
         "append" => {
             let existing = section_content.join("\n");
             format!("{}\n\n{}", existing, new_content)
         }
         "prepend" => {
             let existing_body = section_content[1..].join("\n");
-            format!("{}\n{}\n\n{}", heading, new_content, existing_body)
+            format!(
+                "{}\n{}\n\n{}",
+                section_content[0], new_content, existing_body
+            )
         }
-        _ => anyhow::bail!("Unknown action: {}. Use replace, append, prepend, or delete", action),
+        _ => anyhow::bail!(
+            "Unknown action: {}. Use replace, append, prepend, clear, delete, or insert",
+            action
+        ),
     };
 
     let mut result = before.join("\n");
@@ -653,45 +1575,485 @@ fn edit_markdown_section(content: &str, heading: &str, new_content: &str, action
         if !result.is_empty() {
             result.push('\n');
         }
-        result.push_str(&after.join("\n"));
+        result.push_str(&after.join("\n"));
+    }
+
+    Ok(result)
+}
+
+pub fn list_markdown_sections(content: &str) -> Vec<(String, usize)> {
+    let lines: Vec<&str> = content.lines().collect();
+    headings(&lines)
+        .into_iter()
+        .enumerate()
+        .filter_map(|(line_num, parsed)| {
+            let (level, heading) = parsed?;
+            (!heading.is_empty())
+                .then(|| (format!("{} {}", "#".repeat(level), heading), line_num + 1))
+        })
+        .collect()
+}
+
+/// Size figures for a rule file, reported by `rules_stats`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RulesStats {
+    pub lines: usize,
+    pub chars: usize,
+    /// Rough chars/4 estimate; real tokenizers vary by model.
+    pub estimated_tokens: usize,
+    pub sections: usize,
+}
+
+impl RulesStats {
+    pub fn of(content: &str) -> Self {
+        let chars = content.chars().count();
+        Self {
+            lines: content.lines().count(),
+            chars,
+            estimated_tokens: chars.div_ceil(4),
+            sections: list_markdown_sections(content).len(),
+        }
+    }
+}
+
+/// What `sync` would change, as shown by the `sync_preview` tool and `mooagent sync --dry-run`.
+pub fn sync_preview(paths: &ConfigPaths) -> Result<String> {
+    let agents = paths.get_agents();
+    let mut result = String::from("## Sync Preview (Dry Run)\n\n");
+
+    let agents_needing_sync: Vec<_> = agents
+        .iter()
+        .filter(|a| a.status != crate::config::AgentStatus::Ok)
+        .collect();
+
+    if agents_needing_sync.is_empty() {
+        result.push_str("### Rules\n\nAll agents already in sync.\n\n");
+    } else {
+        result.push_str(&format!(
+            "### Rules\n\n{} agent(s) would be synced:\n\n",
+            agents_needing_sync.len()
+        ));
+        for agent in &agents_needing_sync {
+            let action = match agent.status {
+                crate::config::AgentStatus::Missing => "CREATE",
+                crate::config::AgentStatus::Drift => "UPDATE",
+                crate::config::AgentStatus::Ok => "SKIP",
+            };
+            let strategy = match agent.strategy {
+                crate::config::SyncStrategy::Merge => "merge",
+                crate::config::SyncStrategy::Symlink => "symlink",
+            };
+            result.push_str(&format!(
+                "- **{}** [{}]: {} ({})\n",
+                agent.name,
+                action,
+                agent.target_path.display(),
+                strategy
+            ));
+        }
+        result.push('\n');
+    }
+
+    result.push_str("### Preferences\n\n");
+    let mcp_count = paths.preferences.global_prefs.mcp_servers.len();
+    if mcp_count > 0 {
+        result.push_str(&format!("{} MCP server(s) configured.\n\n", mcp_count));
+    } else {
+        result.push_str("No MCP servers configured.\n\n");
+    }
+    result.push_str(&format_preference_changes(&paths.preview_preferences()?));
+
+    // `sync` refreshes these first, which may also change the files listed above
+    let mut credentials = CredentialManager::new(&paths.config_dir);
+    let _ = credentials.load();
+    let expired =
+        crate::config::expired_oauth_servers(&paths.preferences.get_merged(), &credentials);
+    if !expired.is_empty() {
+        result.push_str(&format!(
+            "\nExpired OAuth token(s) that `sync` refreshes first: {}\n",
+            expired.join(", ")
+        ));
+    }
+
+    result.push_str("\n---\n\nRun `sync` to apply these changes.");
+    Ok(result)
+}
+
+/// Appended to a sync result when `refresh_expired_oauth_tokens` renewed any tokens.
+pub fn refreshed_tokens_note(refreshed: &[String]) -> String {
+    if refreshed.is_empty() {
+        String::new()
+    } else {
+        format!(" Refreshed OAuth token(s) for {}.", refreshed.join(", "))
+    }
+}
+
+fn format_preference_changes(changes: &[crate::config::PreferenceFileChange]) -> String {
+    if changes.is_empty() {
+        return "All agent preference files already in sync.\n".to_string();
+    }
+
+    let mut result = format!("{} preference file(s) would change:\n\n", changes.len());
+    for change in changes {
+        result.push_str(&format!(
+            "- **{}** [{}]: {}\n",
+            change.agent,
+            if change.created { "CREATE" } else { "UPDATE" },
+            change.path.display()
+        ));
+        for key in &change.changed_keys {
+            result.push_str(&format!("    - `{}`\n", key));
+        }
+    }
+    result
+}
+
+/// Global preferences merged with the project's, as the sync would apply them.
+pub fn effective_config(paths: &ConfigPaths) -> Value {
+    let merged = paths.preferences.get_merged();
+
+    let mcp_servers: BTreeMap<&String, McpServerConfig> = merged
+        .mcp_servers
+        .iter()
+        .map(|(name, config)| (name, config.redacted()))
+        .collect();
+    let tools: BTreeMap<String, bool> = expand_tools(&merged).into_iter().collect();
+    let agent_general: BTreeMap<String, GeneralPreferences> = paths
+        .preference_agent_names()
+        .into_iter()
+        .map(|agent| {
+            let general = merged.general_for(&agent);
+            (agent, general)
+        })
+        .collect();
+
+    json!({
+        "project_preferences": paths.preferences.project_prefs.is_some(),
+        "mcp_servers": mcp_servers,
+        "disabled_mcp_servers": merged.disabled_mcp_servers,
+        "tools": tools,
+        "general": merged.general,
+        "agent_general": agent_general,
+        "tool_permissions": merged.tool_permissions,
+    })
+}
+
+/// `get_status` as JSON, for agents that poll the sync state.
+pub fn sync_status_json(paths: &ConfigPaths) -> Value {
+    let agents = paths.get_agents();
+    let preference_drift = agents
+        .iter()
+        .any(|a| a.sync_status.preferences == SyncState::Drift);
+    let mcp_drift = agents
+        .iter()
+        .any(|a| a.sync_status.mcp_servers == SyncState::Drift);
+
+    json!({
+        "agents": agents
+            .iter()
+            .map(|agent| json!({
+                "name": agent.name,
+                "status": agent.status,
+                "target_path": agent.target_path,
+                "strategy": agent.strategy,
+                "rules": agent.sync_status.rules,
+                "global_rules": agent.sync_status.global_rules,
+                "preferences": agent.sync_status.preferences,
+                "mcp_servers": agent.sync_status.mcp_servers,
+                "broken_link": agent.has_broken_link(),
+                "last_synced": agent.last_synced.map(|t| t.to_rfc3339()),
+            }))
+            .collect::<Vec<_>>(),
+        "global_rules_drift": paths.check_global_rules_drift(),
+        "preference_drift": preference_drift,
+        "mcp_drift": mcp_drift,
+        "global_rules": paths.global_rules_primary,
+        "project_rules": paths.project_agents,
+        "mcp_server_count": paths.preferences.global_prefs.mcp_servers.len(),
+    })
+}
+
+/// Resolves a `diff_config` side: the current global/project preferences, or export JSON,
+/// optionally still wrapped in the markdown fence `export_config` prints.
+fn parse_config_arg(paths: &ConfigPaths, value: &str) -> Result<AgentPreferences> {
+    match value.trim() {
+        "global" => Ok(paths.preferences.global_prefs.clone()),
+        "project" => paths
+            .preferences
+            .project_prefs
+            .clone()
+            .ok_or_else(|| anyhow::anyhow!("No project preferences (.mooagent.toml) found")),
+        text => {
+            let json_text = match text.split_once("```json") {
+                Some((_, rest)) => rest.split("```").next().unwrap_or(rest),
+                None => text,
+            };
+            serde_json::from_str(json_text)
+                .map_err(|e| anyhow::anyhow!("Invalid config JSON: {}", e))
+        }
+    }
+}
+
+fn diff_map<T: Serialize>(
+    section: &str,
+    old: &HashMap<String, T>,
+    new: &HashMap<String, T>,
+    lines: &mut Vec<String>,
+) {
+    let names: BTreeSet<&String> = old.keys().chain(new.keys()).collect();
+    let mut section_lines = Vec::new();
+
+    for name in names {
+        let old_value = old.get(name).and_then(|v| serde_json::to_value(v).ok());
+        let new_value = new.get(name).and_then(|v| serde_json::to_value(v).ok());
+        match (old_value, new_value) {
+            (None, Some(_)) => section_lines.push(format!("+ {}", name)),
+            (Some(_), None) => section_lines.push(format!("- {}", name)),
+            (Some(Value::Object(a)), Some(Value::Object(b))) if a != b => {
+                let fields: BTreeSet<&String> = a
+                    .keys()
+                    .chain(b.keys())
+                    .filter(|k| a.get(*k) != b.get(*k))
+                    .collect();
+                let fields: Vec<&str> = fields.into_iter().map(|f| f.as_str()).collect();
+                section_lines.push(format!("~ {} (changed: {})", name, fields.join(", ")));
+            }
+            (Some(a), Some(b)) if a != b => {
+                section_lines.push(format!("~ {}: {} -> {}", name, a, b));
+            }
+            _ => {}
+        }
+    }
+
+    if !section_lines.is_empty() {
+        lines.push(format!("### {}\n", section));
+        lines.extend(section_lines.into_iter().map(|l| format!("    {}", l)));
+        lines.push(String::new());
+    }
+}
+
+fn diff_list(section: &str, old: &[String], new: &[String], lines: &mut Vec<String>) {
+    let added: Vec<&String> = new.iter().filter(|v| !old.contains(v)).collect();
+    let removed: Vec<&String> = old.iter().filter(|v| !new.contains(v)).collect();
+    if added.is_empty() && removed.is_empty() {
+        return;
+    }
+
+    lines.push(format!("### {}\n", section));
+    lines.extend(added.into_iter().map(|v| format!("    + {}", v)));
+    lines.extend(removed.into_iter().map(|v| format!("    - {}", v)));
+    lines.push(String::new());
+}
+
+pub const DEFAULT_TEST_TIMEOUT_SECS: u64 = 10;
+const MAX_TEST_REDIRECTS: usize = 5;
+
+pub struct RemoteProbe {
+    /// The HTTP method that got a response.
+    pub method: &'static str,
+    pub status: reqwest::StatusCode,
+    /// Where the request ended up after following redirects.
+    pub final_url: String,
+}
+
+/// Pings a remote MCP endpoint with HEAD, retrying with GET when HEAD is rejected
+/// (405). Redirects are followed up to `MAX_TEST_REDIRECTS` hops.
+pub async fn probe_remote(url: &str, timeout_secs: u64) -> Result<RemoteProbe, String> {
+    let client = reqwest::Client::builder()
+        .timeout(std::time::Duration::from_secs(timeout_secs))
+        .redirect(reqwest::redirect::Policy::limited(MAX_TEST_REDIRECTS))
+        .build()
+        .map_err(|e| e.to_string())?;
+
+    let describe = |e: reqwest::Error| {
+        if e.is_timeout() {
+            format!("Connection timed out ({}s)", timeout_secs)
+        } else if e.is_redirect() {
+            format!(
+                "Redirect loop (gave up after {} redirects)",
+                MAX_TEST_REDIRECTS
+            )
+        } else if e.is_connect() {
+            "Connection refused".to_string()
+        } else {
+            e.to_string()
+        }
+    };
+
+    let response = client.head(url).send().await.map_err(describe)?;
+    let (method, response) = if response.status() == reqwest::StatusCode::METHOD_NOT_ALLOWED {
+        // Only the status line is needed; the body (possibly an SSE stream) is dropped unread
+        ("GET", client.get(url).send().await.map_err(describe)?)
+    } else {
+        ("HEAD", response)
+    };
+
+    Ok(RemoteProbe {
+        method,
+        status: response.status(),
+        final_url: response.url().to_string(),
+    })
+}
+
+/// Human-readable diff of the MCP servers, presets and tool settings of two configs.
+pub fn diff_preferences(old: &AgentPreferences, new: &AgentPreferences) -> String {
+    let mut lines = Vec::new();
+
+    diff_map(
+        "MCP servers",
+        &old.mcp_servers,
+        &new.mcp_servers,
+        &mut lines,
+    );
+    diff_list(
+        "Disabled MCP servers",
+        &old.disabled_mcp_servers,
+        &new.disabled_mcp_servers,
+        &mut lines,
+    );
+    diff_map(
+        "Tool presets",
+        &old.tool_presets,
+        &new.tool_presets,
+        &mut lines,
+    );
+    diff_map(
+        "Individual tools",
+        &old.individual_tools,
+        &new.individual_tools,
+        &mut lines,
+    );
+    diff_list(
+        "Allowed tools",
+        &old.tool_permissions.allow,
+        &new.tool_permissions.allow,
+        &mut lines,
+    );
+    diff_list(
+        "Ask tools",
+        &old.tool_permissions.ask,
+        &new.tool_permissions.ask,
+        &mut lines,
+    );
+    diff_list(
+        "Denied tools",
+        &old.tool_permissions.deny,
+        &new.tool_permissions.deny,
+        &mut lines,
+    );
+
+    if lines.is_empty() {
+        return "No differences in MCP servers, presets or tool settings.\n".to_string();
+    }
+    lines.join("\n")
+}
+
+/// Runs the tool on a worker thread so a hung network call can't stall the request loop.
+/// A call that times out keeps running in the background and its result is dropped; until
+/// it finishes, tools that write are refused.
+fn call_tool(
+    state: &mut McpState,
+    name: &str,
+    arguments: Value,
+    progress_token: Option<Value>,
+) -> Result<String> {
+    state.check_orphaned_writer(name)?;
+    let mut paths = state.take_paths()?;
+    let timeout = tool_timeout(&paths.preferences.get_merged().general, name);
+    let deadline = std::time::Instant::now() + timeout;
+
+    let (tx, rx) = std::sync::mpsc::channel();
+    let progress = progress_token
+        .map(|token| Progress::new(token, tx.clone()))
+        .unwrap_or_default();
+    let tool = name.to_string();
+    let worker = std::thread::spawn(move || {
+        let audit_arguments = arguments.clone();
+        let result = run_tool(&mut paths, &tool, arguments, &progress)
+            .map(|message| with_rules_size_warnings(&paths, &tool, message));
+
+        if let Err(e) = crate::audit::record(
+            &paths.audit_log_file,
+            &tool,
+            &audit_arguments,
+            result.is_ok(),
+        ) {
+            log::warn!("Failed to write audit log: {}", e);
+        }
+        let _ = tx.send(ToolEvent::Done(Box::new(paths), result));
+    });
+
+    loop {
+        let remaining = deadline.saturating_duration_since(std::time::Instant::now());
+        match rx.recv_timeout(remaining) {
+            Ok(ToolEvent::Progress(notification)) => state.notify(notification),
+            Ok(ToolEvent::Done(paths, result)) => {
+                // A failed tool may have changed the config partway, so it is reloaded instead
+                if result.is_ok() {
+                    state.restore(*paths);
+                }
+                return result;
+            }
+            Err(std::sync::mpsc::RecvTimeoutError::Timeout) => {
+                if !is_read_only(name) {
+                    state.orphaned = Some((name.to_string(), worker));
+                }
+                return Err(ToolTimeout {
+                    tool: name.to_string(),
+                    secs: timeout.as_secs(),
+                }
+                .into());
+            }
+            Err(std::sync::mpsc::RecvTimeoutError::Disconnected) => {
+                return Err(anyhow::anyhow!("Tool '{}' crashed; see mcp.log", name));
+            }
+        }
     }
-
-    Ok(result)
 }
 
-fn list_markdown_sections(content: &str) -> Vec<(String, usize)> {
-    content
-        .lines()
-        .enumerate()
-        .filter_map(|(line_num, line)| {
-            let trimmed = line.trim();
-            if trimmed.starts_with('#') {
-                let level = trimmed.chars().take_while(|&c| c == '#').count();
-                let heading = trimmed.trim_start_matches('#').trim();
-                if !heading.is_empty() {
-                    return Some((format!("{} {}", "#".repeat(level), heading), line_num + 1));
-                }
-            }
-            None
-        })
-        .collect()
+/// Whether the append tools back up the rules file first (`general.backup_before_edit`).
+fn backup_before_edit(paths: &ConfigPaths) -> bool {
+    paths
+        .preferences
+        .get_merged()
+        .general
+        .backup_before_edit
+        .unwrap_or(true)
 }
 
-fn backup_file(path: &std::path::Path, backup_dir: &std::path::Path) -> Result<()> {
-    if path.exists() {
-        let timestamp = Local::now().format("%Y%m%d_%H%M%S");
-        let filename = path.file_name().and_then(|n| n.to_str()).unwrap_or("unknown");
-        let backup_name = format!("{}_{}", filename, timestamp);
-        let backup = backup_dir.join(backup_name);
-        std::fs::create_dir_all(backup_dir)?;
-        std::fs::copy(path, &backup)?;
+/// Appends oversized-rules warnings to the result of tools that write or sync rules.
+fn with_rules_size_warnings(paths: &ConfigPaths, name: &str, message: String) -> String {
+    let writes_rules = matches!(
+        name,
+        "sync"
+            | "edit_global_rules"
+            | "edit_project_rules"
+            | "set_global_rules"
+            | "set_project_rules"
+            | "edit_section_global_rules"
+            | "edit_section_project_rules"
+    );
+    if !writes_rules {
+        return message;
     }
-    Ok(())
-}
 
-fn call_tool(name: &str, arguments: Value) -> Result<String> {
-    let mut paths = ConfigPaths::new()?;
+    let warnings = paths.oversized_rules();
+    if warnings.is_empty() {
+        return message;
+    }
+    let mut message = message;
+    for warning in warnings {
+        message.push_str(&format!("\n⚠️ {}", warning));
+    }
+    message
+}
 
+pub fn run_tool(
+    paths: &mut ConfigPaths,
+    name: &str,
+    arguments: Value,
+    progress: &Progress,
+) -> Result<String> {
     // Check permissions
     let merged = paths.preferences.get_merged();
     let enabled_tools = crate::preferences::expand_tools(&merged);
@@ -710,10 +2072,19 @@ fn call_tool(name: &str, arguments: Value) -> Result<String> {
     {
         return Err(anyhow::anyhow!(
             "Tool '{}' is disabled in mooagent MCP server config.\n\nTo enable: remove '{}' from disabled_tools in the mooagent MCP configuration.",
-            name, name
+            name,
+            name
         ));
     }
 
+    // 3. Destructive tools need an explicit second call when the interlock is on
+    if merged.general.require_confirmation_for_destructive == Some(true)
+        && is_destructive(name, &arguments)
+        && let Some(message) = require_confirmation(name, &arguments)
+    {
+        return Ok(message);
+    }
+
     match name {
         "mcp_list" => {
             let merged = paths.preferences.get_merged();
@@ -728,6 +2099,12 @@ fn call_tool(name: &str, arguments: Value) -> Result<String> {
             let mut result = String::from("Configured MCP servers (Effective):\n\n");
             for (name, config) in servers {
                 result.push_str(&format!("- **{}**\n", name));
+                if let Some(description) = config.description() {
+                    result.push_str(&format!("  Description: {}\n", description));
+                }
+                if !config.tags().is_empty() {
+                    result.push_str(&format!("  Tags: {}\n", config.tags().join(", ")));
+                }
                 match config {
                     McpServerConfig::Stdio { command, args, env, .. } => {
                         result.push_str("  Type: local (stdio)\n");
@@ -738,19 +2115,40 @@ fn call_tool(name: &str, arguments: Value) -> Result<String> {
                         if !env.is_empty() {
                             result.push_str("  Env:\n");
                             for (k, v) in env {
+                                let v = if crate::preferences::is_secret_key(k) {
+                                    "***"
+                                } else {
+                                    v
+                                };
                                 result.push_str(&format!("    {}={}\n", k, v));
                             }
                         }
                     }
-                    McpServerConfig::Sse { url, auth, .. } => {
+                    McpServerConfig::Sse {
+                        url, auth, headers, ..
+                    } => {
                         result.push_str("  Type: remote (SSE)\n");
                         result.push_str(&format!("  URL: {}\n", url));
                         format_auth_status(&mut result, url, auth, &credentials);
+                        format_header_names(&mut result, headers);
                     }
-                    McpServerConfig::Http { http_url, auth, .. } => {
+                    McpServerConfig::Http {
+                        http_url,
+                        auth,
+                        headers,
+                        ..
+                    } => {
                         result.push_str("  Type: remote (HTTP)\n");
                         result.push_str(&format!("  URL: {}\n", http_url));
                         format_auth_status(&mut result, http_url, auth, &credentials);
+                        format_header_names(&mut result, headers);
+                    }
+                    McpServerConfig::Unknown(raw) => {
+                        result.push_str("  Type: unrecognized config (not synced)\n");
+                        if let Some(fields) = raw.as_object() {
+                            let keys: Vec<&str> = fields.keys().map(|k| k.as_str()).collect();
+                            result.push_str(&format!("  Fields: {}\n", keys.join(", ")));
+                        }
                     }
                 }
                 result.push('\n');
@@ -767,16 +2165,43 @@ fn call_tool(name: &str, arguments: Value) -> Result<String> {
                 .get("command")
                 .and_then(|v| v.as_str())
                 .ok_or_else(|| anyhow::anyhow!("Missing 'command' argument"))?;
-
-            let mut paths = paths;
+            let description = arguments
+                .get("description")
+                .and_then(|v| v.as_str())
+                .map(str::trim)
+                .filter(|s| !s.is_empty())
+                .map(String::from);
+            let tags: Vec<String> = arguments
+                .get("tags")
+                .and_then(|v| v.as_array())
+                .map(|arr| {
+                    arr.iter()
+                        .filter_map(|v| v.as_str().map(|s| s.trim().to_string()))
+                        .filter(|s| !s.is_empty())
+                        .collect()
+                })
+                .unwrap_or_default();
 
             let config = if command.starts_with("http://") || command.starts_with("https://") {
                 let auth = parse_auth_config(&arguments);
+                let headers: HashMap<String, String> = arguments
+                    .get("headers")
+                    .and_then(|v| v.as_object())
+                    .map(|obj| {
+                        obj.iter()
+                            .filter_map(|(k, v)| v.as_str().map(|s| (k.clone(), s.to_string())))
+                            .collect()
+                    })
+                    .unwrap_or_default();
                 McpServerConfig::Sse {
                     url: command.to_string(),
                     auth,
+                    headers,
+                    headers_override_auth: false,
                     disabled_tools: Vec::new(),
                     auto_allow: false,
+                    description,
+                    tags,
                 }
             } else {
                 let args: Vec<String> = arguments
@@ -805,11 +2230,14 @@ fn call_tool(name: &str, arguments: Value) -> Result<String> {
                     env,
                     disabled_tools: Vec::new(),
                     auto_allow: false,
+                    description,
+                    tags,
                 }
             };
 
             let has_oauth = config.requires_oauth();
 
+            let _lock = paths.preferences.begin_global_update()?;
             paths
                 .preferences
                 .global_prefs
@@ -836,25 +2264,80 @@ fn call_tool(name: &str, arguments: Value) -> Result<String> {
                 .and_then(|v| v.as_str())
                 .ok_or_else(|| anyhow::anyhow!("Missing 'name' argument"))?;
 
-            let mut paths = paths;
-
-            if paths
-                .preferences
-                .global_prefs
-                .mcp_servers
-                .remove(name)
-                .is_some()
-            {
+            let _lock = paths.preferences.begin_global_update()?;
+            if let Some(removed) = paths.preferences.global_prefs.mcp_servers.remove(name) {
                 paths.preferences.save_global()?;
+
+                let mut credentials = CredentialManager::new(&paths.config_dir);
+                credentials.load()?;
+                let mut removed_tokens = false;
+                for key in crate::preferences::orphaned_credential_keys(
+                    &removed,
+                    &paths.preferences.global_prefs.mcp_servers,
+                ) {
+                    removed_tokens |= credentials.remove_token(key)?.is_some();
+                }
+
                 Ok(format!(
-                    "Removed MCP server '{}'. Run 'sync' to apply to all agents.",
-                    name
+                    "Removed MCP server '{}'{}. Run 'sync' to apply to all agents.",
+                    name,
+                    if removed_tokens {
+                        " and its stored credentials"
+                    } else {
+                        ""
+                    }
                 ))
             } else {
                 Ok(format!("MCP server '{}' not found.", name))
             }
         }
 
+        "mcp_rename" => {
+            let old_name = arguments
+                .get("old_name")
+                .and_then(|v| v.as_str())
+                .ok_or_else(|| anyhow::anyhow!("Missing 'old_name' argument"))?;
+            let new_name = arguments
+                .get("new_name")
+                .and_then(|v| v.as_str())
+                .ok_or_else(|| anyhow::anyhow!("Missing 'new_name' argument"))?;
+            if new_name.trim().is_empty() {
+                anyhow::bail!("'new_name' must not be empty");
+            }
+
+            let _lock = paths.preferences.begin_global_update()?;
+            let taken_in_project = paths
+                .preferences
+                .project_prefs
+                .as_ref()
+                .is_some_and(|project| project.mcp_servers.contains_key(new_name));
+            let servers = &mut paths.preferences.global_prefs.mcp_servers;
+            if servers.contains_key(new_name) || taken_in_project {
+                anyhow::bail!("MCP server '{}' already exists", new_name);
+            }
+            let config = servers
+                .remove(old_name)
+                .ok_or_else(|| anyhow::anyhow!("MCP server '{}' not found", old_name))?;
+            servers.insert(new_name.to_string(), config);
+            paths.preferences.save_global()?;
+
+            // Keep this project's override and disabled entry attached to the server
+            if let Some(project) = &mut paths.preferences.project_prefs
+                && rename_project_server(project, old_name, new_name)
+            {
+                paths.preferences.save_project(&paths.config_file)?;
+            }
+
+            Ok(format!(
+                "Renamed MCP server '{old}' to '{new}'. Run 'sync' to apply to all agents.\n\
+                 ⚠️ Only this project's .mooagent.toml was updated. Other projects that disable \
+                 or override '{old}' still use the old name; change it to '{new}' there, or \
+                 the server will be re-enabled for them.",
+                old = old_name,
+                new = new_name
+            ))
+        }
+
         "edit_global_rules" => {
             let content = arguments
                 .get("content")
@@ -873,7 +2356,10 @@ fn call_tool(name: &str, arguments: Value) -> Result<String> {
                 format!("{}\n\n{}", current.trim_end(), content)
             };
 
-            std::fs::write(&paths.global_rules_primary, new_content)?;
+            if backup_before_edit(paths) {
+                paths.backup_if_needed(&paths.global_rules_primary)?;
+            }
+            write_atomic(&paths.global_rules_primary, new_content)?;
             Ok("Updated GLOBAL_RULES.md. Run 'sync' to propagate to all agents.".to_string())
         }
 
@@ -895,7 +2381,10 @@ fn call_tool(name: &str, arguments: Value) -> Result<String> {
                 format!("{}\n\n{}", current.trim_end(), content)
             };
 
-            std::fs::write(&paths.project_agents, new_content)?;
+            if backup_before_edit(paths) {
+                paths.backup_if_needed(&paths.project_agents)?;
+            }
+            write_atomic(&paths.project_agents, new_content)?;
             Ok("Updated AGENTS.md. Run 'sync' to propagate to all agents.".to_string())
         }
 
@@ -931,8 +2420,8 @@ fn call_tool(name: &str, arguments: Value) -> Result<String> {
                 .and_then(|v| v.as_str())
                 .ok_or_else(|| anyhow::anyhow!("Missing 'content' argument"))?;
 
-            backup_file(&paths.global_rules_primary, &paths.backup_dir)?;
-            std::fs::write(&paths.global_rules_primary, content)?;
+            paths.backup_if_needed(&paths.global_rules_primary)?;
+            write_atomic(&paths.global_rules_primary, content)?;
             Ok("Replaced GLOBAL_RULES.md content. Run 'sync' to propagate to all agents.".to_string())
         }
 
@@ -942,8 +2431,8 @@ fn call_tool(name: &str, arguments: Value) -> Result<String> {
                 .and_then(|v| v.as_str())
                 .ok_or_else(|| anyhow::anyhow!("Missing 'content' argument"))?;
 
-            backup_file(&paths.project_agents, &paths.backup_dir)?;
-            std::fs::write(&paths.project_agents, content)?;
+            paths.backup_if_needed(&paths.project_agents)?;
+            write_atomic(&paths.project_agents, content)?;
             Ok("Replaced AGENTS.md content. Run 'sync' to propagate to all agents.".to_string())
         }
 
@@ -963,26 +2452,27 @@ fn call_tool(name: &str, arguments: Value) -> Result<String> {
                 .and_then(|v| v.as_str())
                 .unwrap_or("replace");
 
+            let recursive = arguments
+                .get("recursive")
+                .and_then(|v| v.as_bool())
+                .unwrap_or(true);
+
             if !paths.global_rules_primary.exists() {
                 anyhow::bail!("GLOBAL_RULES.md does not exist");
             }
 
             let current = std::fs::read_to_string(&paths.global_rules_primary)?;
-            let updated = edit_markdown_section(&current, section_heading, content, action)?;
-
-            backup_file(&paths.global_rules_primary, &paths.backup_dir)?;
-            std::fs::write(&paths.global_rules_primary, updated)?;
+            let updated = if action == "insert" {
+                let position = section_position(&arguments)?;
+                insert_markdown_section(&current, section_heading, content, position)?
+            } else {
+                edit_markdown_section(&current, section_heading, content, action, recursive)?
+            };
 
-            Ok(format!(
-                "Section '{}' {}. Run 'sync' to propagate to all agents.",
-                section_heading,
-                match action {
-                    "delete" => "deleted",
-                    "append" => "appended",
-                    "prepend" => "prepended",
-                    _ => "replaced",
-                }
-            ))
+            paths.backup_if_needed(&paths.global_rules_primary)?;
+            let message = section_edit_message(&updated, section_heading, action, recursive);
+            write_atomic(&paths.global_rules_primary, updated)?;
+            Ok(message)
         }
 
         "edit_section_project_rules" => {
@@ -1001,26 +2491,27 @@ fn call_tool(name: &str, arguments: Value) -> Result<String> {
                 .and_then(|v| v.as_str())
                 .unwrap_or("replace");
 
+            let recursive = arguments
+                .get("recursive")
+                .and_then(|v| v.as_bool())
+                .unwrap_or(true);
+
             if !paths.project_agents.exists() {
                 anyhow::bail!("AGENTS.md does not exist in current project");
             }
 
             let current = std::fs::read_to_string(&paths.project_agents)?;
-            let updated = edit_markdown_section(&current, section_heading, content, action)?;
-
-            backup_file(&paths.project_agents, &paths.backup_dir)?;
-            std::fs::write(&paths.project_agents, updated)?;
+            let updated = if action == "insert" {
+                let position = section_position(&arguments)?;
+                insert_markdown_section(&current, section_heading, content, position)?
+            } else {
+                edit_markdown_section(&current, section_heading, content, action, recursive)?
+            };
 
-            Ok(format!(
-                "Section '{}' {}. Run 'sync' to propagate to all agents.",
-                section_heading,
-                match action {
-                    "delete" => "deleted",
-                    "append" => "appended",
-                    "prepend" => "prepended",
-                    _ => "replaced",
-                }
-            ))
+            paths.backup_if_needed(&paths.project_agents)?;
+            let message = section_edit_message(&updated, section_heading, action, recursive);
+            write_atomic(&paths.project_agents, updated)?;
+            Ok(message)
         }
 
         "list_sections_global_rules" => {
@@ -1061,73 +2552,121 @@ fn call_tool(name: &str, arguments: Value) -> Result<String> {
             Ok(result)
         }
 
+        "rules_stats" => {
+            let mut result = String::from("Rule file sizes:\n\n");
+            for (label, path) in [
+                ("GLOBAL_RULES.md", &paths.global_rules_primary),
+                ("AGENTS.md", &paths.project_agents),
+            ] {
+                if !path.exists() {
+                    result.push_str(&format!("{}: does not exist\n", label));
+                    continue;
+                }
+                let stats = RulesStats::of(&std::fs::read_to_string(path)?);
+                result.push_str(&format!(
+                    "{} ({}):\n  Lines: {}\n  Characters: {}\n  Estimated tokens: ~{}\n  Sections: {}\n",
+                    label,
+                    path.display(),
+                    stats.lines,
+                    stats.chars,
+                    stats.estimated_tokens,
+                    stats.sections
+                ));
+            }
+            Ok(result)
+        }
+
         "sync" => {
+            progress.report(0, 4, "Syncing project rules");
             let rules_result = paths.sync();
+            progress.report(1, 4, "Syncing global rules");
             let global_result = paths.sync_global_rules();
+            progress.report(2, 4, "Refreshing expired OAuth tokens");
+            let refreshed = paths.refresh_expired_oauth_tokens();
+            progress.report(3, 4, "Syncing preferences");
             let prefs_result = paths.sync_preferences();
+            progress.report(4, 4, "Sync finished");
 
             match (&rules_result, &global_result, &prefs_result) {
-                (Ok(rules_msg), Ok(_), Ok(prefs_msg)) => {
-                    Ok(format!("{}\nGlobal rules synced.\n{}", rules_msg, prefs_msg))
-                }
+                (Ok(rules_msg), Ok(_), Ok(prefs_msg)) => Ok(format!(
+                    "{}\nGlobal rules synced.\n{}{}",
+                    rules_msg,
+                    prefs_msg,
+                    refreshed_tokens_note(&refreshed)
+                )),
                 (Err(e), _, _) => Err(anyhow::anyhow!("Rules sync failed: {}", e)),
                 (_, Err(e), _) => Err(anyhow::anyhow!("Global rules sync failed: {}", e)),
                 (_, _, Err(e)) => Err(anyhow::anyhow!("Preferences sync failed: {}", e)),
             }
         }
 
-        "sync_preview" => {
-            let agents = paths.get_agents();
-            let mut result = String::from("## Sync Preview (Dry Run)\n\n");
+        "sync_preview" => sync_preview(paths),
 
-            let agents_needing_sync: Vec<_> = agents
-                .iter()
-                .filter(|a| a.status != crate::config::AgentStatus::Ok)
-                .collect();
+        "preferences_preview" => {
+            let changes = paths.preview_preferences()?;
+            let mut result = String::from("## Preferences Preview (Dry Run)\n\n");
+            result.push_str(&format_preference_changes(&changes));
+            if !changes.is_empty() {
+                result.push_str("\n---\n\nRun `sync` to apply these changes.");
+            }
+            Ok(result)
+        }
 
-            if agents_needing_sync.is_empty() {
-                result.push_str("### Rules\n\nAll agents already in sync.\n\n");
-            } else {
-                result.push_str(&format!(
-                    "### Rules\n\n{} agent(s) would be synced:\n\n",
-                    agents_needing_sync.len()
-                ));
-                for agent in &agents_needing_sync {
-                    let action = match agent.status {
-                        crate::config::AgentStatus::Missing => "CREATE",
-                        crate::config::AgentStatus::Drift => "UPDATE",
-                        crate::config::AgentStatus::Ok => "SKIP",
-                    };
-                    let strategy = match agent.strategy {
-                        crate::config::SyncStrategy::Merge => "merge",
-                        crate::config::SyncStrategy::Symlink => "symlink",
-                    };
-                    result.push_str(&format!(
-                        "- **{}** [{}]: {} ({})\n",
-                        agent.name,
-                        action,
-                        agent.target_path.display(),
-                        strategy
-                    ));
-                }
-                result.push('\n');
+        "backup_now" => {
+            let backups = paths.backup_now()?;
+            if backups.is_empty() {
+                return Ok("Nothing to back up: no rule files exist yet.".to_string());
             }
 
-            result.push_str("### Preferences\n\n");
-            let mcp_count = paths.preferences.global_prefs.mcp_servers.len();
-            if mcp_count > 0 {
-                result.push_str(&format!(
-                    "{} MCP server(s) would be synced to agent configs.\n",
-                    mcp_count
-                ));
-            } else {
-                result.push_str("No MCP servers configured.\n");
+            let mut result = format!("Created {} backup(s):\n", backups.len());
+            for backup in backups {
+                result.push_str(&format!("- {}\n", backup.display()));
+            }
+            Ok(result)
+        }
+
+        "list_backups" => {
+            let target = arguments
+                .get("target")
+                .and_then(|v| v.as_str())
+                .ok_or_else(|| anyhow::anyhow!("Missing 'target' argument"))?;
+            let path = match target {
+                "global" => &paths.global_rules_primary,
+                "project" => &paths.project_agents,
+                other => anyhow::bail!(
+                    "Unknown target '{}' (expected 'global' or 'project')",
+                    other
+                ),
+            };
+
+            let backups = paths.list_backups_for(path);
+            if backups.is_empty() {
+                return Ok(format!("No backups found for {}.", path.display()));
             }
 
-            result.push_str("\n---\n\nRun `sync` to apply these changes.");
+            let mut result = format!("## Backups of {}\n\n", path.display());
+            for backup in backups {
+                result.push_str(&format!("- {}\n", backup.display()));
+            }
             Ok(result)
         }
 
+        "sync_status_json" => Ok(serde_json::to_string_pretty(&sync_status_json(paths))?),
+
+        "refresh" => {
+            *paths = paths.reload()?;
+            Ok(format!(
+                "Reloaded configuration for {}: {} agent(s), {} MCP server(s).",
+                paths
+                    .project_agents
+                    .parent()
+                    .unwrap_or(&paths.project_agents)
+                    .display(),
+                paths.agent_configs.len(),
+                paths.preferences.get_merged().mcp_servers.len()
+            ))
+        }
+
         "get_status" => {
             let agents = paths.get_agents();
             let mut result = String::from("Agent Status:\n\n");
@@ -1163,44 +2702,13 @@ fn call_tool(name: &str, arguments: Value) -> Result<String> {
         }
 
         "bootstrap" => {
-            let installed_path = dirs::home_dir()
-                .map(|h| h.join(".local/bin/mooagent"))
-                .filter(|p| p.exists());
-
-            let mooagent_path =
-                installed_path.unwrap_or_else(|| std::env::current_exe().unwrap_or_default());
-
-            if !mooagent_path.exists() {
-                return Err(anyhow::anyhow!(
-                    "mooagent binary not found. Run 'just install' first to install to ~/.local/bin/"
-                ));
-            }
-
-            let mut paths = paths;
-
-            if paths
-                .preferences
-                .global_prefs
-                .mcp_servers
-                .contains_key("mooagent")
-            {
+            let _lock = paths.preferences.begin_global_update()?;
+            let Some(mooagent_path) = paths.add_mooagent_mcp()? else {
                 return Ok(
                     "mooagent MCP is already configured. Run 'sync' if you need to update agents."
                         .to_string(),
                 );
-            }
-
-            paths.preferences.global_prefs.mcp_servers.insert(
-                "mooagent".to_string(),
-                McpServerConfig::Stdio {
-                    command: mooagent_path.to_string_lossy().to_string(),
-                    args: vec!["--mcp".to_string()],
-                    env: HashMap::new(),
-                    disabled_tools: Vec::new(),
-                    auto_allow: false,
-                },
-            );
-            paths.preferences.save_global()?;
+            };
 
             let rules_result = paths.sync();
             let prefs_result = paths.sync_preferences();
@@ -1211,15 +2719,16 @@ fn call_tool(name: &str, arguments: Value) -> Result<String> {
                      Added: {}\n\
                      Synced to all agents.\n\n\
                      All agents now have access to mooagent tools:\n\
-                     - mcp_list, mcp_add, mcp_remove\n\
+                     - mcp_list, mcp_add, mcp_remove, mcp_rename\n\
                      - read_global_rules, read_project_rules\n\
                      - edit_global_rules, edit_project_rules (append)\n\
                      - set_global_rules, set_project_rules (replace)\n\
                      - edit_section_global_rules, edit_section_project_rules\n\
-                     - list_sections_global_rules, list_sections_project_rules\n\
-                     - sync, sync_preview, get_status, bootstrap\n\
-                     - test_mcp_server, oauth_status, oauth_login, oauth_logout\n\
-                     - export_config, import_config",
+                     - list_sections_global_rules, list_sections_project_rules, rules_stats\n\
+                     - sync, sync_preview, preferences_preview, get_status, sync_status_json, refresh, bootstrap\n\
+                     - backup_now, list_backups\n\
+                     - test_mcp_server, doctor, oauth_status, oauth_login, oauth_refresh, oauth_logout\n\
+                     - export_config, import_config, diff_config, get_effective_config",
                     mooagent_path.display()
                 )),
                 (Err(e), _) => Err(anyhow::anyhow!(
@@ -1247,74 +2756,27 @@ fn call_tool(name: &str, arguments: Value) -> Result<String> {
                 .ok_or_else(|| anyhow::anyhow!("MCP server '{}' not found.\n\nTo add: use 'mcp_add' tool or run mooagent TUI (press 3 for MCP tab, then 'a' to add).", name))?
                 .clone();
 
-            match &server {
-                McpServerConfig::Stdio { command, .. } => {
-                    let cmd_exists = std::process::Command::new("which")
-                        .arg(command)
-                        .output()
-                        .map(|o| o.status.success())
-                        .unwrap_or(false);
-
-                    if cmd_exists {
-                        Ok(format!(
-                            "✅ Local server '{}': command '{}' found in PATH",
-                            name, command
-                        ))
-                    } else {
-                        Ok(format!(
-                            "❌ Local server '{}': command '{}' NOT found in PATH",
-                            name, command
-                        ))
-                    }
-                }
-                McpServerConfig::Sse { url, .. } | McpServerConfig::Http { http_url: url, .. } => {
-                    let rt = tokio::runtime::Runtime::new()?;
-                    let url_clone = url.clone();
-                    let result: Result<(reqwest::StatusCode, Option<String>), String> = rt.block_on(async {
-                        let client = reqwest::Client::builder()
-                            .timeout(std::time::Duration::from_secs(10))
-                            .build()
-                            .map_err(|e| e.to_string())?;
-
-                        match client.head(&url_clone).send().await {
-                            Ok(resp) => Ok((resp.status(), None)),
-                            Err(e) => {
-                                if e.is_timeout() {
-                                    Err("Connection timed out (10s)".to_string())
-                                } else if e.is_connect() {
-                                    Err("Connection refused".to_string())
-                                } else {
-                                    Ok((reqwest::StatusCode::default(), Some(e.to_string())))
-                                }
-                            }
-                        }
-                    });
-
-                    match result {
-                        Ok((status, None)) => {
-                            if status.is_success() || status.is_redirection() {
-                                Ok(format!(
-                                    "✅ Remote server '{}' ({}) is reachable (HTTP {})",
-                                    name, url, status.as_u16()
-                                ))
-                            } else {
-                                Ok(format!(
-                                    "⚠️ Remote server '{}' ({}) responded with HTTP {}",
-                                    name, url, status.as_u16()
-                                ))
-                            }
-                        }
-                        Ok((_, Some(err))) => Ok(format!(
-                            "❌ Remote server '{}' ({}): {}",
-                            name, url, err
-                        )),
-                        Err(msg) => Ok(format!(
-                            "❌ Remote server '{}' ({}): {}",
-                            name, url, msg
-                        )),
-                    }
-                }
-            }
+            let timeout_secs = arguments
+                .get("timeout_secs")
+                .and_then(|v| v.as_u64())
+                .or(paths.preferences.get_merged().general.mcp_test_timeout_secs)
+                .unwrap_or(DEFAULT_TEST_TIMEOUT_SECS);
+
+            let (status, message) = check_mcp_server(name, &server, timeout_secs)?;
+            Ok(format!("{} {}", status.symbol(), message))
+        }
+
+        "doctor" => {
+            let timeout_secs = arguments
+                .get("timeout_secs")
+                .and_then(|v| v.as_u64())
+                .or(paths.preferences.get_merged().general.mcp_test_timeout_secs)
+                .unwrap_or(DEFAULT_TEST_TIMEOUT_SECS);
+            let checks =
+                crate::doctor::run_with_progress(paths, timeout_secs, |done, total, name| {
+                    progress.report(done, total, &format!("Tested MCP server '{}'", name))
+                });
+            Ok(crate::doctor::format_report(&checks))
         }
 
         "oauth_status" => {
@@ -1339,6 +2801,12 @@ fn call_tool(name: &str, arguments: Value) -> Result<String> {
                         name
                     ));
                 }
+                McpServerConfig::Unknown(_) => {
+                    return Ok(format!(
+                        "MCP server '{}' has an unrecognized config - fix it in preferences.toml first.",
+                        name
+                    ));
+                }
             };
 
             match auth {
@@ -1346,7 +2814,7 @@ fn call_tool(name: &str, arguments: Value) -> Result<String> {
                     "MCP server '{}' has no authentication configured.",
                     name
                 )),
-                McpAuth::Bearer { .. } => {
+                McpAuth::Bearer { .. } | McpAuth::BearerRef { .. } => {
                     Ok(format!("MCP server '{}' uses a static bearer token.", name))
                 }
                 McpAuth::OAuth {
@@ -1361,16 +2829,44 @@ fn call_tool(name: &str, arguments: Value) -> Result<String> {
                     if !scopes.is_empty() {
                         result.push_str(&format!("  Scopes: {}\n", scopes.join(" ")));
                     }
-                    result.push_str(&format!(
-                        "  Status: {} {}\n",
-                        status.symbol(),
-                        status.description()
-                    ));
+                    let refreshable = credentials.refresh_token(url).is_some();
+                    if status == TokenStatus::Expired && refreshable {
+                        result.push_str(&format!(
+                            "  Status: {} Expired (refreshable)\n",
+                            status.symbol()
+                        ));
+                    } else {
+                        result.push_str(&format!(
+                            "  Status: {} {}\n",
+                            status.symbol(),
+                            status.description()
+                        ));
+                    }
 
-                    if let Some(token) = credentials.get_token(url)
-                        && let Some(expires) = token.expires_at
-                    {
-                        result.push_str(&format!("  Expires: {}\n", expires));
+                    if let Some(token) = credentials.get_token(url) {
+                        if let Some(expires) = token.expires_at {
+                            result.push_str(&format!("  Expires: {}\n", expires));
+                        }
+                        result.push_str(&format!(
+                            "  Refresh token: {}\n",
+                            if refreshable { "present" } else { "none" }
+                        ));
+                    }
+
+                    match status {
+                        TokenStatus::Expired | TokenStatus::ExpiresSoon if refreshable => {
+                            result.push_str(&format!(
+                                "\nRun 'oauth_refresh' with name '{}' to renew the token without logging in again.\n",
+                                name
+                            ));
+                        }
+                        TokenStatus::Expired | TokenStatus::None => {
+                            result.push_str(&format!(
+                                "\nRun 'oauth_login' with name '{}' to authenticate.\n",
+                                name
+                            ));
+                        }
+                        _ => {}
                     }
 
                     Ok(result)
@@ -1401,6 +2897,12 @@ fn call_tool(name: &str, arguments: Value) -> Result<String> {
                         name
                     ));
                 }
+                McpServerConfig::Unknown(_) => {
+                    return Err(anyhow::anyhow!(
+                        "MCP server '{}' has an unrecognized config - fix it in preferences.toml first.",
+                        name
+                    ));
+                }
             };
 
             match auth {
@@ -1438,13 +2940,92 @@ fn call_tool(name: &str, arguments: Value) -> Result<String> {
                     "MCP server '{}' has no authentication configured. Add OAuth config first.",
                     name
                 )),
-                McpAuth::Bearer { .. } => Err(anyhow::anyhow!(
+                McpAuth::Bearer { .. } | McpAuth::BearerRef { .. } => Err(anyhow::anyhow!(
                     "MCP server '{}' uses a static bearer token - no login needed.",
                     name
                 )),
             }
         }
 
+        "oauth_refresh" => {
+            let name = arguments
+                .get("name")
+                .and_then(|v| v.as_str())
+                .ok_or_else(|| anyhow::anyhow!("Missing 'name' argument"))?;
+
+            let server = paths
+                .preferences
+                .global_prefs
+                .mcp_servers
+                .get(name)
+                .ok_or_else(|| anyhow::anyhow!("MCP server '{}' not found.\n\nTo add: use 'mcp_add' tool or run mooagent TUI (press 3 for MCP tab, then 'a' to add).", name))?
+                .clone();
+
+            let (url, auth) = match &server {
+                McpServerConfig::Sse { url, auth, .. } => (url.clone(), auth.clone()),
+                McpServerConfig::Http { http_url, auth, .. } => (http_url.clone(), auth.clone()),
+                McpServerConfig::Stdio { .. } => {
+                    return Err(anyhow::anyhow!(
+                        "MCP server '{}' is a local (stdio) server - OAuth not applicable.",
+                        name
+                    ));
+                }
+                McpServerConfig::Unknown(_) => {
+                    return Err(anyhow::anyhow!(
+                        "MCP server '{}' has an unrecognized config - fix it in preferences.toml first.",
+                        name
+                    ));
+                }
+            };
+
+            let McpAuth::OAuth {
+                client_id,
+                client_secret,
+                auth_server_url,
+                ..
+            } = auth
+            else {
+                return Err(anyhow::anyhow!(
+                    "MCP server '{}' does not use OAuth - nothing to refresh.",
+                    name
+                ));
+            };
+
+            let mut credentials = CredentialManager::new(&paths.config_dir);
+            let _ = credentials.load();
+
+            let refresh_token = credentials
+                .refresh_token(&url)
+                .map(String::from)
+                .ok_or_else(|| {
+                    anyhow::anyhow!(
+                        "No refresh token stored for '{}'. Run 'oauth_login' to authenticate.",
+                        name
+                    )
+                })?;
+
+            let rt = tokio::runtime::Runtime::new()?;
+            let token = rt.block_on(oauth::refresh_oauth_token(
+                &url,
+                &client_id,
+                client_secret.as_deref(),
+                &refresh_token,
+                auth_server_url.as_deref(),
+            ))?;
+            credentials.store_token(&url, token)?;
+
+            match paths.sync_preferences() {
+                Ok(msg) => Ok(format!(
+                    "Refreshed OAuth token for '{}' and synced to agents.\n\n{}",
+                    name, msg
+                )),
+                Err(e) => Ok(format!(
+                    "Refreshed OAuth token for '{}', but sync failed: {}",
+                    name, e
+                )),
+            }
+        }
+
         "oauth_logout" => {
             let name = arguments
                 .get("name")
@@ -1467,6 +3048,12 @@ fn call_tool(name: &str, arguments: Value) -> Result<String> {
                         name
                     ));
                 }
+                McpServerConfig::Unknown(_) => {
+                    return Err(anyhow::anyhow!(
+                        "MCP server '{}' has an unrecognized config - fix it in preferences.toml first.",
+                        name
+                    ));
+                }
             };
 
             let mut credentials = CredentialManager::new(&paths.config_dir);
@@ -1483,9 +3070,26 @@ fn call_tool(name: &str, arguments: Value) -> Result<String> {
         }
 
         "export_config" => {
+            let include_secrets = arguments
+                .get("include_secrets")
+                .and_then(|v| v.as_bool())
+                .unwrap_or(false);
+
             let prefs = &paths.preferences.global_prefs;
+            let mcp_servers: HashMap<&String, McpServerConfig> = prefs
+                .mcp_servers
+                .iter()
+                .map(|(name, config)| {
+                    let config = if include_secrets {
+                        config.clone()
+                    } else {
+                        config.redacted()
+                    };
+                    (name, config)
+                })
+                .collect();
             let export = json!({
-                "mcp_servers": prefs.mcp_servers,
+                "mcp_servers": mcp_servers,
                 "tool_permissions": prefs.tool_permissions,
                 "tool_presets": prefs.tool_presets,
                 "individual_tools": prefs.individual_tools,
@@ -1499,6 +3103,33 @@ fn call_tool(name: &str, arguments: Value) -> Result<String> {
             ))
         }
 
+        "get_effective_config" => {
+            let json_str = serde_json::to_string_pretty(&effective_config(paths))?;
+            Ok(format!(
+                "## Effective Configuration\n\n```json\n{}\n```",
+                json_str
+            ))
+        }
+
+        "diff_config" => {
+            let from = arguments
+                .get("from")
+                .and_then(|v| v.as_str())
+                .ok_or_else(|| anyhow::anyhow!("Missing 'from' argument"))?;
+            let to = arguments
+                .get("to")
+                .and_then(|v| v.as_str())
+                .ok_or_else(|| anyhow::anyhow!("Missing 'to' argument"))?;
+
+            let old = parse_config_arg(paths, from)?;
+            let new = parse_config_arg(paths, to)?;
+
+            Ok(format!(
+                "## Config Diff\n\n(+ only in 'to', - only in 'from', ~ changed)\n\n{}",
+                diff_preferences(&old, &new)
+            ))
+        }
+
         "import_config" => {
             let config_str = arguments
                 .get("config")
@@ -1514,14 +3145,28 @@ fn call_tool(name: &str, arguments: Value) -> Result<String> {
                 .map_err(|e| anyhow::anyhow!("Invalid JSON: {}", e))?;
 
             let mut count = 0;
+            let mut missing_secrets = Vec::new();
+            let _lock = paths.preferences.begin_global_update()?;
 
             if let Some(servers) = import.get("mcp_servers").and_then(|v| v.as_object()) {
-                if replace {
-                    paths.preferences.global_prefs.mcp_servers.clear();
-                }
+                let existing = if replace {
+                    std::mem::take(&mut paths.preferences.global_prefs.mcp_servers)
+                } else {
+                    paths.preferences.global_prefs.mcp_servers.clone()
+                };
                 for (name, config) in servers {
-                    if let Ok(server_config) = serde_json::from_value::<McpServerConfig>(config.clone()) {
-                        paths.preferences.global_prefs.mcp_servers.insert(name.clone(), server_config);
+                    if let Ok(mut server_config) =
+                        serde_json::from_value::<McpServerConfig>(config.clone())
+                    {
+                        // Redacted exports keep the secrets already configured here
+                        if server_config.restore_redacted(existing.get(name)) {
+                            missing_secrets.push(name.clone());
+                        }
+                        paths
+                            .preferences
+                            .global_prefs
+                            .mcp_servers
+                            .insert(name.clone(), server_config);
                         count += 1;
                     }
                 }
@@ -1557,12 +3202,48 @@ fn call_tool(name: &str, arguments: Value) -> Result<String> {
 
             paths.preferences.save_global()?;
 
-            Ok(format!(
+            let mut message = format!(
                 "Imported configuration ({} MCP servers). Run 'sync' to apply to agents.",
                 count
-            ))
+            );
+            if !missing_secrets.is_empty() {
+                missing_secrets.sort();
+                message.push_str(&format!(
+                    "\n\n⚠️ Redacted secrets with no existing value were dropped for: {}. Re-enter them before syncing.",
+                    missing_secrets.join(", ")
+                ));
+            }
+            Ok(message)
         }
 
         _ => Err(anyhow::anyhow!("Unknown tool: {}", name)),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_writes_wait_for_timed_out_call() {
+        let (release, wait) = std::sync::mpsc::channel::<()>();
+        let worker = std::thread::spawn(move || {
+            let _ = wait.recv();
+        });
+        let mut state = McpState {
+            orphaned: Some(("sync".to_string(), worker)),
+            ..Default::default()
+        };
+
+        let err = state.check_orphaned_writer("mcp_add").unwrap_err();
+        assert!(err.to_string().contains("'sync' call is still running"));
+        assert!(state.check_orphaned_writer("mcp_list").is_ok());
+
+        release.send(()).unwrap();
+        while !state.orphaned.as_ref().unwrap().1.is_finished() {
+            std::thread::yield_now();
+        }
+        assert!(state.check_orphaned_writer("mcp_add").is_ok());
+        assert!(state.orphaned.is_none());
+    }
+}
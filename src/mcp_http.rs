@@ -0,0 +1,89 @@
+use crate::mcp::{McpState, handle_message};
+use anyhow::{Context, Result};
+use axum::{
+    Json, Router,
+    extract::State,
+    http::{HeaderMap, StatusCode, header},
+    response::{IntoResponse, Response},
+    routing::{get, post},
+};
+use serde_json::json;
+use std::sync::{Arc, Mutex};
+
+pub const DEFAULT_PORT: u16 = 8765;
+
+/// Serves the MCP JSON-RPC dispatch over Streamable HTTP on localhost.
+pub fn run_mcp_http_server(port: u16) -> Result<()> {
+    let rt = tokio::runtime::Runtime::new()?;
+    rt.block_on(async {
+        let listener = tokio::net::TcpListener::bind(("127.0.0.1", port))
+            .await
+            .with_context(|| format!("Failed to bind MCP HTTP server to port {}", port))?;
+
+        let addr = listener.local_addr()?;
+        log::info!("Starting MooAgent MCP HTTP server on {}", addr);
+        eprintln!("mooagent MCP server listening on http://{}/mcp", addr);
+
+        axum::serve(listener, router()).await?;
+        Ok(())
+    })
+}
+
+pub fn router() -> Router {
+    let state = Arc::new(Mutex::new(McpState::default()));
+    Router::new()
+        .route(
+            "/mcp",
+            post(handle_post).get(|| async { StatusCode::METHOD_NOT_ALLOWED }),
+        )
+        .route("/health", get(health))
+        .with_state(state)
+}
+
+async fn health() -> Json<serde_json::Value> {
+    Json(json!({
+        "status": "ok",
+        "version": env!("CARGO_PKG_VERSION")
+    }))
+}
+
+async fn handle_post(
+    State(state): State<Arc<Mutex<McpState>>>,
+    headers: HeaderMap,
+    body: String,
+) -> Response {
+    if !origin_allowed(&headers) {
+        return (StatusCode::FORBIDDEN, "Origin not allowed").into_response();
+    }
+
+    // Tool calls do blocking file and network I/O, some of it on their own runtime. They
+    // share one config, so requests are handled one at a time.
+    let handle = move || {
+        let mut state = state.lock().unwrap_or_else(|e| e.into_inner());
+        handle_message(&mut state, &body)
+    };
+    match tokio::task::spawn_blocking(handle).await {
+        Ok(Some(response)) => {
+            ([(header::CONTENT_TYPE, "application/json")], response).into_response()
+        }
+        Ok(None) => StatusCode::ACCEPTED.into_response(),
+        Err(e) => {
+            log::error!("MCP request handler failed: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+/// Rejects browser requests from non-local origins to guard against DNS rebinding.
+pub fn origin_allowed(headers: &HeaderMap) -> bool {
+    let Some(origin) = headers.get(header::ORIGIN) else {
+        return true;
+    };
+
+    origin
+        .to_str()
+        .ok()
+        .and_then(|o| url::Url::parse(o).ok())
+        .and_then(|url| url.host_str().map(String::from))
+        .is_some_and(|host| matches!(host.as_str(), "localhost" | "127.0.0.1" | "[::1]"))
+}
@@ -280,7 +280,6 @@ impl OAuthFlow {
         Ok(response.into_stored_token())
     }
 
-    #[allow(dead_code)]
     pub async fn refresh_token(&mut self, refresh_token: &str) -> Result<StoredToken> {
         let metadata = self.discover_metadata().await?;
         let token_endpoint = metadata.token_endpoint.clone();
@@ -442,7 +441,6 @@ pub async fn run_oauth_flow(
     flow.authorize().await
 }
 
-#[allow(dead_code)]
 pub async fn refresh_oauth_token(
     server_url: &str,
     client_id: &str,
@@ -1,6 +1,6 @@
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::fs;
 use std::path::{Path, PathBuf};
 
@@ -12,6 +12,10 @@ pub enum McpAuth {
     Bearer {
         token: String,
     },
+    /// Bearer token kept in the credential store under `key` instead of inline.
+    BearerRef {
+        key: String,
+    },
     OAuth {
         client_id: String,
         #[serde(default, skip_serializing_if = "Option::is_none")]
@@ -34,7 +38,7 @@ impl McpAuth {
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
-#[serde(untagged)]
+#[serde(untagged, deny_unknown_fields)]
 pub enum McpServerConfig {
     Stdio {
         command: String,
@@ -46,35 +50,215 @@ pub enum McpServerConfig {
         disabled_tools: Vec<String>,
         #[serde(default)]
         auto_allow: bool,
+        /// A note on what the server is for; never written into agent configs.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        description: Option<String>,
+        /// Labels for grouping and filtering servers; never written into agent configs.
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        tags: Vec<String>,
     },
     Sse {
         url: String,
         #[serde(default, skip_serializing_if = "McpAuth::is_none")]
         auth: McpAuth,
+        /// Extra request headers, e.g. `X-Api-Key`.
+        #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+        headers: HashMap<String, String>,
+        /// Let `headers` replace the `Authorization` header generated from `auth`.
+        #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+        headers_override_auth: bool,
         #[serde(default)]
         disabled_tools: Vec<String>,
         #[serde(default)]
         auto_allow: bool,
+        /// A note on what the server is for; never written into agent configs.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        description: Option<String>,
+        /// Labels for grouping and filtering servers; never written into agent configs.
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        tags: Vec<String>,
     },
     Http {
         #[serde(rename = "httpUrl")]
         http_url: String,
         #[serde(default, skip_serializing_if = "McpAuth::is_none")]
         auth: McpAuth,
+        /// Extra request headers, e.g. `X-Api-Key`.
+        #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+        headers: HashMap<String, String>,
+        /// Let `headers` replace the `Authorization` header generated from `auth`.
+        #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+        headers_override_auth: bool,
         #[serde(default)]
         disabled_tools: Vec<String>,
         #[serde(default)]
         auto_allow: bool,
+        /// A note on what the server is for; never written into agent configs.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        description: Option<String>,
+        /// Labels for grouping and filtering servers; never written into agent configs.
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        tags: Vec<String>,
     },
+    /// An entry matching none of the shapes above (e.g. a typo'd field), kept verbatim
+    /// so saving preferences doesn't drop it.
+    Unknown(serde_json::Value),
+}
+
+/// Placeholder written in place of secrets in shared exports.
+pub const REDACTED: &str = "<redacted>";
+
+/// Credential keys of a deleted server that none of the `remaining` servers still use.
+pub fn orphaned_credential_keys<'a>(
+    removed: &'a McpServerConfig,
+    remaining: &HashMap<String, McpServerConfig>,
+) -> Vec<&'a str> {
+    let still_used: Vec<&str> = remaining
+        .values()
+        .flat_map(|c| c.credential_keys())
+        .collect();
+    removed
+        .credential_keys()
+        .into_iter()
+        .filter(|key| !still_used.contains(key))
+        .collect()
+}
+
+/// Whether a key (an env var, header or argument name) looks like it holds a credential.
+pub fn is_secret_key(key: &str) -> bool {
+    let key = key.to_lowercase();
+    [
+        "token",
+        "secret",
+        "password",
+        "authorization",
+        "api_key",
+        "apikey",
+    ]
+    .iter()
+    .any(|needle| key.contains(needle))
 }
 
 impl McpServerConfig {
-    #[allow(dead_code)]
+    /// Copy with bearer tokens, OAuth client secrets, header values and secret-looking
+    /// env values replaced by `REDACTED`, for configs meant to be shared.
+    pub fn redacted(&self) -> Self {
+        let mut config = self.clone();
+        match &mut config {
+            McpServerConfig::Stdio { env, .. } => {
+                for (key, value) in env.iter_mut() {
+                    if is_secret_key(key) {
+                        *value = REDACTED.to_string();
+                    }
+                }
+            }
+            McpServerConfig::Sse { auth, headers, .. }
+            | McpServerConfig::Http { auth, headers, .. } => {
+                match auth {
+                    McpAuth::Bearer { token } => *token = REDACTED.to_string(),
+                    McpAuth::OAuth {
+                        client_secret: Some(secret),
+                        ..
+                    } => *secret = REDACTED.to_string(),
+                    _ => {}
+                }
+                for value in headers.values_mut() {
+                    *value = REDACTED.to_string();
+                }
+            }
+            McpServerConfig::Unknown(_) => {}
+        }
+        config
+    }
+
+    /// Fills `REDACTED` placeholders from `existing`, the config this one replaces.
+    /// Placeholders with nothing to restore are dropped; returns true if any were.
+    pub fn restore_redacted(&mut self, existing: Option<&McpServerConfig>) -> bool {
+        let mut missing = false;
+        match self {
+            McpServerConfig::Stdio { env, .. } => {
+                let old_env = match existing {
+                    Some(McpServerConfig::Stdio { env, .. }) => Some(env),
+                    _ => None,
+                };
+                env.retain(|key, value| {
+                    if value != REDACTED {
+                        return true;
+                    }
+                    match old_env.and_then(|e| e.get(key)) {
+                        Some(old) => {
+                            *value = old.clone();
+                            true
+                        }
+                        None => {
+                            missing = true;
+                            false
+                        }
+                    }
+                });
+            }
+            McpServerConfig::Sse { auth, headers, .. }
+            | McpServerConfig::Http { auth, headers, .. } => {
+                let (old_auth, old_headers) = match existing {
+                    Some(McpServerConfig::Sse { auth, headers, .. })
+                    | Some(McpServerConfig::Http { auth, headers, .. }) => {
+                        (Some(auth), Some(headers))
+                    }
+                    _ => (None, None),
+                };
+
+                let bearer_redacted =
+                    matches!(auth, McpAuth::Bearer { token } if token == REDACTED);
+                if bearer_redacted {
+                    // The existing token may be inline or already moved to the credential store
+                    match old_auth {
+                        Some(old @ (McpAuth::Bearer { .. } | McpAuth::BearerRef { .. })) => {
+                            *auth = old.clone();
+                        }
+                        _ => {
+                            *auth = McpAuth::None;
+                            missing = true;
+                        }
+                    }
+                }
+                if let McpAuth::OAuth { client_secret, .. } = auth
+                    && client_secret.as_deref() == Some(REDACTED)
+                {
+                    *client_secret = match old_auth {
+                        Some(McpAuth::OAuth {
+                            client_secret: old, ..
+                        }) => old.clone(),
+                        _ => None,
+                    };
+                    missing |= client_secret.is_none();
+                }
+
+                headers.retain(|key, value| {
+                    if value != REDACTED {
+                        return true;
+                    }
+                    match old_headers.and_then(|h| h.get(key)) {
+                        Some(old) => {
+                            *value = old.clone();
+                            true
+                        }
+                        None => {
+                            missing = true;
+                            false
+                        }
+                    }
+                });
+            }
+            McpServerConfig::Unknown(_) => {}
+        }
+        missing
+    }
+
     pub fn url(&self) -> Option<&str> {
         match self {
             McpServerConfig::Sse { url, .. } => Some(url),
             McpServerConfig::Http { http_url, .. } => Some(http_url),
-            McpServerConfig::Stdio { .. } => None,
+            McpServerConfig::Stdio { .. } | McpServerConfig::Unknown(_) => None,
         }
     }
 
@@ -82,7 +266,7 @@ impl McpServerConfig {
         match self {
             McpServerConfig::Sse { auth, .. } => Some(auth),
             McpServerConfig::Http { auth, .. } => Some(auth),
-            McpServerConfig::Stdio { .. } => None,
+            McpServerConfig::Stdio { .. } | McpServerConfig::Unknown(_) => None,
         }
     }
 
@@ -90,11 +274,51 @@ impl McpServerConfig {
         self.auth().map(|a| a.requires_oauth()).unwrap_or(false)
     }
 
+    /// Credential store keys this server's tokens may live under: OAuth tokens are keyed
+    /// by URL, stored bearer tokens by their `key`.
+    pub fn credential_keys(&self) -> Vec<&str> {
+        let mut keys: Vec<&str> = self.url().into_iter().collect();
+        if let Some(McpAuth::BearerRef { key }) = self.auth()
+            && !keys.contains(&key.as_str())
+        {
+            keys.push(key);
+        }
+        keys
+    }
+
+    /// Moves an inline bearer token into the credential store, keyed by server URL.
+    pub fn migrate_inline_bearer(&mut self, credentials: &mut CredentialManager) -> Result<bool> {
+        let (url, auth) = match self {
+            McpServerConfig::Sse { url, auth, .. } => (url, auth),
+            McpServerConfig::Http { http_url, auth, .. } => (http_url, auth),
+            McpServerConfig::Stdio { .. } | McpServerConfig::Unknown(_) => return Ok(false),
+        };
+
+        let McpAuth::Bearer { token } = auth else {
+            return Ok(false);
+        };
+
+        credentials.store_bearer_token(url, token)?;
+        *auth = McpAuth::BearerRef { key: url.clone() };
+        Ok(true)
+    }
+
     pub fn disabled_tools(&self) -> &[String] {
         match self {
             McpServerConfig::Stdio { disabled_tools, .. } => disabled_tools,
             McpServerConfig::Sse { disabled_tools, .. } => disabled_tools,
             McpServerConfig::Http { disabled_tools, .. } => disabled_tools,
+            McpServerConfig::Unknown(_) => &[],
+        }
+    }
+
+    /// `None` for unrecognized configs, which are never rewritten.
+    pub fn disabled_tools_mut(&mut self) -> Option<&mut Vec<String>> {
+        match self {
+            McpServerConfig::Stdio { disabled_tools, .. }
+            | McpServerConfig::Sse { disabled_tools, .. }
+            | McpServerConfig::Http { disabled_tools, .. } => Some(disabled_tools),
+            McpServerConfig::Unknown(_) => None,
         }
     }
 
@@ -103,8 +327,48 @@ impl McpServerConfig {
             McpServerConfig::Stdio { auto_allow, .. } => *auto_allow,
             McpServerConfig::Sse { auto_allow, .. } => *auto_allow,
             McpServerConfig::Http { auto_allow, .. } => *auto_allow,
+            McpServerConfig::Unknown(_) => false,
+        }
+    }
+
+    pub fn is_unknown(&self) -> bool {
+        matches!(self, McpServerConfig::Unknown(_))
+    }
+
+    pub fn description(&self) -> Option<&str> {
+        match self {
+            McpServerConfig::Stdio { description, .. }
+            | McpServerConfig::Sse { description, .. }
+            | McpServerConfig::Http { description, .. } => description.as_deref(),
+            McpServerConfig::Unknown(_) => None,
+        }
+    }
+
+    pub fn tags(&self) -> &[String] {
+        match self {
+            McpServerConfig::Stdio { tags, .. }
+            | McpServerConfig::Sse { tags, .. }
+            | McpServerConfig::Http { tags, .. } => tags,
+            McpServerConfig::Unknown(_) => &[],
         }
     }
+
+    /// Whether the MCP-tab filter `query` matches: `#tag` matches a tag exactly, anything
+    /// else is a case-insensitive substring of the name, description or a tag.
+    pub fn matches_filter(&self, name: &str, query: &str) -> bool {
+        let query = query.trim().to_lowercase();
+        if let Some(tag) = query.strip_prefix('#') {
+            return self.tags().iter().any(|t| t.to_lowercase() == tag);
+        }
+        name.to_lowercase().contains(&query)
+            || self
+                .description()
+                .is_some_and(|d| d.to_lowercase().contains(&query))
+            || self
+                .tags()
+                .iter()
+                .any(|t| t.to_lowercase().contains(&query))
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -117,16 +381,35 @@ pub struct ToolPermissions {
     pub deny: Vec<String>,
 }
 
+/// Current `preferences.toml` schema version. Bump it and add a step to
+/// `migrate_preferences` whenever a key is renamed or removed.
+pub const PREFERENCES_VERSION: u32 = 2;
+
+/// Presets renamed since version 0, as (old, new). Carried over by the version 1 migration.
+const RENAMED_PRESETS: &[(&str, &str)] = &[];
+
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct AgentPreferences {
+    /// Schema version the file was written with; 0 for files that predate versioning.
+    #[serde(default)]
+    pub version: u32,
     #[serde(default)]
     pub general: GeneralPreferences,
     #[serde(default)]
     pub tool_presets: HashMap<String, PresetGroup>,
     #[serde(default)]
     pub individual_tools: HashMap<String, bool>,
+    /// Categories picked for hand-added tools, overriding the guess from their name.
+    #[serde(default)]
+    pub tool_categories: HashMap<String, ToolCategory>,
     #[serde(default)]
     pub tool_permissions: ToolPermissions,
+    /// Explicit Claude permission patterns per tool, e.g. `mytool` -> `Bash(mytool sub:*)`.
+    #[serde(default)]
+    pub permission_overrides: HashMap<String, String>,
+    /// Extra tools that never get a generated Claude permission.
+    #[serde(default)]
+    pub permission_skip_tools: Vec<String>,
     #[serde(default)]
     pub mcp_servers: HashMap<String, McpServerConfig>,
     #[serde(default)]
@@ -135,11 +418,189 @@ pub struct AgentPreferences {
     pub agent_specific: HashMap<String, AgentSpecificPrefs>,
 }
 
+impl AgentPreferences {
+    pub fn tool_category(&self, name: &str) -> ToolCategory {
+        self.tool_categories
+            .get(name)
+            .copied()
+            .unwrap_or_else(|| ToolCategory::guess(name))
+    }
+
+    /// `[general]` with the agent's own overrides applied on top.
+    pub fn general_for(&self, agent: &str) -> GeneralPreferences {
+        let mut general = self.general.clone();
+        if let Some(over) = self
+            .agent_specific
+            .get(agent)
+            .and_then(|ap| ap.general.as_ref())
+        {
+            general.overlay(over);
+        }
+        general
+    }
+
+    /// Adds `servers` to `disabled_mcp_servers`, or removes them if they are all disabled
+    /// already. Returns whether they were disabled and how many entries changed.
+    pub fn toggle_disabled_mcp_servers(&mut self, servers: &[String]) -> (bool, usize) {
+        let disabled = &mut self.disabled_mcp_servers;
+        let newly: Vec<String> = servers
+            .iter()
+            .filter(|name| !disabled.contains(name))
+            .cloned()
+            .collect();
+        if newly.is_empty() {
+            let before = disabled.len();
+            disabled.retain(|name| !servers.contains(name));
+            (false, before - disabled.len())
+        } else {
+            let count = newly.len();
+            disabled.extend(newly);
+            (true, count)
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct GeneralPreferences {
     pub auto_accept_tools: Option<bool>,
     pub enable_logging: Option<bool>,
     pub sandboxed_mode: Option<bool>,
+    /// Timeout for remote MCP connectivity tests; defaults to 10 seconds.
+    pub mcp_test_timeout_secs: Option<u64>,
+    /// Makes destructive MCP tools wait for a second call that passes back the token the
+    /// first one returned.
+    pub require_confirmation_for_destructive: Option<bool>,
+    /// Rule files larger than this are flagged; defaults to 64 KiB, 0 disables the check.
+    pub max_rules_bytes: Option<u64>,
+    /// How long the TUI waits for input when nothing is pending; defaults to 1000 ms.
+    pub idle_poll_interval_ms: Option<u64>,
+    /// How long the MCP server waits for a tool call; defaults to 120 seconds.
+    pub mcp_tool_timeout_secs: Option<u64>,
+    /// How long `oauth_login` waits for the browser sign-in; defaults to 300 seconds.
+    pub oauth_login_timeout_secs: Option<u64>,
+    /// Back up rule files before the append tools edit them; defaults to on.
+    pub backup_before_edit: Option<bool>,
+    /// Status messages the TUI keeps for the log view; defaults to 100.
+    pub status_log_capacity: Option<usize>,
+}
+
+impl GeneralPreferences {
+    /// Replaces every field `over` sets, keeping the rest.
+    pub fn overlay(&mut self, over: &GeneralPreferences) {
+        if let Some(val) = over.auto_accept_tools {
+            self.auto_accept_tools = Some(val);
+        }
+        if let Some(val) = over.enable_logging {
+            self.enable_logging = Some(val);
+        }
+        if let Some(val) = over.sandboxed_mode {
+            self.sandboxed_mode = Some(val);
+        }
+        if let Some(val) = over.mcp_test_timeout_secs {
+            self.mcp_test_timeout_secs = Some(val);
+        }
+        if let Some(val) = over.require_confirmation_for_destructive {
+            self.require_confirmation_for_destructive = Some(val);
+        }
+        if let Some(val) = over.max_rules_bytes {
+            self.max_rules_bytes = Some(val);
+        }
+        if let Some(val) = over.idle_poll_interval_ms {
+            self.idle_poll_interval_ms = Some(val);
+        }
+        if let Some(val) = over.mcp_tool_timeout_secs {
+            self.mcp_tool_timeout_secs = Some(val);
+        }
+        if let Some(val) = over.oauth_login_timeout_secs {
+            self.oauth_login_timeout_secs = Some(val);
+        }
+        if let Some(val) = over.backup_before_edit {
+            self.backup_before_edit = Some(val);
+        }
+        if let Some(val) = over.status_log_capacity {
+            self.status_log_capacity = Some(val);
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.auto_accept_tools.is_none()
+            && self.enable_logging.is_none()
+            && self.sandboxed_mode.is_none()
+            && self.mcp_test_timeout_secs.is_none()
+            && self.require_confirmation_for_destructive.is_none()
+            && self.max_rules_bytes.is_none()
+            && self.idle_poll_interval_ms.is_none()
+            && self.mcp_tool_timeout_secs.is_none()
+            && self.oauth_login_timeout_secs.is_none()
+            && self.backup_before_edit.is_none()
+            && self.status_log_capacity.is_none()
+    }
+}
+
+/// Whether an individual tool is one of the agent's own tools or a program it runs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ToolCategory {
+    Llm,
+    Program,
+}
+
+impl ToolCategory {
+    /// Agent tools are PascalCase (`Read`, `WebFetch`); shell commands are lowercase.
+    pub fn guess(name: &str) -> Self {
+        if name.starts_with(|c: char| c.is_uppercase()) {
+            ToolCategory::Llm
+        } else {
+            ToolCategory::Program
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ToolCategory::Llm => "LLM tool",
+            ToolCategory::Program => "Program",
+        }
+    }
+}
+
+/// The on/off toggles in `[general]` that map onto agents' own settings files.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GeneralSetting {
+    AutoAcceptTools,
+    EnableLogging,
+    SandboxedMode,
+}
+
+impl GeneralSetting {
+    pub const ALL: [GeneralSetting; 3] = [
+        GeneralSetting::AutoAcceptTools,
+        GeneralSetting::EnableLogging,
+        GeneralSetting::SandboxedMode,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            GeneralSetting::AutoAcceptTools => "Auto-Accept Tools",
+            GeneralSetting::EnableLogging => "Enable Logging",
+            GeneralSetting::SandboxedMode => "Sandboxed Mode",
+        }
+    }
+
+    pub fn value(self, general: &GeneralPreferences) -> Option<bool> {
+        match self {
+            GeneralSetting::AutoAcceptTools => general.auto_accept_tools,
+            GeneralSetting::EnableLogging => general.enable_logging,
+            GeneralSetting::SandboxedMode => general.sandboxed_mode,
+        }
+    }
+
+    pub fn value_mut(self, general: &mut GeneralPreferences) -> &mut Option<bool> {
+        match self {
+            GeneralSetting::AutoAcceptTools => &mut general.auto_accept_tools,
+            GeneralSetting::EnableLogging => &mut general.enable_logging,
+            GeneralSetting::SandboxedMode => &mut general.sandboxed_mode,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -155,6 +616,9 @@ pub struct AgentSpecificPrefs {
     pub mcp_servers: HashMap<String, McpServerConfig>,
     #[serde(default)]
     pub plugins: HashMap<String, bool>,
+    /// Overrides `[general]` for this agent only.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub general: Option<GeneralPreferences>,
 }
 
 pub struct PreferenceManager {
@@ -177,42 +641,131 @@ pub trait ConfigGenerator {
         prefs: &AgentPreferences,
         credentials: Option<&CredentialManager>,
     ) -> Result<Vec<(PathBuf, String)>>;
+
+    /// Files and object keys holding the MCP server entries this generator writes,
+    /// so entries for servers removed from mooagent can be pruned on the next sync.
+    fn managed_objects(&self) -> Vec<(PathBuf, &'static str)> {
+        Vec::new()
+    }
+
+    /// General toggles this agent has a native setting for; the rest are ignored.
+    fn supported_general_settings(&self) -> &'static [GeneralSetting] {
+        &[]
+    }
+
+    /// The entry this agent would get for one MCP server, generated in memory.
+    /// `None` if the agent doesn't keep MCP servers in an object keyed by name.
+    fn preview_server(
+        &self,
+        name: &str,
+        config: &McpServerConfig,
+    ) -> Result<Option<serde_json::Value>> {
+        let mut prefs = AgentPreferences::default();
+        prefs.mcp_servers.insert(name.to_string(), config.clone());
+        let files = self.generate(&prefs, None)?;
+
+        for (path, key) in self.managed_objects() {
+            if let Some((_, content)) = files.iter().find(|(p, _)| *p == path) {
+                let value: serde_json::Value = serde_json::from_str(content)?;
+                return Ok(value.get(key).and_then(|v| v.get(name)).cloned());
+            }
+        }
+        Ok(None)
+    }
+}
+
+/// Expands `${VAR}` placeholders from the environment. Unset variables are left as-is.
+pub fn expand_env_vars(input: &str) -> String {
+    let mut result = String::with_capacity(input.len());
+    let mut rest = input;
+
+    while let Some(start) = rest.find("${") {
+        let Some(len) = rest[start + 2..].find('}') else {
+            break;
+        };
+        let name = &rest[start + 2..start + 2 + len];
+        result.push_str(&rest[..start]);
+        match std::env::var(name) {
+            Ok(value) if !name.is_empty() => result.push_str(&value),
+            _ => {
+                log::warn!(
+                    "Environment variable '{}' is not set; leaving '${{{}}}' in place",
+                    name,
+                    name
+                );
+                result.push_str(&rest[start..start + 3 + len]);
+            }
+        }
+        rest = &rest[start + 3 + len..];
+    }
+
+    result.push_str(rest);
+    result
 }
 
 fn get_auth_headers(
     config: &McpServerConfig,
     credentials: Option<&CredentialManager>,
 ) -> Option<serde_json::Map<String, serde_json::Value>> {
-    let (url, auth) = match config {
-        McpServerConfig::Sse { url, auth, .. } => (url, auth),
-        McpServerConfig::Http { http_url, auth, .. } => (http_url, auth),
-        McpServerConfig::Stdio { .. } => return None,
+    let (url, auth, custom_headers, headers_override_auth) = match config {
+        McpServerConfig::Sse {
+            url,
+            auth,
+            headers,
+            headers_override_auth,
+            ..
+        } => (url, auth, headers, *headers_override_auth),
+        McpServerConfig::Http {
+            http_url,
+            auth,
+            headers,
+            headers_override_auth,
+            ..
+        } => (http_url, auth, headers, *headers_override_auth),
+        McpServerConfig::Stdio { .. } | McpServerConfig::Unknown(_) => return None,
+    };
+
+    let mut headers = serde_json::Map::new();
+    for (name, value) in custom_headers {
+        headers.insert(
+            name.clone(),
+            serde_json::Value::String(expand_env_vars(value)),
+        );
+    }
+
+    let token = match auth {
+        McpAuth::Bearer { token } => Some(token.clone()),
+        McpAuth::BearerRef { key } => {
+            let token = credentials.and_then(|creds| creds.get_token(key));
+            if token.is_none() {
+                log::warn!("No stored bearer token found for '{}'", key);
+            }
+            token.map(|t| t.access_token.clone())
+        }
+        McpAuth::OAuth { .. } => credentials
+            .and_then(|creds| creds.get_valid_token(url))
+            .map(|t| t.access_token.clone()),
+        McpAuth::None => None,
     };
 
-    match auth {
-        McpAuth::Bearer { token } => {
-            let mut headers = serde_json::Map::new();
+    if let Some(token) = token {
+        let overridden = headers_override_auth
+            && headers
+                .keys()
+                .any(|k| k.eq_ignore_ascii_case("authorization"));
+        if !overridden {
+            headers.retain(|k, _| !k.eq_ignore_ascii_case("authorization"));
             headers.insert(
                 "Authorization".to_string(),
                 serde_json::Value::String(format!("Bearer {}", token)),
             );
-            Some(headers)
-        }
-        McpAuth::OAuth { .. } => {
-            if let Some(creds) = credentials
-                && let Some(token) = creds.get_valid_token(url)
-            {
-                let mut headers = serde_json::Map::new();
-                headers.insert(
-                    "Authorization".to_string(),
-                    serde_json::Value::String(format!("Bearer {}", token.access_token)),
-                );
-                Some(headers)
-            } else {
-                None
-            }
         }
-        McpAuth::None => None,
+    }
+
+    if headers.is_empty() {
+        None
+    } else {
+        Some(headers)
     }
 }
 
@@ -234,11 +787,24 @@ impl PreferenceManager {
         if self.global_path.exists() {
             let content = fs::read_to_string(&self.global_path)
                 .context("Failed to read global preferences")?;
-            let loaded: AgentPreferences =
+            let mut raw: toml::Table =
                 toml::from_str(&content).context("Failed to parse global preferences")?;
+            let migrated = migrate_preferences(&mut raw);
+            let loaded: AgentPreferences = raw
+                .try_into()
+                .context("Failed to parse global preferences")?;
+            let version = loaded.version;
 
             let defaults = self.default_preferences();
             self.global_prefs = self.merge_prefs(defaults, Some(loaded));
+            self.global_prefs.version = version;
+
+            if !migrated.is_empty() {
+                for note in &migrated {
+                    log::info!("Migrated {}: {}", self.global_path.display(), note);
+                }
+                self.save_global()?;
+            }
         } else {
             self.global_prefs = self.default_preferences();
             self.save_global()?;
@@ -251,8 +817,16 @@ impl PreferenceManager {
             let content =
                 fs::read_to_string(config_file).context("Failed to read project config")?;
 
-            let wrapper: ProjectConfigWrapper =
+            let mut raw: toml::Table =
                 toml::from_str(&content).context("Failed to parse project preferences")?;
+            if let Some(toml::Value::Table(prefs)) = raw.get_mut("preferences") {
+                for note in migrate_preferences(prefs) {
+                    log::info!("Migrated {} (in memory): {}", config_file.display(), note);
+                }
+            }
+            let wrapper: ProjectConfigWrapper = raw
+                .try_into()
+                .context("Failed to parse project preferences")?;
 
             self.project_prefs = wrapper.preferences;
         } else {
@@ -276,15 +850,7 @@ impl PreferenceManager {
 
         let mut merged = base;
 
-        if let Some(val) = over.general.auto_accept_tools {
-            merged.general.auto_accept_tools = Some(val);
-        }
-        if let Some(val) = over.general.enable_logging {
-            merged.general.enable_logging = Some(val);
-        }
-        if let Some(val) = over.general.sandboxed_mode {
-            merged.general.sandboxed_mode = Some(val);
-        }
+        merged.general.overlay(&over.general);
 
         for (k, v) in over.tool_presets {
             merged.tool_presets.insert(k, v);
@@ -294,6 +860,20 @@ impl PreferenceManager {
             merged.individual_tools.insert(k, v);
         }
 
+        for (k, v) in over.tool_categories {
+            merged.tool_categories.insert(k, v);
+        }
+
+        for (k, v) in over.permission_overrides {
+            merged.permission_overrides.insert(k, v);
+        }
+
+        for tool in over.permission_skip_tools {
+            if !merged.permission_skip_tools.contains(&tool) {
+                merged.permission_skip_tools.push(tool);
+            }
+        }
+
         for (k, v) in over.mcp_servers {
             merged.mcp_servers.insert(k, v);
         }
@@ -320,6 +900,13 @@ impl PreferenceManager {
             for (k, v) in agent_conf.plugins {
                 entry.plugins.insert(k, v);
             }
+
+            if let Some(general) = agent_conf.general {
+                entry
+                    .general
+                    .get_or_insert_with(GeneralPreferences::default)
+                    .overlay(&general);
+            }
         }
 
         merged
@@ -338,33 +925,81 @@ impl PreferenceManager {
         tool_presets.insert("web_access".to_string(), PresetGroup { enabled: false });
 
         AgentPreferences {
+            version: PREFERENCES_VERSION,
             general: GeneralPreferences {
-                auto_accept_tools: Some(true),
-                enable_logging: Some(true),
-                sandboxed_mode: Some(true),
+                auto_accept_tools: None,
+                enable_logging: None,
+                sandboxed_mode: None,
+                mcp_test_timeout_secs: None,
+                require_confirmation_for_destructive: None,
+                max_rules_bytes: None,
+                idle_poll_interval_ms: None,
+                mcp_tool_timeout_secs: None,
+                oauth_login_timeout_secs: None,
+                backup_before_edit: None,
+                status_log_capacity: None,
             },
             tool_presets,
             individual_tools: HashMap::new(),
+            tool_categories: HashMap::new(),
             tool_permissions: ToolPermissions::default(),
+            permission_overrides: HashMap::new(),
+            permission_skip_tools: Vec::new(),
             mcp_servers: HashMap::new(),
             disabled_mcp_servers: Vec::new(),
             agent_specific: HashMap::new(),
         }
     }
 
+    fn lock(&self) -> Result<crate::lock::ConfigLock> {
+        crate::lock::acquire(self.global_path.parent().unwrap_or(Path::new(".")))
+    }
+
+    /// Takes the config lock and re-reads the global preferences, so a change saved while
+    /// the returned guard is held merges with, rather than overwrites, other instances' edits.
+    pub fn begin_global_update(&mut self) -> Result<crate::lock::ConfigLock> {
+        let lock = self.lock()?;
+        self.load_global()?;
+        Ok(lock)
+    }
+
+    /// Returns whether the global and project preferences changed, respectively.
+    pub fn migrate_inline_bearer_tokens(
+        &mut self,
+        credentials: &mut CredentialManager,
+    ) -> Result<(bool, bool)> {
+        let mut global_changed = false;
+        for (name, server) in self.global_prefs.mcp_servers.iter_mut() {
+            if server.migrate_inline_bearer(credentials)? {
+                log::info!("Moved bearer token for '{}' into credential store", name);
+                global_changed = true;
+            }
+        }
+
+        let mut project_changed = false;
+        if let Some(prefs) = &mut self.project_prefs {
+            for (name, server) in prefs.mcp_servers.iter_mut() {
+                if server.migrate_inline_bearer(credentials)? {
+                    log::info!("Moved bearer token for '{}' into credential store", name);
+                    project_changed = true;
+                }
+            }
+        }
+
+        Ok((global_changed, project_changed))
+    }
+
     pub fn save_global(&self) -> Result<()> {
         let content = toml::to_string_pretty(&self.global_prefs)?;
-        if let Some(parent) = self.global_path.parent() {
-            fs::create_dir_all(parent)?;
-        }
-        fs::write(&self.global_path, content)?;
-        Ok(())
+        let _lock = self.lock()?;
+        crate::config::write_atomic(&self.global_path, content)
     }
 
     pub fn save_project(&self, config_file: &Path) -> Result<()> {
         let Some(prefs) = &self.project_prefs else {
             return Ok(());
         };
+        let _lock = self.lock()?;
 
         let mut existing_content = String::new();
         if config_file.exists() {
@@ -386,12 +1021,78 @@ impl PreferenceManager {
         }
 
         let content = toml::to_string_pretty(&toml_val)?;
+        crate::config::write_atomic(config_file, content)
+    }
+}
+
+/// Upgrades a raw preferences table to `PREFERENCES_VERSION` in place.
+/// Returns a note per change made, or nothing if it was already current.
+pub fn migrate_preferences(prefs: &mut toml::Table) -> Vec<String> {
+    let version = prefs
+        .get("version")
+        .and_then(|v| v.as_integer())
+        .unwrap_or(0);
+    let mut notes = Vec::new();
+
+    if version > PREFERENCES_VERSION as i64 {
+        log::warn!(
+            "Preferences version {} is newer than supported version {}; loading as-is",
+            version,
+            PREFERENCES_VERSION
+        );
+        return notes;
+    }
+
+    if version < 1 {
+        rename_presets(prefs, RENAMED_PRESETS, &mut notes);
+    }
+
+    if version < 2 {
+        clear_old_general_defaults(prefs, &mut notes);
+    }
+
+    if version < PREFERENCES_VERSION as i64 {
+        prefs.insert(
+            "version".to_string(),
+            toml::Value::Integer(PREFERENCES_VERSION as i64),
+        );
+        notes.push(format!(
+            "upgraded schema from version {} to {}",
+            version, PREFERENCES_VERSION
+        ));
+    }
+
+    notes
+}
+
+/// Version 1 saved these toggles as `true` by default. Now that sync writes them into the
+/// agents' settings, those defaults are cleared so nothing changes until the user opts in.
+fn clear_old_general_defaults(prefs: &mut toml::Table, notes: &mut Vec<String>) {
+    let Some(toml::Value::Table(general)) = prefs.get_mut("general") else {
+        return;
+    };
 
-        if let Some(parent) = config_file.parent() {
-            fs::create_dir_all(parent)?;
+    for key in ["auto_accept_tools", "enable_logging", "sandboxed_mode"] {
+        if general.get(key).and_then(|v| v.as_bool()) == Some(true) {
+            general.remove(key);
+            notes.push(format!("cleared the old default general.{} = true", key));
+        }
+    }
+}
+
+fn rename_presets(prefs: &mut toml::Table, renames: &[(&str, &str)], notes: &mut Vec<String>) {
+    let Some(toml::Value::Table(presets)) = prefs.get_mut("tool_presets") else {
+        return;
+    };
+
+    for (old, new) in renames {
+        if let Some(preset) = presets.remove(*old) {
+            // An entry under the new name wins over the legacy one
+            if !presets.contains_key(*new) {
+                presets.insert(new.to_string(), preset);
+            }
+            notes.push(format!("renamed preset '{}' to '{}'", old, new));
         }
-        fs::write(config_file, content)?;
-        Ok(())
     }
 }
 
@@ -415,6 +1116,150 @@ pub fn expand_tools(prefs: &AgentPreferences) -> HashMap<String, bool> {
     tools
 }
 
+#[derive(Debug, Default, Deserialize)]
+struct DefaultMcpServersFile {
+    #[serde(default)]
+    mcp_servers: BTreeMap<String, McpServerConfig>,
+}
+
+fn builtin_default_mcp_servers() -> BTreeMap<String, McpServerConfig> {
+    let defaults = [
+        (
+            "filesystem",
+            "npx",
+            vec!["-y", "@modelcontextprotocol/server-filesystem", "."],
+        ),
+        (
+            "memory",
+            "npx",
+            vec!["-y", "@modelcontextprotocol/server-memory"],
+        ),
+        ("filesystem-uvx", "uvx", vec!["mcp-server-filesystem", "."]),
+        ("memory-uvx", "uvx", vec!["mcp-server-memory"]),
+    ];
+
+    defaults
+        .into_iter()
+        .map(|(name, cmd, args)| {
+            (
+                name.to_string(),
+                McpServerConfig::Stdio {
+                    command: cmd.to_string(),
+                    args: args.into_iter().map(String::from).collect(),
+                    env: HashMap::new(),
+                    disabled_tools: Vec::new(),
+                    auto_allow: false,
+                    description: None,
+                    tags: Vec::new(),
+                },
+            )
+        })
+        .collect()
+}
+
+/// Servers added by magic setup. A `default_mcp_servers.toml` in the config dir, using the
+/// same `[mcp_servers.<name>]` tables as preferences.toml, replaces the built-in set.
+pub fn load_default_mcp_servers(config_dir: &Path) -> Result<BTreeMap<String, McpServerConfig>> {
+    let path = config_dir.join("default_mcp_servers.toml");
+    if !path.exists() {
+        return Ok(builtin_default_mcp_servers());
+    }
+
+    let content =
+        fs::read_to_string(&path).with_context(|| format!("Failed to read {}", path.display()))?;
+    let file: DefaultMcpServersFile =
+        toml::from_str(&content).with_context(|| format!("Failed to parse {}", path.display()))?;
+    Ok(file.mcp_servers)
+}
+
+pub const PRESET_NAMES: &[&str] = &[
+    "core_unix_tools",
+    "file_operations",
+    "code_search",
+    "network_tools",
+    "development_tools",
+    "web_access",
+];
+
+/// Maps each preset member to the presets that include it, in `PRESET_NAMES` order.
+pub fn tool_preset_index() -> HashMap<&'static str, Vec<&'static str>> {
+    let mut index: HashMap<&'static str, Vec<&'static str>> = HashMap::new();
+    for preset in PRESET_NAMES {
+        for tool in get_preset_tools(preset).unwrap_or_default() {
+            index.entry(tool).or_default().push(preset);
+        }
+    }
+    index
+}
+
+/// Built-in agent tools that aren't shell commands, beyond the ones presets cover.
+const AGENT_TOOLS: &[&str] = &[
+    "Bash",
+    "Grep",
+    "LS",
+    "MultiEdit",
+    "NotebookEdit",
+    "Task",
+    "TodoWrite",
+    "LSP",
+    "AskFollowupQuestion",
+];
+
+/// Every tool name mooagent knows about: preset members plus common agent tools.
+pub fn known_tool_names() -> BTreeSet<&'static str> {
+    PRESET_NAMES
+        .iter()
+        .filter_map(|preset| get_preset_tools(preset))
+        .flatten()
+        .chain(AGENT_TOOLS.iter().copied())
+        .collect()
+}
+
+/// Warns about a tool name that is neither a known agent tool nor a command on PATH,
+/// suggesting the closest known name when it looks like a typo.
+pub fn unknown_tool_warning(name: &str) -> Option<String> {
+    let known = known_tool_names();
+    if known.contains(name) || crate::config::command_exists(name) {
+        return None;
+    }
+
+    let suggestion = known
+        .iter()
+        .map(|k| (edit_distance(&name.to_lowercase(), &k.to_lowercase()), k))
+        .filter(|(distance, _)| *distance <= 2)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, k)| format!(" (did you mean '{}'?)", k))
+        .unwrap_or_default();
+
+    Some(format!(
+        "Unknown tool '{}' - saved anyway{}",
+        name, suggestion
+    ))
+}
+
+/// Levenshtein distance, counting a swap of adjacent characters as one edit.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let mut dist = vec![vec![0; b.len() + 1]; a.len() + 1];
+    for (i, row) in dist.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    dist[0] = (0..=b.len()).collect();
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            dist[i][j] = (dist[i - 1][j] + 1)
+                .min(dist[i][j - 1] + 1)
+                .min(dist[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                dist[i][j] = dist[i][j].min(dist[i - 2][j - 2] + 1);
+            }
+        }
+    }
+    dist[a.len()][b.len()]
+}
+
 pub fn get_preset_tools(group_name: &str) -> Option<Vec<&'static str>> {
     match group_name {
         "core_unix_tools" => Some(vec![
@@ -441,6 +1286,35 @@ fn read_json_or_empty(path: &Path) -> serde_json::Map<String, serde_json::Value>
     serde_json::Map::new()
 }
 
+/// Merges generated entries into the object at `key`, replacing entries mooagent
+/// produces and leaving ones the user added directly in the agent config.
+fn merge_json_object(
+    map: &mut serde_json::Map<String, serde_json::Value>,
+    key: &str,
+    generated: serde_json::Map<String, serde_json::Value>,
+) {
+    match map.get_mut(key).and_then(|v| v.as_object_mut()) {
+        Some(existing) => existing.extend(generated),
+        None => {
+            map.insert(key.to_string(), serde_json::Value::Object(generated));
+        }
+    }
+}
+
+/// Sets `map[object_key][key]`, creating the object if needed and keeping its other keys.
+fn set_nested_json(
+    map: &mut serde_json::Map<String, serde_json::Value>,
+    object_key: &str,
+    key: &str,
+    value: serde_json::Value,
+) {
+    merge_json_object(
+        map,
+        object_key,
+        serde_json::Map::from_iter([(key.to_string(), value)]),
+    );
+}
+
 pub struct ClaudeConfigGenerator {
     pub config_dir: PathBuf,
     pub user_config_path: PathBuf,
@@ -451,12 +1325,24 @@ impl ConfigGenerator for ClaudeConfigGenerator {
         "Claude"
     }
 
+    fn managed_objects(&self) -> Vec<(PathBuf, &'static str)> {
+        vec![(self.user_config_path.clone(), "mcpServers")]
+    }
+
+    fn supported_general_settings(&self) -> &'static [GeneralSetting] {
+        &[
+            GeneralSetting::AutoAcceptTools,
+            GeneralSetting::SandboxedMode,
+        ]
+    }
+
     fn generate(
         &self,
         prefs: &AgentPreferences,
         credentials: Option<&CredentialManager>,
     ) -> Result<Vec<(PathBuf, String)>> {
         let agent_prefs = prefs.agent_specific.get("Claude");
+        let general = prefs.general_for("Claude");
         let mut results = Vec::new();
 
         let settings_path = self.config_dir.join("settings.json");
@@ -522,8 +1408,29 @@ impl ConfigGenerator for ClaudeConfigGenerator {
             );
         }
 
+        if let Some(auto_accept) = general.auto_accept_tools {
+            let mode = if auto_accept {
+                "acceptEdits"
+            } else {
+                "default"
+            };
+            perm_map.insert(
+                "defaultMode".to_string(),
+                serde_json::Value::String(mode.to_string()),
+            );
+        }
+
         if !perm_map.is_empty() {
-            settings_map.insert("permissions".to_string(), serde_json::Value::Object(perm_map));
+            merge_json_object(&mut settings_map, "permissions", perm_map);
+        }
+
+        if let Some(sandboxed) = general.sandboxed_mode {
+            set_nested_json(
+                &mut settings_map,
+                "sandbox",
+                "enabled",
+                serde_json::Value::Bool(sandboxed),
+            );
         }
 
         results.push((settings_path, serde_json::to_string_pretty(&settings_map)?));
@@ -573,8 +1480,10 @@ impl ConfigGenerator for ClaudeConfigGenerator {
                             "type".to_string(),
                             serde_json::Value::String("sse".to_string()),
                         );
-                        server_def
-                            .insert("url".to_string(), serde_json::Value::String(url.clone()));
+                        server_def.insert(
+                            "url".to_string(),
+                            serde_json::Value::String(expand_env_vars(url)),
+                        );
                         if let Some(headers) = get_auth_headers(config, credentials) {
                             server_def
                                 .insert("headers".to_string(), serde_json::Value::Object(headers));
@@ -595,7 +1504,7 @@ impl ConfigGenerator for ClaudeConfigGenerator {
                         );
                         server_def.insert(
                             "url".to_string(),
-                            serde_json::Value::String(http_url.clone()),
+                            serde_json::Value::String(expand_env_vars(http_url)),
                         );
                         if let Some(headers) = get_auth_headers(config, credentials) {
                             server_def
@@ -606,10 +1515,14 @@ impl ConfigGenerator for ClaudeConfigGenerator {
                                 .insert("autoAllow".to_string(), serde_json::Value::Bool(true));
                         }
                     }
+                    McpServerConfig::Unknown(_) => {
+                        log::warn!("Skipping MCP server '{}' with unrecognized config", name);
+                        continue;
+                    }
                 }
                 servers.insert(name.clone(), serde_json::Value::Object(server_def));
             }
-            user_map.insert("mcpServers".to_string(), serde_json::Value::Object(servers));
+            merge_json_object(&mut user_map, "mcpServers", servers);
         }
 
         results.push((
@@ -630,7 +1543,7 @@ fn build_claude_permissions(prefs: &AgentPreferences) -> ToolPermissions {
 
     let enabled_tools = expand_tools(prefs);
     for (tool, enabled) in &enabled_tools {
-        if let Some(pattern) = format_tool_permission(tool)
+        if let Some(pattern) = format_tool_permission(tool, prefs)
             && *enabled
             && !result.allow.contains(&pattern)
             && !result.ask.contains(&pattern)
@@ -663,7 +1576,12 @@ fn build_claude_permissions(prefs: &AgentPreferences) -> ToolPermissions {
     result
 }
 
-fn format_tool_permission(tool: &str) -> Option<String> {
+fn format_tool_permission(tool: &str, prefs: &AgentPreferences) -> Option<String> {
+    // User-provided patterns win over everything else, including the skip list
+    if let Some(pattern) = prefs.permission_overrides.get(tool) {
+        return Some(pattern.clone());
+    }
+
     // Only generate permissions for Bash commands - these use prefix matching with :*
     // Other tools either:
     // - Don't need permissions (Glob, Grep - read-only)
@@ -677,7 +1595,7 @@ fn format_tool_permission(tool: &str) -> Option<String> {
         "NotebookEdit", "LSP", "AskFollowupQuestion",
     ];
 
-    if skip_tools.contains(&tool) {
+    if skip_tools.contains(&tool) || prefs.permission_skip_tools.iter().any(|t| t == tool) {
         return None;
     }
 
@@ -694,27 +1612,45 @@ impl ConfigGenerator for GeminiConfigGenerator {
         "Gemini"
     }
 
+    fn managed_objects(&self) -> Vec<(PathBuf, &'static str)> {
+        vec![(self.config_dir.join("settings.json"), "mcpServers")]
+    }
+
+    fn supported_general_settings(&self) -> &'static [GeneralSetting] {
+        // Not EnableLogging: Gemini's telemetry sends usage data rather than logging locally
+        &[
+            GeneralSetting::AutoAcceptTools,
+            GeneralSetting::SandboxedMode,
+        ]
+    }
+
     fn generate(
         &self,
         prefs: &AgentPreferences,
         credentials: Option<&CredentialManager>,
     ) -> Result<Vec<(PathBuf, String)>> {
         let agent_prefs = prefs.agent_specific.get("Gemini");
+        let general = prefs.general_for("Gemini");
         let mut results = Vec::new();
 
         let settings_path = self.config_dir.join("settings.json");
         let mut settings_map = read_json_or_empty(&settings_path);
 
-        if let Some(auto_accept) = prefs.general.auto_accept_tools {
-            let tools_obj = settings_map
-                .entry("tools".to_string())
-                .or_insert(serde_json::Value::Object(serde_json::Map::new()));
-            if let Some(tools_map) = tools_obj.as_object_mut() {
-                tools_map.insert(
-                    "autoAccept".to_string(),
-                    serde_json::Value::Bool(auto_accept),
-                );
-            }
+        if let Some(auto_accept) = general.auto_accept_tools {
+            set_nested_json(
+                &mut settings_map,
+                "tools",
+                "autoAccept",
+                serde_json::Value::Bool(auto_accept),
+            );
+        }
+        if let Some(sandboxed) = general.sandboxed_mode {
+            set_nested_json(
+                &mut settings_map,
+                "tools",
+                "sandbox",
+                serde_json::Value::Bool(sandboxed),
+            );
         }
 
         if let Some(ap) = agent_prefs
@@ -765,7 +1701,10 @@ impl ConfigGenerator for GeminiConfigGenerator {
                     auto_allow,
                     ..
                 } => {
-                    server_def.insert("url".to_string(), serde_json::Value::String(url.clone()));
+                    server_def.insert(
+                        "url".to_string(),
+                        serde_json::Value::String(expand_env_vars(url)),
+                    );
                     if let Some(headers) = get_auth_headers(config, credentials) {
                         server_def
                             .insert("headers".to_string(), serde_json::Value::Object(headers));
@@ -782,7 +1721,7 @@ impl ConfigGenerator for GeminiConfigGenerator {
                 } => {
                     server_def.insert(
                         "httpUrl".to_string(),
-                        serde_json::Value::String(http_url.clone()),
+                        serde_json::Value::String(expand_env_vars(http_url)),
                     );
                     if let Some(headers) = get_auth_headers(config, credentials) {
                         server_def
@@ -793,10 +1732,14 @@ impl ConfigGenerator for GeminiConfigGenerator {
                             .insert("autoAllow".to_string(), serde_json::Value::Bool(true));
                     }
                 }
+                McpServerConfig::Unknown(_) => {
+                    log::warn!("Skipping MCP server '{}' with unrecognized config", name);
+                    continue;
+                }
             }
             servers.insert(name.clone(), serde_json::Value::Object(server_def));
         }
-        settings_map.insert("mcpServers".to_string(), serde_json::Value::Object(servers));
+        merge_json_object(&mut settings_map, "mcpServers", servers);
         results.push((settings_path, serde_json::to_string_pretty(&settings_map)?));
 
         let mut enabled_tools = expand_tools(prefs);
@@ -830,6 +1773,14 @@ impl ConfigGenerator for OpenCodeConfigGenerator {
         "OpenCode"
     }
 
+    fn managed_objects(&self) -> Vec<(PathBuf, &'static str)> {
+        vec![(self.config_dir.join("opencode.json"), "mcp")]
+    }
+
+    fn supported_general_settings(&self) -> &'static [GeneralSetting] {
+        &[GeneralSetting::AutoAcceptTools]
+    }
+
     fn generate(
         &self,
         prefs: &AgentPreferences,
@@ -880,7 +1831,10 @@ impl ConfigGenerator for OpenCodeConfigGenerator {
                         "type".to_string(),
                         serde_json::Value::String("remote".to_string()),
                     );
-                    server_def.insert("url".to_string(), serde_json::Value::String(url.clone()));
+                    server_def.insert(
+                        "url".to_string(),
+                        serde_json::Value::String(expand_env_vars(url)),
+                    );
                     if let Some(headers) = get_auth_headers(config, credentials) {
                         server_def
                             .insert("headers".to_string(), serde_json::Value::Object(headers));
@@ -901,7 +1855,7 @@ impl ConfigGenerator for OpenCodeConfigGenerator {
                     );
                     server_def.insert(
                         "url".to_string(),
-                        serde_json::Value::String(http_url.clone()),
+                        serde_json::Value::String(expand_env_vars(http_url)),
                     );
                     if let Some(headers) = get_auth_headers(config, credentials) {
                         server_def
@@ -912,10 +1866,25 @@ impl ConfigGenerator for OpenCodeConfigGenerator {
                             .insert("autoAllow".to_string(), serde_json::Value::Bool(true));
                     }
                 }
+                McpServerConfig::Unknown(_) => {
+                    log::warn!("Skipping MCP server '{}' with unrecognized config", name);
+                    continue;
+                }
             }
             mcp_servers.insert(name.clone(), serde_json::Value::Object(server_def));
         }
-        config_map.insert("mcp".to_string(), serde_json::Value::Object(mcp_servers));
+        merge_json_object(&mut config_map, "mcp", mcp_servers);
+
+        // Only edits, like the other agents' auto-accept; shell commands keep asking
+        if let Some(auto_accept) = prefs.general_for("OpenCode").auto_accept_tools {
+            let level = if auto_accept { "allow" } else { "ask" };
+            let mut permission = serde_json::Map::new();
+            permission.insert(
+                "edit".to_string(),
+                serde_json::Value::String(level.to_string()),
+            );
+            merge_json_object(&mut config_map, "permission", permission);
+        }
 
         let mut enabled_tools = expand_tools(prefs);
 
@@ -943,3 +1912,245 @@ impl ConfigGenerator for OpenCodeConfigGenerator {
         Ok(results)
     }
 }
+
+/// Continue.dev keeps MCP servers in an array rather than a map keyed by name, so the
+/// whole `experimental.modelContextProtocolServers` array is owned by mooagent.
+pub struct ContinueConfigGenerator {
+    pub config_dir: PathBuf,
+}
+
+impl ConfigGenerator for ContinueConfigGenerator {
+    fn agent_name(&self) -> &str {
+        "Continue"
+    }
+
+    fn preview_server(
+        &self,
+        name: &str,
+        config: &McpServerConfig,
+    ) -> Result<Option<serde_json::Value>> {
+        let mut prefs = AgentPreferences::default();
+        prefs.mcp_servers.insert(name.to_string(), config.clone());
+        let files = self.generate(&prefs, None)?;
+        let Some((_, content)) = files.first() else {
+            return Ok(None);
+        };
+        let value: serde_json::Value = serde_json::from_str(content)?;
+        Ok(value["experimental"]["modelContextProtocolServers"]
+            .as_array()
+            .and_then(|servers| servers.iter().find(|s| s["name"] == name))
+            .cloned())
+    }
+
+    fn generate(
+        &self,
+        prefs: &AgentPreferences,
+        credentials: Option<&CredentialManager>,
+    ) -> Result<Vec<(PathBuf, String)>> {
+        let config_path = self.config_dir.join("config.json");
+        let mut config_map = read_json_or_empty(&config_path);
+
+        let mut servers = Vec::new();
+        for (name, config) in &prefs.mcp_servers {
+            let mut transport = serde_json::Map::new();
+            match config {
+                McpServerConfig::Stdio {
+                    command, args, env, ..
+                } => {
+                    transport.insert(
+                        "type".to_string(),
+                        serde_json::Value::String("stdio".to_string()),
+                    );
+                    transport.insert(
+                        "command".to_string(),
+                        serde_json::Value::String(command.clone()),
+                    );
+                    transport.insert(
+                        "args".to_string(),
+                        serde_json::Value::Array(
+                            args.iter()
+                                .map(|s| serde_json::Value::String(s.clone()))
+                                .collect(),
+                        ),
+                    );
+                    if !env.is_empty() {
+                        transport.insert("env".to_string(), serde_json::to_value(env)?);
+                    }
+                }
+                McpServerConfig::Sse { url, .. } | McpServerConfig::Http { http_url: url, .. } => {
+                    let transport_type = if matches!(config, McpServerConfig::Sse { .. }) {
+                        "sse"
+                    } else {
+                        "streamable-http"
+                    };
+                    transport.insert(
+                        "type".to_string(),
+                        serde_json::Value::String(transport_type.to_string()),
+                    );
+                    transport.insert(
+                        "url".to_string(),
+                        serde_json::Value::String(expand_env_vars(url)),
+                    );
+                    if let Some(headers) = get_auth_headers(config, credentials) {
+                        let mut request_options = serde_json::Map::new();
+                        request_options
+                            .insert("headers".to_string(), serde_json::Value::Object(headers));
+                        transport.insert(
+                            "requestOptions".to_string(),
+                            serde_json::Value::Object(request_options),
+                        );
+                    }
+                }
+                McpServerConfig::Unknown(_) => {
+                    log::warn!("Skipping MCP server '{}' with unrecognized config", name);
+                    continue;
+                }
+            }
+
+            let mut server_def = serde_json::Map::new();
+            server_def.insert("name".to_string(), serde_json::Value::String(name.clone()));
+            server_def.insert(
+                "transport".to_string(),
+                serde_json::Value::Object(transport),
+            );
+            servers.push((name, serde_json::Value::Object(server_def)));
+        }
+        // Keep the array order stable across syncs so drift checks don't flap
+        servers.sort_by(|a, b| a.0.cmp(b.0));
+
+        let experimental = config_map
+            .entry("experimental".to_string())
+            .or_insert(serde_json::Value::Object(serde_json::Map::new()));
+        if let Some(experimental_map) = experimental.as_object_mut() {
+            experimental_map.insert(
+                "modelContextProtocolServers".to_string(),
+                serde_json::Value::Array(servers.into_iter().map(|(_, v)| v).collect()),
+            );
+        }
+
+        Ok(vec![(
+            config_path,
+            serde_json::to_string_pretty(&config_map)?,
+        )])
+    }
+}
+
+/// Cursor reads MCP servers from `~/.cursor/mcp.json`. Remote servers of either
+/// transport are written as a plain `url`.
+pub struct CursorConfigGenerator {
+    pub config_dir: PathBuf,
+}
+
+impl ConfigGenerator for CursorConfigGenerator {
+    fn agent_name(&self) -> &str {
+        "Cursor"
+    }
+
+    fn managed_objects(&self) -> Vec<(PathBuf, &'static str)> {
+        vec![(self.config_dir.join("mcp.json"), "mcpServers")]
+    }
+
+    fn generate(
+        &self,
+        prefs: &AgentPreferences,
+        credentials: Option<&CredentialManager>,
+    ) -> Result<Vec<(PathBuf, String)>> {
+        let config_path = self.config_dir.join("mcp.json");
+        let mut config_map = read_json_or_empty(&config_path);
+
+        let mut servers = serde_json::Map::new();
+        for (name, config) in &prefs.mcp_servers {
+            let mut server_def = serde_json::Map::new();
+            match config {
+                McpServerConfig::Stdio {
+                    command, args, env, ..
+                } => {
+                    server_def.insert(
+                        "command".to_string(),
+                        serde_json::Value::String(command.clone()),
+                    );
+                    server_def.insert(
+                        "args".to_string(),
+                        serde_json::Value::Array(
+                            args.iter()
+                                .map(|s| serde_json::Value::String(s.clone()))
+                                .collect(),
+                        ),
+                    );
+                    server_def.insert("env".to_string(), serde_json::to_value(env)?);
+                }
+                McpServerConfig::Sse { url, .. } | McpServerConfig::Http { http_url: url, .. } => {
+                    server_def.insert(
+                        "url".to_string(),
+                        serde_json::Value::String(expand_env_vars(url)),
+                    );
+                    if let Some(headers) = get_auth_headers(config, credentials) {
+                        server_def
+                            .insert("headers".to_string(), serde_json::Value::Object(headers));
+                    }
+                }
+                McpServerConfig::Unknown(_) => {
+                    log::warn!("Skipping MCP server '{}' with unrecognized config", name);
+                    continue;
+                }
+            }
+            servers.insert(name.clone(), serde_json::Value::Object(server_def));
+        }
+        merge_json_object(&mut config_map, "mcpServers", servers);
+
+        Ok(vec![(
+            config_path,
+            serde_json::to_string_pretty(&config_map)?,
+        )])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expand_env_vars() {
+        let path = std::env::var("PATH").unwrap();
+        assert_eq!(
+            expand_env_vars("https://${PATH}.example.com/mcp"),
+            format!("https://{}.example.com/mcp", path)
+        );
+        assert_eq!(
+            expand_env_vars("https://${MOOAGENT_UNSET_TENANT}.example.com/${}"),
+            "https://${MOOAGENT_UNSET_TENANT}.example.com/${}"
+        );
+        assert_eq!(expand_env_vars("no ${closing"), "no ${closing");
+    }
+
+    #[test]
+    fn test_rename_presets() {
+        let mut prefs: toml::Table = toml::from_str(
+            r#"
+[tool_presets.unix_tools]
+enabled = false
+
+[tool_presets.web]
+enabled = true
+
+[tool_presets.web_access]
+enabled = false
+"#,
+        )
+        .unwrap();
+
+        let mut notes = Vec::new();
+        rename_presets(
+            &mut prefs,
+            &[("unix_tools", "core_unix_tools"), ("web", "web_access")],
+            &mut notes,
+        );
+
+        let presets = prefs["tool_presets"].as_table().unwrap();
+        assert_eq!(presets["core_unix_tools"]["enabled"].as_bool(), Some(false));
+        assert_eq!(presets["web_access"]["enabled"].as_bool(), Some(false));
+        assert!(!presets.contains_key("unix_tools"));
+        assert!(!presets.contains_key("web"));
+        assert_eq!(notes.len(), 2);
+    }
+}
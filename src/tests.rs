@@ -1,11 +1,31 @@
 #[cfg(test)]
+#[allow(clippy::module_inception)]
 mod tests {
     use crate::preferences::ConfigGenerator;
     use serde_json::json;
     use std::fs;
-    use std::path::PathBuf;
+    use std::path::{Path, PathBuf};
     use tempfile::tempdir;
 
+    /// A `ConfigPaths` with everything under `dir` (config in `dir/config`) and no agents.
+    fn test_paths(dir: &Path) -> crate::config::ConfigPaths {
+        let config_dir = dir.join("config");
+        crate::config::ConfigPaths {
+            project_agents: dir.join("AGENTS.md"),
+            config_file: dir.join(".mooagent.toml"),
+            agent_configs: Vec::new(),
+            global_rules_primary: config_dir.join("GLOBAL_RULES.md"),
+            backup_dir: dir.join("backups"),
+            project_id: "proj".to_string(),
+            preferences: crate::preferences::PreferenceManager::new(&config_dir),
+            config_dir,
+            ui_state_file: dir.join("ui_state.json"),
+            sync_state_file: dir.join("sync_state.json"),
+            managed_entries_file: dir.join("managed_entries.json"),
+            audit_log_file: dir.join("audit.jsonl"),
+        }
+    }
+
     #[test]
     fn test_merge_strategy_claude() {
         let dir = tempdir().unwrap();
@@ -90,6 +110,8 @@ mod tests {
                 env,
                 disabled_tools: Vec::new(),
                 auto_allow: false,
+                description: None,
+                tags: Vec::new(),
             },
         );
 
@@ -110,6 +132,10 @@ mod tests {
             new_config["mcpServers"]["new-server"]["env"]["API_KEY"],
             "123"
         );
+        assert_eq!(
+            new_config["mcpServers"]["existing-server"]["command"],
+            "node"
+        );
     }
 
     #[test]
@@ -125,6 +151,8 @@ mod tests {
             status: crate::config::AgentStatus::Ok,
             strategy: crate::config::SyncStrategy::Merge,
             sync_status: crate::config::AgentSyncStatus::default(),
+            last_synced: None,
+            link: None,
         }];
 
         let defaults = vec![
@@ -155,6 +183,8 @@ mod tests {
                             env: std::collections::HashMap::new(),
                             disabled_tools: Vec::new(),
                             auto_allow: false,
+                            description: None,
+                            tags: Vec::new(),
                         },
                     );
                 }
@@ -193,6 +223,8 @@ mod tests {
                 env: std::collections::HashMap::new(),
                 disabled_tools: Vec::new(),
                 auto_allow: false,
+                description: None,
+                tags: Vec::new(),
             },
         );
         global_prefs.mcp_servers.insert(
@@ -203,6 +235,8 @@ mod tests {
                 env: std::collections::HashMap::new(),
                 disabled_tools: Vec::new(),
                 auto_allow: false,
+                description: None,
+                tags: Vec::new(),
             },
         );
         mgr.global_prefs = global_prefs;
@@ -249,6 +283,8 @@ mod tests {
                 env: std::collections::HashMap::new(),
                 disabled_tools: vec!["dangerous_tool".to_string()],
                 auto_allow: true,
+                description: None,
+                tags: Vec::new(),
             },
         );
 
@@ -278,4 +314,3018 @@ mod tests {
         // Disabled MCP tool gets exact deny entry (not wildcard pattern)
         assert!(deny.iter().any(|v| v.as_str() == Some("mcp__test-server__dangerous_tool")));
     }
+
+    #[test]
+    fn test_claude_permission_overrides() {
+        let dir = tempdir().unwrap();
+        let config_dir = dir.path().join(".claude");
+        fs::create_dir_all(&config_dir).unwrap();
+
+        let generator = crate::preferences::ClaudeConfigGenerator {
+            config_dir: config_dir.clone(),
+            user_config_path: dir.path().join(".claude.json"),
+        };
+
+        let mut prefs = crate::preferences::AgentPreferences::default();
+        prefs.individual_tools.insert("mytool".to_string(), true);
+        prefs.individual_tools.insert("secret".to_string(), true);
+        prefs.individual_tools.insert("git".to_string(), true);
+        prefs
+            .permission_overrides
+            .insert("mytool".to_string(), "Bash(mytool subcommand:*)".to_string());
+        prefs.permission_skip_tools.push("secret".to_string());
+
+        let results = generator.generate(&prefs, None).unwrap();
+
+        let settings_path = config_dir.join("settings.json");
+        let (_, content) = results.iter().find(|(p, _)| p == &settings_path).unwrap();
+        let settings: serde_json::Value = serde_json::from_str(content).unwrap();
+        let allow = settings["permissions"]["allow"].as_array().unwrap();
+
+        assert!(
+            allow
+                .iter()
+                .any(|v| v.as_str() == Some("Bash(mytool subcommand:*)"))
+        );
+        assert!(!allow.iter().any(|v| v.as_str() == Some("Bash(mytool:*)")));
+        assert!(!allow.iter().any(|v| v.as_str() == Some("Bash(secret:*)")));
+        assert!(allow.iter().any(|v| v.as_str() == Some("Bash(git:*)")));
+    }
+
+    #[test]
+    fn test_json_changed_keys() {
+        let old = json!({
+            "theme": "dark",
+            "permissions": { "allow": ["Bash(git:*)"], "deny": [] },
+            "mcpServers": { "a": { "command": "x" } }
+        });
+        let new = json!({
+            "theme": "dark",
+            "permissions": { "allow": ["Bash(git:*)", "Bash(npm:*)"], "deny": [] },
+            "mcpServers": { "a": { "command": "y" }, "b": { "command": "z" } },
+            "fontSize": 14
+        });
+
+        let keys = crate::config::json_changed_keys(&old.to_string(), &new.to_string());
+
+        assert_eq!(
+            keys,
+            vec![
+                "fontSize".to_string(),
+                "mcpServers.a".to_string(),
+                "mcpServers.b".to_string(),
+                "permissions.allow".to_string(),
+            ]
+        );
+        assert!(crate::config::json_changed_keys("{}", "{}").is_empty());
+    }
+
+    #[test]
+    fn test_atomic_write_failure_keeps_original() {
+        use std::io::Write;
+
+        let dir = tempdir().unwrap();
+        let settings_path = dir.path().join("settings.json");
+        fs::write(&settings_path, r#"{"theme": "dark"}"#).unwrap();
+
+        let result = crate::config::write_atomic_with(&settings_path, |file| {
+            file.write_all(br#"{"theme": "li"#)?;
+            Err(std::io::Error::other("simulated crash"))
+        });
+
+        assert!(result.is_err());
+        assert_eq!(
+            fs::read_to_string(&settings_path).unwrap(),
+            r#"{"theme": "dark"}"#
+        );
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 1);
+
+        crate::config::write_atomic(&settings_path, r#"{"theme": "light"}"#).unwrap();
+        assert_eq!(
+            fs::read_to_string(&settings_path).unwrap(),
+            r#"{"theme": "light"}"#
+        );
+    }
+
+    #[test]
+    fn test_bearer_token_migrated_to_credential_store() {
+        let dir = tempdir().unwrap();
+        let config_dir = dir.path().join(".config");
+        fs::create_dir_all(&config_dir).unwrap();
+
+        let mut mgr = crate::preferences::PreferenceManager::new(&config_dir);
+        let mut prefs = crate::preferences::AgentPreferences::default();
+        prefs.mcp_servers.insert(
+            "remote".to_string(),
+            crate::preferences::McpServerConfig::Sse {
+                url: "https://example.com/sse".to_string(),
+                auth: crate::preferences::McpAuth::Bearer {
+                    token: "secret".to_string(),
+                },
+                headers: Default::default(),
+                headers_override_auth: false,
+                disabled_tools: Vec::new(),
+                auto_allow: false,
+                description: None,
+                tags: Vec::new(),
+            },
+        );
+        mgr.global_prefs = prefs;
+
+        let mut credentials = crate::credentials::CredentialManager::new(&config_dir);
+        let (global_changed, project_changed) =
+            mgr.migrate_inline_bearer_tokens(&mut credentials).unwrap();
+        assert!(global_changed);
+        assert!(!project_changed);
+
+        let auth = mgr.global_prefs.mcp_servers["remote"].auth().unwrap();
+        assert_eq!(
+            auth,
+            &crate::preferences::McpAuth::BearerRef {
+                key: "https://example.com/sse".to_string()
+            }
+        );
+        assert!(
+            !toml::to_string(&mgr.global_prefs)
+                .unwrap()
+                .contains("secret")
+        );
+
+        let generator = crate::preferences::ClaudeConfigGenerator {
+            config_dir: dir.path().join(".claude"),
+            user_config_path: dir.path().join(".claude.json"),
+        };
+        let results = generator
+            .generate(&mgr.get_merged(), Some(&credentials))
+            .unwrap();
+        let (_, content) = results
+            .iter()
+            .find(|(p, _)| p == &dir.path().join(".claude.json"))
+            .unwrap();
+        let config: serde_json::Value = serde_json::from_str(content).unwrap();
+        assert_eq!(
+            config["mcpServers"]["remote"]["headers"]["Authorization"],
+            "Bearer secret"
+        );
+
+        let (global_changed, _) = mgr.migrate_inline_bearer_tokens(&mut credentials).unwrap();
+        assert!(!global_changed);
+    }
+
+    #[test]
+    fn test_command_exists() {
+        let dir = tempdir().unwrap();
+        let binary = dir.path().join("mooagent");
+        fs::write(&binary, "").unwrap();
+
+        assert!(crate::config::command_exists(binary.to_str().unwrap()));
+        assert!(!crate::config::command_exists(
+            dir.path().join("missing/mooagent").to_str().unwrap()
+        ));
+        assert!(!crate::config::command_exists(
+            "mooagent-definitely-not-installed"
+        ));
+    }
+
+    #[test]
+    fn test_rescale_scroll() {
+        use crate::app::rescale_scroll;
+
+        assert_eq!(rescale_scroll(10, 100, 100), 10);
+        assert_eq!(rescale_scroll(50, 100, 200), 100);
+        assert_eq!(rescale_scroll(80, 100, 10), 8);
+        assert_eq!(rescale_scroll(5, 10, 0), 0);
+        assert_eq!(rescale_scroll(30, 20, 20), 19);
+        assert_eq!(rescale_scroll(0, 0, 50), 0);
+    }
+
+    #[test]
+    fn test_ui_state_roundtrip_and_fallback() {
+        use crate::app::{ActiveTab, Focus, UiState};
+
+        let dir = tempdir().unwrap();
+        let state_path = dir.path().join("ui_state.json");
+
+        let defaults = UiState::load(&state_path);
+        assert_eq!(defaults.active_tab, ActiveTab::Dashboard);
+        assert_eq!(defaults.focus, Focus::Agents);
+        assert!(!defaults.auto_sync);
+
+        UiState {
+            active_tab: ActiveTab::McpServers,
+            focus: Focus::Project,
+            auto_sync: true,
+        }
+        .save(&state_path)
+        .unwrap();
+        let restored = UiState::load(&state_path);
+        assert_eq!(restored.active_tab, ActiveTab::McpServers);
+        assert_eq!(restored.focus, Focus::Project);
+        assert!(restored.auto_sync);
+
+        fs::write(&state_path, "not json").unwrap();
+        assert_eq!(UiState::load(&state_path).active_tab, ActiveTab::Dashboard);
+    }
+
+    #[test]
+    fn test_mcp_batch_request() {
+        let mut state = crate::mcp::McpState::default();
+        let batch = json!([
+            {"jsonrpc": "2.0", "id": 1, "method": "initialize", "params": {}},
+            {"jsonrpc": "2.0", "method": "notifications/initialized"},
+            {"jsonrpc": "2.0", "id": 2, "method": "tools/list"}
+        ]);
+
+        let response = crate::mcp::handle_message(&mut state, &batch.to_string()).unwrap();
+        let response: serde_json::Value = serde_json::from_str(&response).unwrap();
+        let responses = response.as_array().unwrap();
+
+        assert_eq!(responses.len(), 2);
+        assert_eq!(responses[0]["id"], 1);
+        assert_eq!(responses[0]["result"]["serverInfo"]["name"], "mooagent");
+        assert_eq!(responses[1]["id"], 2);
+        assert!(responses[1]["result"]["tools"].is_array());
+    }
+
+    #[test]
+    fn test_mcp_invalid_messages() {
+        let mut state = crate::mcp::McpState::default();
+        let response = crate::mcp::handle_message(&mut state, "{not json").unwrap();
+        let response: serde_json::Value = serde_json::from_str(&response).unwrap();
+        assert_eq!(response["error"]["code"], -32700);
+
+        let response = crate::mcp::handle_message(&mut state, "[]").unwrap();
+        let response: serde_json::Value = serde_json::from_str(&response).unwrap();
+        assert_eq!(response["error"]["code"], -32600);
+
+        let response =
+            crate::mcp::handle_message(&mut state, r#"[{"jsonrpc": "2.0", "id": 7}]"#).unwrap();
+        let response: serde_json::Value = serde_json::from_str(&response).unwrap();
+        assert_eq!(response[0]["id"], 7);
+        assert_eq!(response[0]["error"]["code"], -32600);
+
+        assert!(
+            crate::mcp::handle_message(
+                &mut state,
+                r#"[{"jsonrpc": "2.0", "method": "notifications/initialized"}]"#
+            )
+            .is_none()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_mcp_http_transport() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            axum::serve(listener, crate::mcp_http::router())
+                .await
+                .unwrap();
+        });
+
+        let client = reqwest::Client::new();
+
+        let health: serde_json::Value = client
+            .get(format!("http://{}/health", addr))
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        assert_eq!(health["status"], "ok");
+
+        let response: serde_json::Value = client
+            .post(format!("http://{}/mcp", addr))
+            .json(&json!({"jsonrpc": "2.0", "id": 1, "method": "tools/list"}))
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        assert_eq!(response["id"], 1);
+        assert!(response["result"]["tools"].is_array());
+
+        let notification = client
+            .post(format!("http://{}/mcp", addr))
+            .json(&json!({"jsonrpc": "2.0", "method": "notifications/initialized"}))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(notification.status(), reqwest::StatusCode::ACCEPTED);
+
+        let forbidden = client
+            .post(format!("http://{}/mcp", addr))
+            .header("Origin", "https://evil.example.com")
+            .json(&json!({"jsonrpc": "2.0", "id": 2, "method": "tools/list"}))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(forbidden.status(), reqwest::StatusCode::FORBIDDEN);
+    }
+
+    #[test]
+    fn test_default_mcp_servers_override() {
+        let dir = tempdir().unwrap();
+
+        let builtin = crate::preferences::load_default_mcp_servers(dir.path()).unwrap();
+        assert!(builtin.contains_key("filesystem"));
+        assert!(builtin.contains_key("memory-uvx"));
+
+        fs::write(
+            dir.path().join("default_mcp_servers.toml"),
+            r#"
+[mcp_servers.team-docs]
+command = "team-docs-mcp"
+args = ["--readonly"]
+
+[mcp_servers.team-api]
+url = "https://mcp.example.com/sse"
+"#,
+        )
+        .unwrap();
+
+        let team = crate::preferences::load_default_mcp_servers(dir.path()).unwrap();
+        assert_eq!(team.len(), 2);
+        assert!(!team.contains_key("filesystem"));
+        if let crate::preferences::McpServerConfig::Stdio { command, args, .. } = &team["team-docs"]
+        {
+            assert_eq!(command, "team-docs-mcp");
+            assert_eq!(args, &vec!["--readonly".to_string()]);
+        } else {
+            panic!("Expected Stdio config");
+        }
+        assert_eq!(team["team-api"].url(), Some("https://mcp.example.com/sse"));
+    }
+
+    #[test]
+    fn test_diff_preferences() {
+        use crate::preferences::AgentPreferences;
+
+        let work: AgentPreferences = serde_json::from_str(
+            r#"{
+                "mcp_servers": {
+                    "filesystem": {"command": "npx", "args": ["a"]},
+                    "jira": {"url": "https://jira.example.com/sse"}
+                },
+                "tool_presets": {"git": {"enabled": true}},
+                "tool_permissions": {"allow": ["git"], "deny": ["rm"]}
+            }"#,
+        )
+        .unwrap();
+        let personal: AgentPreferences = serde_json::from_str(
+            r#"{
+                "mcp_servers": {
+                    "filesystem": {"command": "npx", "args": ["b"]},
+                    "memory": {"command": "npx"}
+                },
+                "tool_presets": {"git": {"enabled": false}},
+                "tool_permissions": {"allow": ["git", "cargo"], "deny": ["rm"]}
+            }"#,
+        )
+        .unwrap();
+
+        let diff = crate::mcp::diff_preferences(&work, &personal);
+        assert!(diff.contains("+ memory"));
+        assert!(diff.contains("- jira"));
+        assert!(diff.contains("~ filesystem (changed: args)"));
+        assert!(diff.contains("~ git (changed: enabled)"));
+        assert!(diff.contains("+ cargo"));
+        assert!(!diff.contains("Denied tools"));
+
+        assert!(crate::mcp::diff_preferences(&work, &work).starts_with("No differences"));
+    }
+
+    #[test]
+    fn test_preferences_migration() {
+        let dir = tempdir().unwrap();
+        let prefs_path = dir.path().join("preferences.toml");
+        fs::write(
+            &prefs_path,
+            r#"
+[tool_presets.web_access]
+enabled = true
+"#,
+        )
+        .unwrap();
+
+        let mut manager = crate::preferences::PreferenceManager::new(dir.path());
+        manager.load_global().unwrap();
+        assert_eq!(
+            manager.global_prefs.version,
+            crate::preferences::PREFERENCES_VERSION
+        );
+        assert!(manager.global_prefs.tool_presets["web_access"].enabled);
+
+        // Upgraded file is written back once
+        let saved = fs::read_to_string(&prefs_path).unwrap();
+        assert!(saved.contains(&format!(
+            "version = {}",
+            crate::preferences::PREFERENCES_VERSION
+        )));
+        assert!(
+            crate::preferences::migrate_preferences(&mut toml::from_str(&saved).unwrap())
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_migration_clears_old_general_defaults() {
+        use crate::preferences::ConfigGenerator;
+
+        let dir = tempdir().unwrap();
+        fs::write(
+            dir.path().join("preferences.toml"),
+            r#"
+version = 1
+
+[general]
+auto_accept_tools = true
+enable_logging = true
+sandboxed_mode = false
+"#,
+        )
+        .unwrap();
+
+        let mut manager = crate::preferences::PreferenceManager::new(dir.path());
+        manager.load_global().unwrap();
+        let general = &manager.global_prefs.general;
+        assert_eq!(general.auto_accept_tools, None);
+        assert_eq!(general.enable_logging, None);
+        // Only the old default is cleared; an explicit off stays
+        assert_eq!(general.sandboxed_mode, Some(false));
+
+        // Fresh defaults leave the agents' own settings alone as well
+        let defaults = crate::preferences::PreferenceManager::new(dir.path());
+        let generator = crate::preferences::OpenCodeConfigGenerator {
+            config_dir: dir.path().join("opencode"),
+        };
+        let files = generator.generate(&defaults.global_prefs, None).unwrap();
+        let config: serde_json::Value = serde_json::from_str(&files[0].1).unwrap();
+        assert!(config.get("permission").is_none());
+    }
+
+    #[test]
+    fn test_unknown_mcp_server_config_preserved() {
+        use crate::preferences::{ConfigGenerator, McpServerConfig, PreferenceManager};
+
+        let dir = tempdir().unwrap();
+        fs::write(
+            dir.path().join("preferences.toml"),
+            r#"
+version = 1
+
+[mcp_servers.good]
+command = "npx"
+
+[mcp_servers.typo]
+comand = "uvx"
+args = ["mcp-server-git"]
+
+[mcp_servers.misspelled]
+command = "uvx"
+comand_args = ["mcp-server-git"]
+"#,
+        )
+        .unwrap();
+
+        let mut manager = PreferenceManager::new(dir.path());
+        manager.load_global().unwrap();
+        let servers = &manager.global_prefs.mcp_servers;
+        assert!(matches!(servers["good"], McpServerConfig::Stdio { .. }));
+        assert!(servers["typo"].is_unknown());
+        // A real variant with a misspelled key is rejected rather than losing the key
+        assert!(servers["misspelled"].is_unknown());
+
+        manager.save_global().unwrap();
+        let saved = fs::read_to_string(dir.path().join("preferences.toml")).unwrap();
+        assert!(saved.contains("comand = \"uvx\""));
+        let saved: toml::Table = toml::from_str(&saved).unwrap();
+        let original: toml::Table =
+            toml::from_str("command = \"uvx\"\ncomand_args = [\"mcp-server-git\"]").unwrap();
+        assert_eq!(
+            saved["mcp_servers"]["misspelled"],
+            toml::Value::Table(original)
+        );
+
+        let generator = crate::preferences::OpenCodeConfigGenerator {
+            config_dir: dir.path().join("opencode"),
+        };
+        let files = generator.generate(&manager.global_prefs, None).unwrap();
+        let config: serde_json::Value = serde_json::from_str(&files[0].1).unwrap();
+        assert!(config["mcp"].get("good").is_some());
+        assert!(config["mcp"].get("typo").is_none());
+        assert!(config["mcp"].get("misspelled").is_none());
+    }
+
+    #[test]
+    fn test_custom_mcp_headers() {
+        use crate::preferences::{ConfigGenerator, McpAuth, McpServerConfig};
+
+        let dir = tempdir().unwrap();
+        let mut prefs = crate::preferences::AgentPreferences::default();
+        let headers = crate::app::parse_key_values(
+            "X-Api-Key=abc, anthropic-beta = mcp,Authorization=Basic xyz",
+        );
+        prefs.mcp_servers.insert(
+            "remote".to_string(),
+            McpServerConfig::Http {
+                http_url: "https://example.com/mcp".to_string(),
+                auth: McpAuth::Bearer {
+                    token: "secret".to_string(),
+                },
+                headers: headers.clone(),
+                headers_override_auth: false,
+                disabled_tools: Vec::new(),
+                auto_allow: false,
+                description: None,
+                tags: Vec::new(),
+            },
+        );
+
+        let generator = crate::preferences::GeminiConfigGenerator {
+            config_dir: dir.path().to_path_buf(),
+        };
+        let generate = |prefs: &crate::preferences::AgentPreferences| {
+            let files = generator.generate(prefs, None).unwrap();
+            let config: serde_json::Value = serde_json::from_str(&files[0].1).unwrap();
+            config["mcpServers"]["remote"]["headers"].clone()
+        };
+
+        let generated = generate(&prefs);
+        assert_eq!(generated["X-Api-Key"], "abc");
+        assert_eq!(generated["anthropic-beta"], "mcp");
+        assert_eq!(generated["Authorization"], "Bearer secret");
+
+        if let Some(McpServerConfig::Http {
+            headers_override_auth,
+            ..
+        }) = prefs.mcp_servers.get_mut("remote")
+        {
+            *headers_override_auth = true;
+        }
+        assert_eq!(generate(&prefs)["Authorization"], "Basic xyz");
+    }
+
+    #[test]
+    fn test_backup_now() {
+        let dir = tempdir().unwrap();
+        let paths = test_paths(dir.path());
+        fs::create_dir_all(&paths.backup_dir).unwrap();
+
+        assert!(paths.backup_now().unwrap().is_empty());
+
+        fs::write(&paths.project_agents, "# Project rules").unwrap();
+        let backups = paths.backup_now().unwrap();
+        assert_eq!(backups.len(), 1);
+        assert!(
+            backups[0]
+                .file_name()
+                .unwrap()
+                .to_str()
+                .unwrap()
+                .starts_with("proj_AGENTS.md.")
+        );
+        assert_eq!(fs::read_to_string(&backups[0]).unwrap(), "# Project rules");
+
+        assert_eq!(paths.list_backups_for(&paths.project_agents), backups);
+        assert!(
+            paths
+                .list_backups_for(&paths.global_rules_primary)
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_wrap_aware_scrolling() {
+        use crate::app::{end_scroll, page_down_scroll, page_up_scroll, wrapped_line_rows};
+
+        let content = "short\n\none two three four five six\nabcdefghijklmnopqrstuvwxyz";
+        let rows = wrapped_line_rows(content, 10);
+        assert_eq!(rows, vec![1, 1, 3, 3]);
+
+        // The last line wraps to 3 rows, so a 4-row pane ends on it, not on the line before
+        assert_eq!(end_scroll(&rows, 4), 3);
+        assert_eq!(end_scroll(&rows, 6), 2);
+        assert_eq!(end_scroll(&rows, 100), 0);
+
+        assert_eq!(page_down_scroll(0, &rows, 4), 2);
+        assert_eq!(page_down_scroll(2, &rows, 4), 3);
+        assert_eq!(page_down_scroll(3, &rows, 4), 3);
+        assert_eq!(page_up_scroll(3, &rows, 4), 1);
+        assert_eq!(page_up_scroll(1, &rows, 4), 0);
+        assert_eq!(page_up_scroll(0, &rows, 4), 0);
+    }
+
+    #[test]
+    fn test_last_synced_recorded() {
+        let dir = tempdir().unwrap();
+        let config_dir = dir.path().join("config");
+        fs::create_dir_all(&config_dir).unwrap();
+        let paths = crate::config::ConfigPaths {
+            agent_configs: vec![crate::config::AgentDefinition {
+                name: "Custom".to_string(),
+                target_path: dir.path().join("CUSTOM.md"),
+                strategy: crate::config::SyncStrategy::Merge,
+                global_file: None,
+                group: None,
+                header: None,
+                footer: None,
+            }],
+            ..test_paths(dir.path())
+        };
+        fs::create_dir_all(&paths.backup_dir).unwrap();
+
+        assert!(paths.get_agents()[0].last_synced.is_none());
+
+        paths.sync().unwrap();
+        let first = paths.get_agents()[0].last_synced.unwrap();
+        assert!(paths.load_sync_times().contains_key("Custom"));
+
+        // Already in sync: the recorded time is left alone
+        paths.sync_agent(0).unwrap();
+        assert_eq!(paths.get_agents()[0].last_synced, Some(first));
+
+        let other = crate::config::ConfigPaths {
+            project_id: "other".to_string(),
+            agent_configs: Vec::new(),
+            preferences: crate::preferences::PreferenceManager::new(&config_dir),
+            ..paths
+        };
+        assert!(other.load_sync_times().is_empty());
+    }
+
+    #[test]
+    fn test_sync_continues_past_failed_agent() {
+        let dir = tempdir().unwrap();
+        let config_dir = dir.path().join("config");
+        fs::create_dir_all(&config_dir).unwrap();
+        // A regular file where a directory is expected makes the first target unwritable
+        fs::write(dir.path().join("blocker"), "").unwrap();
+        let agent = |name: &str, target: PathBuf| crate::config::AgentDefinition {
+            name: name.to_string(),
+            target_path: target,
+            strategy: crate::config::SyncStrategy::Merge,
+            global_file: None,
+            group: None,
+            header: None,
+            footer: None,
+        };
+        let paths = crate::config::ConfigPaths {
+            agent_configs: vec![
+                agent("Broken", dir.path().join("blocker/BROKEN.md")),
+                agent("Custom", dir.path().join("CUSTOM.md")),
+            ],
+            ..test_paths(dir.path())
+        };
+        fs::create_dir_all(&paths.backup_dir).unwrap();
+
+        let summary = paths.sync().unwrap();
+        assert!(summary.starts_with("Synced 1 of 2 agent(s); failed: Broken ("));
+        assert!(dir.path().join("CUSTOM.md").exists());
+
+        let agents = paths.get_agents();
+        assert!(agents[0].last_synced.is_none());
+        assert!(agents[1].last_synced.is_some());
+
+        assert!(paths.sync_agent(0).is_err());
+    }
+
+    #[test]
+    fn test_generators_keep_foreign_entries() {
+        let dir = tempdir().unwrap();
+        let claude_dir = dir.path().join(".claude");
+        let gemini_dir = dir.path().join(".gemini");
+        let opencode_dir = dir.path().join("opencode");
+        for d in [&claude_dir, &gemini_dir, &opencode_dir] {
+            fs::create_dir_all(d).unwrap();
+        }
+
+        let manual_server = json!({ "command": "manual", "args": [] });
+        fs::write(
+            claude_dir.join("settings.json"),
+            json!({
+                "permissions": {
+                    "allow": ["Bash(old:*)"],
+                    "defaultMode": "acceptEdits"
+                }
+            })
+            .to_string(),
+        )
+        .unwrap();
+        fs::write(
+            gemini_dir.join("settings.json"),
+            json!({ "mcpServers": { "manual": manual_server } }).to_string(),
+        )
+        .unwrap();
+        fs::write(
+            opencode_dir.join("opencode.json"),
+            json!({ "mcp": { "manual": manual_server } }).to_string(),
+        )
+        .unwrap();
+
+        let mut prefs = crate::preferences::AgentPreferences::default();
+        prefs.tool_permissions.allow.push("Bash(git:*)".to_string());
+        prefs.mcp_servers.insert(
+            "managed".to_string(),
+            crate::preferences::McpServerConfig::Stdio {
+                command: "managed".to_string(),
+                args: Vec::new(),
+                env: std::collections::HashMap::new(),
+                disabled_tools: Vec::new(),
+                auto_allow: false,
+                description: None,
+                tags: Vec::new(),
+            },
+        );
+
+        let generated = |generator: &dyn crate::preferences::ConfigGenerator, file: &str| {
+            let results = generator.generate(&prefs, None).unwrap();
+            let (_, content) = results.iter().find(|(p, _)| p.ends_with(file)).unwrap();
+            serde_json::from_str::<serde_json::Value>(content).unwrap()
+        };
+
+        let claude = generated(
+            &crate::preferences::ClaudeConfigGenerator {
+                config_dir: claude_dir.clone(),
+                user_config_path: dir.path().join(".claude.json"),
+            },
+            "settings.json",
+        );
+        assert_eq!(claude["permissions"]["defaultMode"], "acceptEdits");
+        assert_eq!(claude["permissions"]["allow"], json!(["Bash(git:*)"]));
+
+        let gemini = generated(
+            &crate::preferences::GeminiConfigGenerator {
+                config_dir: gemini_dir.clone(),
+            },
+            "settings.json",
+        );
+        assert_eq!(gemini["mcpServers"]["manual"], manual_server);
+        assert_eq!(gemini["mcpServers"]["managed"]["command"], "managed");
+
+        let opencode = generated(
+            &crate::preferences::OpenCodeConfigGenerator {
+                config_dir: opencode_dir.clone(),
+            },
+            "opencode.json",
+        );
+        assert_eq!(opencode["mcp"]["manual"], manual_server);
+        assert!(opencode["mcp"]["managed"].is_object());
+    }
+
+    #[test]
+    fn test_removed_servers_pruned_from_agent_config() {
+        let dir = tempdir().unwrap();
+        let config_dir = dir.path().join("config");
+        let gemini_dir = dir.path().join(".gemini");
+        fs::create_dir_all(&gemini_dir).unwrap();
+        let settings_path = gemini_dir.join("settings.json");
+        fs::write(
+            &settings_path,
+            json!({ "mcpServers": { "manual": { "command": "manual" } } }).to_string(),
+        )
+        .unwrap();
+
+        let paths = test_paths(dir.path());
+        let generator = crate::preferences::GeminiConfigGenerator {
+            config_dir: gemini_dir.clone(),
+        };
+        let credentials = crate::credentials::CredentialManager::new(&config_dir);
+        let server = |command: &str| crate::preferences::McpServerConfig::Stdio {
+            command: command.to_string(),
+            args: Vec::new(),
+            env: std::collections::HashMap::new(),
+            disabled_tools: Vec::new(),
+            auto_allow: false,
+            description: None,
+            tags: Vec::new(),
+        };
+
+        let mut prefs = crate::preferences::AgentPreferences::default();
+        prefs.mcp_servers.insert("keep".to_string(), server("keep"));
+        prefs.mcp_servers.insert("drop".to_string(), server("drop"));
+
+        let mut managed = Default::default();
+        let files = paths
+            .generate_reconciled(&generator, &prefs, &credentials, &mut managed)
+            .unwrap();
+        fs::write(&settings_path, &files[0].1).unwrap();
+
+        prefs.mcp_servers.remove("drop");
+        let files = paths
+            .generate_reconciled(&generator, &prefs, &credentials, &mut managed)
+            .unwrap();
+        let settings: serde_json::Value = serde_json::from_str(&files[0].1).unwrap();
+        let servers = settings["mcpServers"].as_object().unwrap();
+
+        assert!(servers.contains_key("keep"));
+        assert!(servers.contains_key("manual"));
+        assert!(!servers.contains_key("drop"));
+    }
+
+    #[test]
+    fn test_continue_generator() {
+        let dir = tempdir().unwrap();
+        fs::write(
+            dir.path().join("config.json"),
+            json!({
+                "models": [{ "title": "local" }],
+                "experimental": { "useTools": true }
+            })
+            .to_string(),
+        )
+        .unwrap();
+
+        let mut prefs = crate::preferences::AgentPreferences::default();
+        prefs.mcp_servers.insert(
+            "local".to_string(),
+            crate::preferences::McpServerConfig::Stdio {
+                command: "uvx".to_string(),
+                args: vec!["server".to_string()],
+                env: std::collections::HashMap::new(),
+                disabled_tools: Vec::new(),
+                auto_allow: false,
+                description: None,
+                tags: Vec::new(),
+            },
+        );
+        prefs.mcp_servers.insert(
+            "remote".to_string(),
+            crate::preferences::McpServerConfig::Http {
+                http_url: "https://example.com/mcp".to_string(),
+                auth: crate::preferences::McpAuth::None,
+                headers: std::collections::HashMap::from([(
+                    "X-Team".to_string(),
+                    "moo".to_string(),
+                )]),
+                headers_override_auth: false,
+                disabled_tools: Vec::new(),
+                auto_allow: false,
+                description: None,
+                tags: Vec::new(),
+            },
+        );
+
+        let generator = crate::preferences::ContinueConfigGenerator {
+            config_dir: dir.path().to_path_buf(),
+        };
+        let results = generator.generate(&prefs, None).unwrap();
+        let config: serde_json::Value = serde_json::from_str(&results[0].1).unwrap();
+
+        assert_eq!(config["models"][0]["title"], "local");
+        assert_eq!(config["experimental"]["useTools"], true);
+        assert_eq!(
+            config["experimental"]["modelContextProtocolServers"],
+            json!([
+                {
+                    "name": "local",
+                    "transport": { "type": "stdio", "command": "uvx", "args": ["server"] }
+                },
+                {
+                    "name": "remote",
+                    "transport": {
+                        "type": "streamable-http",
+                        "url": "https://example.com/mcp",
+                        "requestOptions": { "headers": { "X-Team": "moo" } }
+                    }
+                }
+            ])
+        );
+    }
+
+    #[tokio::test]
+    async fn test_probe_remote() {
+        use axum::response::Redirect;
+        use axum::routing::get;
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let app = crate::mcp_http::router()
+            .route("/moved", get(|| async { Redirect::temporary("/health") }))
+            .route("/loop", get(|| async { Redirect::temporary("/loop") }));
+        tokio::spawn(async move {
+            axum::serve(listener, app).await.unwrap();
+        });
+        let url = |path: &str| format!("http://{}{}", addr, path);
+
+        let probe = crate::mcp::probe_remote(&url("/health"), 5).await.unwrap();
+        assert_eq!((probe.method, probe.status.as_u16()), ("HEAD", 200));
+
+        // The JSON-RPC endpoint only accepts POST, so HEAD is rejected and GET is tried
+        let probe = crate::mcp::probe_remote(&url("/mcp"), 5).await.unwrap();
+        assert_eq!((probe.method, probe.status.as_u16()), ("GET", 405));
+
+        let probe = crate::mcp::probe_remote(&url("/moved"), 5).await.unwrap();
+        assert_eq!(probe.status.as_u16(), 200);
+        assert_eq!(probe.final_url, url("/health"));
+
+        let err = crate::mcp::probe_remote(&url("/loop"), 5)
+            .await
+            .err()
+            .unwrap();
+        assert!(err.starts_with("Redirect loop"));
+    }
+
+    #[test]
+    fn test_mooagent_tool_toggle_targets_disabled_tools() {
+        let names = crate::mcp::tool_names();
+        assert!(names.contains(&"set_global_rules".to_string()));
+        assert!(names.contains(&"edit_section_global_rules".to_string()));
+
+        let mut config = crate::preferences::McpServerConfig::Stdio {
+            command: "mooagent".to_string(),
+            args: vec!["--mcp".to_string()],
+            env: std::collections::HashMap::new(),
+            disabled_tools: Vec::new(),
+            auto_allow: false,
+            description: None,
+            tags: Vec::new(),
+        };
+        config
+            .disabled_tools_mut()
+            .unwrap()
+            .push("set_global_rules".to_string());
+        assert_eq!(config.disabled_tools(), ["set_global_rules"]);
+
+        let mut unknown = crate::preferences::McpServerConfig::Unknown(json!({}));
+        assert!(unknown.disabled_tools_mut().is_none());
+    }
+
+    #[test]
+    fn test_destructive_tools() {
+        use crate::mcp::is_destructive;
+
+        assert!(is_destructive("set_global_rules", &json!({"content": ""})));
+        assert!(is_destructive("set_project_rules", &json!({"content": ""})));
+        assert!(is_destructive(
+            "import_config",
+            &json!({"config": "{}", "replace": true})
+        ));
+        assert!(!is_destructive("import_config", &json!({"config": "{}"})));
+        assert!(!is_destructive(
+            "edit_section_global_rules",
+            &json!({"heading": "x"})
+        ));
+    }
+
+    #[test]
+    fn test_redacted_export_round_trip() {
+        use crate::preferences::{McpAuth, McpServerConfig, REDACTED};
+
+        let original = McpServerConfig::Http {
+            http_url: "https://example.com/mcp".to_string(),
+            auth: McpAuth::Bearer {
+                token: "secret-token".to_string(),
+            },
+            headers: std::collections::HashMap::from([(
+                "X-Api-Key".to_string(),
+                "key".to_string(),
+            )]),
+            headers_override_auth: false,
+            disabled_tools: Vec::new(),
+            auto_allow: false,
+            description: None,
+            tags: Vec::new(),
+        };
+
+        let redacted = original.redacted();
+        let exported = serde_json::to_string(&redacted).unwrap();
+        assert!(!exported.contains("secret-token"));
+        assert!(!exported.contains("\"key\""));
+        assert!(exported.contains(REDACTED));
+
+        let mut imported: McpServerConfig = serde_json::from_str(&exported).unwrap();
+        assert!(!imported.restore_redacted(Some(&original)));
+        assert_eq!(imported, original);
+
+        let mut fresh: McpServerConfig = serde_json::from_str(&exported).unwrap();
+        assert!(fresh.restore_redacted(None));
+        let McpServerConfig::Http { auth, headers, .. } = fresh else {
+            panic!("expected an http server");
+        };
+        assert!(auth.is_none());
+        assert!(headers.is_empty());
+
+        let stdio = McpServerConfig::Stdio {
+            command: "gh-mcp".to_string(),
+            args: Vec::new(),
+            env: std::collections::HashMap::from([
+                ("GITHUB_TOKEN".to_string(), "ghp".to_string()),
+                ("LOG_LEVEL".to_string(), "debug".to_string()),
+            ]),
+            disabled_tools: Vec::new(),
+            auto_allow: false,
+            description: None,
+            tags: Vec::new(),
+        };
+        let McpServerConfig::Stdio { env, .. } = stdio.redacted() else {
+            panic!("expected a stdio server");
+        };
+        assert_eq!(env["GITHUB_TOKEN"], REDACTED);
+        assert_eq!(env["LOG_LEVEL"], "debug");
+    }
+
+    #[test]
+    fn test_clear_markdown_section() {
+        let content =
+            "# Rules\n\n## Style\nUse tabs.\n### Nested\nDetail.\n\n## Testing\nRun tests.";
+
+        let cleared =
+            crate::mcp::edit_markdown_section(content, "## Style", "ignored", "clear", true)
+                .unwrap();
+        assert_eq!(cleared, "# Rules\n\n## Style\n\n## Testing\nRun tests.");
+
+        let cleared_last =
+            crate::mcp::edit_markdown_section(content, "## Testing", "", "clear", true).unwrap();
+        assert!(cleared_last.ends_with("## Testing\n"));
+        assert!(!cleared_last.contains("Run tests."));
+        assert!(cleared_last.contains("Use tabs."));
+    }
+
+    #[test]
+    fn test_insert_markdown_section() {
+        use crate::mcp::{SectionPosition, insert_markdown_section};
+
+        let content =
+            "# Rules\n\n## Style\nUse tabs.\n### Nested\nDetail.\n\n## Testing\nRun tests.";
+
+        let at_end =
+            insert_markdown_section(content, "## Docs", "Write docs.", SectionPosition::End)
+                .unwrap();
+        assert!(at_end.ends_with("Run tests.\n\n## Docs\nWrite docs."));
+
+        let after = insert_markdown_section(
+            content,
+            "## Docs",
+            "Write docs.",
+            SectionPosition::After("## Style"),
+        )
+        .unwrap();
+        assert!(after.contains("Detail.\n\n## Docs\nWrite docs.\n\n## Testing"));
+
+        let before =
+            insert_markdown_section(content, "## Docs", "", SectionPosition::Before("## Style"))
+                .unwrap();
+        assert!(before.starts_with("# Rules\n\n## Docs\n\n## Style\n"));
+
+        assert!(insert_markdown_section(content, "## Style", "", SectionPosition::End).is_err());
+        assert!(
+            insert_markdown_section(content, "## Docs", "", SectionPosition::After("## Nope"))
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_section_heading_matching_is_anchored() {
+        use crate::mcp::edit_markdown_section;
+
+        let content =
+            "## Build System\nUse cargo.\n\n## Build\nRun just build.\n\n## Builds\nCI only.";
+
+        let updated =
+            edit_markdown_section(content, "## Build", "Run make.", "replace", true).unwrap();
+        assert!(updated.contains("## Build System\nUse cargo."));
+        assert!(updated.contains("## Build\nRun make."));
+        assert!(updated.contains("## Builds\nCI only."));
+        assert!(!updated.contains("Run just build."));
+
+        // The heading keeps its own spelling when matched case-insensitively
+        let updated =
+            edit_markdown_section(content, "## build", "Run make.", "replace", true).unwrap();
+        assert!(updated.contains("\n## Build\nRun make.\n"));
+        let updated =
+            edit_markdown_section(content, "##  BUILD", "First.", "prepend", true).unwrap();
+        assert!(updated.contains("\n## Build\nFirst.\n\nRun just build."));
+
+        let updated = edit_markdown_section(content, "## build", "", "delete", true).unwrap();
+        assert!(updated.contains("## Build System"));
+        assert!(updated.contains("## Builds"));
+        assert!(!updated.contains("Run just build."));
+
+        // A deeper heading with the same text is a different section
+        assert!(edit_markdown_section(content, "### Build", "", "delete", true).is_err());
+        assert!(edit_markdown_section(content, "## Bui", "", "delete", true).is_err());
+    }
+
+    #[test]
+    fn test_edit_section_recursive_option() {
+        use crate::mcp::edit_markdown_section;
+
+        let content = "# Rules\nIntro.\n\n## Sub\nSub detail.\n\n# Other\nKeep.";
+
+        let whole =
+            edit_markdown_section(content, "# Rules", "New intro.", "replace", true).unwrap();
+        assert_eq!(whole, "# Rules\nNew intro.\n# Other\nKeep.");
+
+        let direct =
+            edit_markdown_section(content, "# Rules", "New intro.", "replace", false).unwrap();
+        assert_eq!(
+            direct,
+            "# Rules\nNew intro.\n## Sub\nSub detail.\n\n# Other\nKeep."
+        );
+
+        let cleared = edit_markdown_section(content, "# Rules", "", "clear", false).unwrap();
+        assert!(cleared.starts_with("# Rules\n\n## Sub\nSub detail."));
+        assert!(!cleared.contains("Intro."));
+    }
+
+    #[test]
+    fn test_read_markdown_section() {
+        use crate::mcp::{edit_markdown_section, read_markdown_section};
+
+        let content = "# Rules\nIntro.\n\n## Style\nBe terse.\n\n## Testing\nRun tests.";
+        let updated =
+            edit_markdown_section(content, "## Style", "Use tabs.", "replace", true).unwrap();
+
+        let style = read_markdown_section(&updated, "## Style", true).unwrap();
+        assert_eq!(style.as_deref(), Some("## Style\nUse tabs."));
+
+        let intro = read_markdown_section(content, "# Rules", false).unwrap();
+        assert_eq!(intro.as_deref(), Some("# Rules\nIntro."));
+
+        let missing = read_markdown_section(content, "## Docs", true).unwrap();
+        assert!(missing.is_none());
+    }
+
+    #[test]
+    fn test_rules_stats() {
+        use crate::mcp::RulesStats;
+
+        let stats = RulesStats::of("# Rules\nBe kind.\n\n## Style\nTabs.\n");
+        assert_eq!(stats.lines, 5);
+        assert_eq!(stats.chars, 33);
+        assert_eq!(stats.estimated_tokens, 9);
+        assert_eq!(stats.sections, 2);
+
+        assert_eq!(RulesStats::of("abcde").estimated_tokens, 2);
+        assert_eq!(RulesStats::of("").sections, 0);
+    }
+
+    #[test]
+    fn test_oversized_rules_warning() {
+        let dir = tempdir().unwrap();
+        let config_dir = dir.path().join("config");
+        fs::create_dir_all(&config_dir).unwrap();
+        let mut paths = test_paths(dir.path());
+        fs::write(&paths.global_rules_primary, "x".repeat(2048)).unwrap();
+        fs::write(&paths.project_agents, "small").unwrap();
+
+        assert!(paths.oversized_rules().is_empty());
+
+        paths.preferences.global_prefs.general.max_rules_bytes = Some(1024);
+        let warnings = paths.oversized_rules();
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].starts_with("GLOBAL_RULES.md is 2.0 KiB"));
+
+        paths.preferences.global_prefs.general.max_rules_bytes = Some(0);
+        assert!(paths.oversized_rules().is_empty());
+    }
+
+    #[test]
+    fn test_text_input_cursor_editing() {
+        use crate::app::{
+            CursorMove, delete_at_cursor, delete_before_cursor, insert_at_cursor, move_cursor,
+        };
+
+        let mut text = "https://exmple.com".to_string();
+        let mut cursor = text.chars().count();
+
+        move_cursor(&text, &mut cursor, CursorMove::Home);
+        for _ in 0..10 {
+            move_cursor(&text, &mut cursor, CursorMove::Right);
+        }
+        insert_at_cursor(&mut text, &mut cursor, "a");
+        assert_eq!(text, "https://example.com");
+        assert_eq!(cursor, 11);
+
+        delete_before_cursor(&mut text, &mut cursor);
+        delete_at_cursor(&mut text, &mut cursor);
+        assert_eq!(text, "https://exple.com");
+        assert_eq!(cursor, 10);
+
+        // Multi-byte characters are addressed by character, not byte
+        let mut text = "héllo".to_string();
+        let mut cursor = 2;
+        insert_at_cursor(&mut text, &mut cursor, "ü");
+        assert_eq!(text, "héüllo");
+        move_cursor(&text, &mut cursor, CursorMove::End);
+        move_cursor(&text, &mut cursor, CursorMove::Right);
+        assert_eq!(cursor, 6);
+
+        // A stale cursor past the end is clamped rather than panicking
+        let mut cursor = 99;
+        delete_before_cursor(&mut text, &mut cursor);
+        assert_eq!(text, "héüll");
+        move_cursor(&text, &mut cursor, CursorMove::Home);
+        move_cursor(&text, &mut cursor, CursorMove::Left);
+        assert_eq!(cursor, 0);
+    }
+
+    #[test]
+    fn test_mcp_editor_switching_server_type_clears_fields() {
+        use crate::app::{McpAuthType, McpEditorState};
+        use crate::preferences::McpServerConfig;
+
+        let mut state = McpEditorState {
+            editing_name: "api".to_string(),
+            editing_command: "https://api.example.com/sse".to_string(),
+            editing_headers: "X-Team=moo".to_string(),
+            editing_auth_type: McpAuthType::OAuth,
+            editing_oauth_client_id: "client".to_string(),
+            editing_oauth_client_secret: "secret".to_string(),
+            ..Default::default()
+        };
+
+        state.editing_command = "/usr/local/bin/api-server".to_string();
+        state.editing_args = "--stdio".to_string();
+        state.clear_fields_for_server_type();
+
+        assert_eq!(state.editing_auth_type, McpAuthType::None);
+        assert!(state.editing_oauth_client_id.is_empty());
+        assert!(state.editing_oauth_client_secret.is_empty());
+        assert!(state.editing_headers.is_empty());
+        match state.to_config(false) {
+            McpServerConfig::Stdio { command, args, .. } => {
+                assert_eq!(command, "/usr/local/bin/api-server");
+                assert_eq!(args, vec!["--stdio"]);
+            }
+            other => panic!("expected stdio server, got {:?}", other),
+        }
+
+        // And back again: args and env do not leak into the remote entry
+        state.editing_env = "KEY=val".to_string();
+        state.editing_command = "https://api.example.com/sse".to_string();
+        state.clear_fields_for_server_type();
+        assert!(state.editing_args.is_empty());
+        assert!(state.editing_env.is_empty());
+        assert!(matches!(
+            state.to_config(false),
+            McpServerConfig::Sse { auth, .. } if auth.is_none()
+        ));
+    }
+
+    #[test]
+    fn test_orphaned_credential_keys() {
+        use crate::preferences::{McpAuth, McpServerConfig, orphaned_credential_keys};
+        use std::collections::HashMap;
+
+        let remote = |url: &str, auth: McpAuth| McpServerConfig::Sse {
+            url: url.to_string(),
+            auth,
+            headers: HashMap::new(),
+            headers_override_auth: false,
+            disabled_tools: Vec::new(),
+            auto_allow: false,
+            description: None,
+            tags: Vec::new(),
+        };
+        let oauth = McpAuth::OAuth {
+            client_id: "client".to_string(),
+            client_secret: None,
+            auth_server_url: None,
+            scopes: Vec::new(),
+        };
+
+        let removed = remote("https://a.example.com", oauth.clone());
+        let mut remaining = HashMap::new();
+        assert_eq!(
+            orphaned_credential_keys(&removed, &remaining),
+            vec!["https://a.example.com"]
+        );
+
+        // A token still used by another entry for the same URL is kept
+        remaining.insert("a-copy".to_string(), remote("https://a.example.com", oauth));
+        assert!(orphaned_credential_keys(&removed, &remaining).is_empty());
+
+        let bearer = remote(
+            "https://b.example.com",
+            McpAuth::BearerRef {
+                key: "b-token".to_string(),
+            },
+        );
+        assert_eq!(
+            orphaned_credential_keys(&bearer, &remaining),
+            vec!["https://b.example.com", "b-token"]
+        );
+
+        let stdio = McpServerConfig::Stdio {
+            command: "server".to_string(),
+            args: Vec::new(),
+            env: std::collections::HashMap::new(),
+            disabled_tools: Vec::new(),
+            auto_allow: false,
+            description: None,
+            tags: Vec::new(),
+        };
+        assert!(orphaned_credential_keys(&stdio, &remaining).is_empty());
+    }
+
+    #[test]
+    fn test_keybindings_markdown() {
+        let markdown = crate::ui::keybindings_markdown();
+
+        assert!(markdown.starts_with("# mooagent keybindings\n"));
+        for (section, bindings) in crate::ui::KEYBINDINGS {
+            assert!(markdown.contains(&format!("## {}\n", section)));
+            for (keys, _) in *bindings {
+                assert!(markdown.contains(&format!("| `{}` |", keys)));
+            }
+        }
+        assert!(markdown.contains("| `Ctrl+g` | Edit global rules (syncs to all agents) |"));
+    }
+
+    #[test]
+    fn test_saves_recreate_deleted_config_dir() {
+        let dir = tempdir().unwrap();
+        let config_dir = dir.path().join("config");
+        fs::create_dir_all(&config_dir).unwrap();
+        let paths = crate::config::ConfigPaths {
+            backup_dir: config_dir.join("backups"),
+            ..test_paths(dir.path())
+        };
+        fs::write(&paths.project_agents, "# Rules").unwrap();
+
+        // A dotfiles reset removes the directory while the TUI is running
+        fs::remove_dir_all(&config_dir).unwrap();
+
+        paths.preferences.save_global().unwrap();
+        assert!(paths.preferences.global_path.exists());
+
+        fs::remove_dir_all(&config_dir).unwrap();
+        crate::config::write_atomic(&paths.global_rules_primary, "# Global").unwrap();
+        assert_eq!(
+            fs::read_to_string(&paths.global_rules_primary).unwrap(),
+            "# Global"
+        );
+
+        fs::remove_dir_all(&config_dir).unwrap();
+        let backup = paths.backup_if_needed(&paths.project_agents).unwrap();
+        assert!(backup.is_some_and(|b| b.exists()));
+    }
+
+    #[test]
+    fn test_doctor_checks() {
+        use crate::doctor::CheckStatus;
+        use crate::preferences::McpServerConfig;
+
+        let dir = tempdir().unwrap();
+        let mut paths = crate::config::ConfigPaths {
+            agent_configs: vec![crate::config::AgentDefinition {
+                name: "Custom".to_string(),
+                target_path: dir.path().join("missing-dir/CUSTOM.md"),
+                strategy: crate::config::SyncStrategy::Merge,
+                global_file: None,
+                group: None,
+                header: None,
+                footer: None,
+            }],
+            ..test_paths(dir.path())
+        };
+        let stdio = |command: &str| McpServerConfig::Stdio {
+            command: command.to_string(),
+            args: Vec::new(),
+            env: std::collections::HashMap::new(),
+            disabled_tools: Vec::new(),
+            auto_allow: false,
+            description: None,
+            tags: Vec::new(),
+        };
+        let servers = &mut paths.preferences.global_prefs.mcp_servers;
+        servers.insert("mooagent".to_string(), stdio("/nonexistent/bin/mooagent"));
+        servers.insert("broken".to_string(), stdio("definitely-not-a-real-command"));
+
+        let checks = crate::doctor::run(&paths, 1);
+        let find = |needle: &str| {
+            checks
+                .iter()
+                .find(|c| c.label.contains(needle))
+                .unwrap_or_else(|| panic!("no check mentioning {:?}", needle))
+        };
+
+        assert_eq!(find("Config directory").status, CheckStatus::Pass);
+        assert_eq!(find("missing-dir").status, CheckStatus::Warn);
+        assert_eq!(find("missing binary").status, CheckStatus::Fail);
+        let broken = find("'broken'");
+        assert_eq!(broken.status, CheckStatus::Fail);
+        assert!(broken.hint.is_some());
+        assert!(crate::doctor::has_failures(&checks));
+
+        let report = crate::doctor::format_report(&checks);
+        assert!(report.contains("❌ Local server 'broken'"));
+        assert!(report.contains("   → "));
+    }
+
+    #[test]
+    fn test_general_settings_written_to_agent_configs() {
+        use crate::preferences::GeneralSetting;
+
+        let dir = tempdir().unwrap();
+        let mut prefs = crate::preferences::AgentPreferences::default();
+        prefs.general.auto_accept_tools = Some(false);
+        prefs.general.enable_logging = Some(true);
+        prefs.general.sandboxed_mode = Some(true);
+
+        let generated = |generator: &dyn ConfigGenerator, file: &str| {
+            let results = generator.generate(&prefs, None).unwrap();
+            let (_, content) = results.iter().find(|(p, _)| p.ends_with(file)).unwrap();
+            serde_json::from_str::<serde_json::Value>(content).unwrap()
+        };
+
+        let claude_generator = crate::preferences::ClaudeConfigGenerator {
+            config_dir: dir.path().join(".claude"),
+            user_config_path: dir.path().join(".claude.json"),
+        };
+        let claude = generated(&claude_generator, "settings.json");
+        assert_eq!(claude["permissions"]["defaultMode"], "default");
+        assert_eq!(claude["sandbox"]["enabled"], true);
+
+        let gemini_generator = crate::preferences::GeminiConfigGenerator {
+            config_dir: dir.path().join(".gemini"),
+        };
+        let gemini = generated(&gemini_generator, "settings.json");
+        assert_eq!(gemini["tools"]["autoAccept"], false);
+        assert_eq!(gemini["tools"]["sandbox"], true);
+        // Telemetry sends usage data, so Enable Logging never turns it on
+        assert!(gemini.get("telemetry").is_none());
+
+        let opencode_generator = crate::preferences::OpenCodeConfigGenerator {
+            config_dir: dir.path().join("opencode"),
+        };
+        let opencode = generated(&opencode_generator, "opencode.json");
+        assert_eq!(opencode["permission"], json!({ "edit": "ask" }));
+
+        let supported = |setting| {
+            [
+                &claude_generator as &dyn ConfigGenerator,
+                &gemini_generator,
+                &opencode_generator,
+            ]
+            .iter()
+            .filter(|g| g.supported_general_settings().contains(&setting))
+            .count()
+        };
+        assert_eq!(supported(GeneralSetting::AutoAcceptTools), 3);
+        assert_eq!(supported(GeneralSetting::EnableLogging), 0);
+        assert_eq!(supported(GeneralSetting::SandboxedMode), 2);
+    }
+
+    #[test]
+    fn test_agent_general_overrides() {
+        let dir = tempdir().unwrap();
+        let config_dir = dir.path().join("config");
+        fs::create_dir_all(&config_dir).unwrap();
+        fs::write(
+            config_dir.join("preferences.toml"),
+            r#"
+version = 2
+
+[general]
+sandboxed_mode = false
+auto_accept_tools = true
+
+[agent_specific.Gemini.general]
+sandboxed_mode = true
+"#,
+        )
+        .unwrap();
+
+        let mut mgr = crate::preferences::PreferenceManager::new(&config_dir);
+        mgr.load_global().unwrap();
+        let prefs = mgr.get_merged();
+
+        let gemini = prefs.general_for("Gemini");
+        assert_eq!(gemini.sandboxed_mode, Some(true));
+        assert_eq!(gemini.auto_accept_tools, Some(true));
+        assert_eq!(prefs.general_for("Claude").sandboxed_mode, Some(false));
+
+        let generated = |generator: &dyn ConfigGenerator| {
+            let results = generator.generate(&prefs, None).unwrap();
+            let (_, content) = results
+                .iter()
+                .find(|(p, _)| p.ends_with("settings.json"))
+                .unwrap();
+            serde_json::from_str::<serde_json::Value>(content).unwrap()
+        };
+        let claude = generated(&crate::preferences::ClaudeConfigGenerator {
+            config_dir: dir.path().join(".claude"),
+            user_config_path: dir.path().join(".claude.json"),
+        });
+        assert_eq!(claude["sandbox"]["enabled"], false);
+        let gemini = generated(&crate::preferences::GeminiConfigGenerator {
+            config_dir: dir.path().join(".gemini"),
+        });
+        assert_eq!(gemini["tools"]["sandbox"], true);
+
+        // Agents without overrides don't grow an empty `general` table on save
+        mgr.global_prefs
+            .agent_specific
+            .entry("Claude".to_string())
+            .or_default()
+            .plugins
+            .insert("git".to_string(), true);
+        mgr.save_global().unwrap();
+        let saved = fs::read_to_string(config_dir.join("preferences.toml")).unwrap();
+        assert!(saved.contains("[agent_specific.Gemini.general]"));
+        assert!(!saved.contains("[agent_specific.Claude.general]"));
+    }
+
+    #[test]
+    fn test_unknown_tool_warning() {
+        use crate::preferences::{known_tool_names, unknown_tool_warning};
+
+        assert!(known_tool_names().contains("WebFetch"));
+        assert!(known_tool_names().contains("TodoWrite"));
+
+        assert_eq!(unknown_tool_warning("Write"), None);
+        assert_eq!(unknown_tool_warning("Bash"), None);
+        // Commands on PATH are legitimate tools even though mooagent doesn't list them
+        assert_eq!(unknown_tool_warning("sh"), None);
+
+        let typo = unknown_tool_warning("Wrtie").unwrap();
+        assert!(typo.contains("saved anyway"));
+        assert!(typo.contains("did you mean 'Write'?"));
+
+        let custom = unknown_tool_warning("MyInternalDeployTool").unwrap();
+        assert!(!custom.contains("did you mean"));
+    }
+
+    #[test]
+    fn test_tools_grouped_by_preset() {
+        use crate::app::{sort_tool_list, tool_section};
+
+        let prefs = crate::preferences::AgentPreferences::default();
+        let index = crate::preferences::tool_preset_index();
+        assert_eq!(index["grep"], vec!["core_unix_tools", "code_search"]);
+        assert_eq!(index["WebFetch"], vec!["web_access"]);
+        assert_eq!(tool_section("Glob", &index, &prefs).1, "file_operations");
+        assert_eq!(
+            tool_section("terraform", &index, &prefs).1,
+            "Custom Programs"
+        );
+
+        let mut tools: Vec<String> = ["terraform", "WebFetch", "Read", "ls", "curl"]
+            .iter()
+            .map(|t| t.to_string())
+            .collect();
+        sort_tool_list(&mut tools, &prefs);
+        assert_eq!(tools, ["ls", "Read", "curl", "WebFetch", "terraform"]);
+    }
+
+    #[test]
+    fn test_custom_tool_categories() {
+        use crate::app::tool_section;
+        use crate::preferences::ToolCategory;
+
+        assert_eq!(ToolCategory::guess("DeployTool"), ToolCategory::Llm);
+        assert_eq!(ToolCategory::guess("terraform"), ToolCategory::Program);
+
+        let mut prefs: crate::preferences::AgentPreferences = toml::from_str(
+            r#"
+[individual_tools]
+DeployTool = true
+kubectl = true
+Make = true
+
+[tool_categories]
+kubectl = "llm"
+Make = "program"
+"#,
+        )
+        .unwrap();
+        let index = crate::preferences::tool_preset_index();
+
+        assert_eq!(
+            tool_section("DeployTool", &index, &prefs).1,
+            "Custom LLM Tools"
+        );
+        assert_eq!(
+            tool_section("kubectl", &index, &prefs).1,
+            "Custom LLM Tools"
+        );
+        assert_eq!(tool_section("Make", &index, &prefs).1, "Custom Programs");
+
+        prefs
+            .tool_categories
+            .insert("DeployTool".to_string(), ToolCategory::Program);
+        let saved = toml::to_string(&prefs).unwrap();
+        assert!(saved.contains("DeployTool = \"program\""));
+    }
+
+    #[test]
+    fn test_effective_config_merges_project_preferences() {
+        use crate::preferences::{McpAuth, McpServerConfig};
+
+        let dir = tempdir().unwrap();
+        let config_file = dir.path().join(".mooagent.toml");
+        fs::write(
+            &config_file,
+            r#"
+[preferences]
+disabled_mcp_servers = ["local"]
+
+[preferences.general]
+sandboxed_mode = false
+
+[preferences.individual_tools]
+terraform = true
+"#,
+        )
+        .unwrap();
+
+        let mut paths = crate::config::ConfigPaths {
+            config_file: config_file.clone(),
+            ..test_paths(dir.path())
+        };
+        let servers = &mut paths.preferences.global_prefs.mcp_servers;
+        servers.insert(
+            "local".to_string(),
+            McpServerConfig::Stdio {
+                command: "local".to_string(),
+                args: Vec::new(),
+                env: std::collections::HashMap::new(),
+                disabled_tools: Vec::new(),
+                auto_allow: false,
+                description: None,
+                tags: Vec::new(),
+            },
+        );
+        servers.insert(
+            "remote".to_string(),
+            McpServerConfig::Http {
+                http_url: "https://example.com/mcp".to_string(),
+                auth: McpAuth::Bearer {
+                    token: "secret-token".to_string(),
+                },
+                headers: std::collections::HashMap::new(),
+                headers_override_auth: false,
+                disabled_tools: Vec::new(),
+                auto_allow: false,
+                description: None,
+                tags: Vec::new(),
+            },
+        );
+        paths.preferences.load_project(&config_file).unwrap();
+
+        let effective = crate::mcp::effective_config(&paths);
+
+        assert_eq!(effective["project_preferences"], true);
+        let servers = effective["mcp_servers"].as_object().unwrap();
+        assert!(!servers.contains_key("local"));
+        assert!(servers.contains_key("remote"));
+        assert!(!effective.to_string().contains("secret-token"));
+        assert_eq!(effective["tools"]["terraform"], true);
+        assert_eq!(effective["tools"]["ls"], true);
+        assert_eq!(effective["general"]["sandboxed_mode"], false);
+        // Unset unless the user opts in
+        assert!(effective["general"]["auto_accept_tools"].is_null());
+    }
+
+    #[test]
+    fn test_stdio_handshake() {
+        use crate::mcp::stdio_handshake;
+
+        let env = std::collections::HashMap::new();
+        let script = |body: &str| vec!["-c".to_string(), body.to_string()];
+
+        let reply =
+            r#"{"jsonrpc":"2.0","id":1,"result":{"serverInfo":{"name":"fake","version":"1.2.3"}}}"#;
+        let info = stdio_handshake(
+            "sh",
+            &script(&format!("read line; echo '{}'", reply)),
+            &env,
+            5,
+        )
+        .unwrap();
+        assert_eq!(info, "fake 1.2.3");
+
+        let err = stdio_handshake("sh", &script("exec sleep 10"), &env, 1).unwrap_err();
+        assert!(err.to_string().contains("within 1s"));
+
+        let err = stdio_handshake("sh", &script("exit 0"), &env, 5).unwrap_err();
+        assert!(err.to_string().contains("without answering"));
+    }
+
+    #[test]
+    fn test_agents_sharing_a_target_are_flagged() {
+        use crate::config::{AgentDefinition, SyncStrategy};
+
+        let dir = tempdir().unwrap();
+        let agent = |name: &str, path: &str, strategy| AgentDefinition {
+            name: name.to_string(),
+            target_path: dir.path().join(path),
+            strategy,
+            global_file: None,
+            group: None,
+            header: None,
+            footer: None,
+        };
+        let mut paths = crate::config::ConfigPaths {
+            agent_configs: vec![
+                agent("Claude", "CLAUDE.md", SyncStrategy::Merge),
+                agent("Gemini", "GEMINI.md", SyncStrategy::Merge),
+                agent("Other", "./CLAUDE.md", SyncStrategy::Symlink),
+            ],
+            ..test_paths(dir.path())
+        };
+
+        let conflicts = paths.target_conflicts();
+        assert_eq!(conflicts.len(), 1);
+        assert!(conflicts[0].contains("Claude (merge), Other (symlink)"));
+        assert!(conflicts[0].contains("CLAUDE.md"));
+
+        let checks = crate::doctor::run(&paths, 1);
+        assert!(
+            checks
+                .iter()
+                .any(|c| c.label == conflicts[0] && c.status == crate::doctor::CheckStatus::Fail)
+        );
+
+        paths.agent_configs.pop();
+        assert!(paths.target_conflicts().is_empty());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_broken_symlink_reported_as_drift() {
+        use crate::config::{AgentDefinition, SyncState, SyncStrategy};
+
+        let dir = tempdir().unwrap();
+        let target = dir.path().join("CLAUDE.md");
+        std::os::unix::fs::symlink("gone/AGENTS.md", &target).unwrap();
+
+        let paths = crate::config::ConfigPaths {
+            agent_configs: vec![AgentDefinition {
+                name: "Claude".to_string(),
+                target_path: target.clone(),
+                strategy: SyncStrategy::Symlink,
+                global_file: None,
+                group: None,
+                header: None,
+                footer: None,
+            }],
+            ..test_paths(dir.path())
+        };
+        fs::write(&paths.project_agents, "# Project\n").unwrap();
+
+        let agent = &paths.get_agents()[0];
+        assert_eq!(agent.sync_status.rules, SyncState::Drift);
+        assert!(agent.has_broken_link());
+        assert_eq!(
+            agent.link.as_ref().unwrap().describe(),
+            "-> gone/AGENTS.md (broken symlink)"
+        );
+
+        paths.sync().unwrap();
+        let agent = &paths.get_agents()[0];
+        assert_eq!(agent.sync_status.rules, SyncState::Ok);
+        assert_eq!(agent.link.as_ref().unwrap().describe(), "-> AGENTS.md");
+    }
+
+    #[test]
+    fn test_resolve_config_path() {
+        use crate::config::resolve_config_path;
+
+        let project = PathBuf::from("/work/project");
+        let home = dirs::home_dir().unwrap();
+
+        assert_eq!(
+            resolve_config_path(&project, "CLAUDE.md"),
+            project.join("CLAUDE.md")
+        );
+        assert_eq!(
+            resolve_config_path(&project, "~/.config/foo/AGENTS.md"),
+            home.join(".config/foo/AGENTS.md")
+        );
+        assert_eq!(
+            resolve_config_path(&project, "/etc/agents/RULES.md"),
+            PathBuf::from("/etc/agents/RULES.md")
+        );
+    }
+
+    #[test]
+    fn test_preview_mcp_server_config() {
+        use crate::preferences::McpServerConfig;
+
+        let dir = tempdir().unwrap();
+        let server = McpServerConfig::Stdio {
+            command: "uvx".to_string(),
+            args: vec!["mcp-server-git".to_string()],
+            env: std::collections::HashMap::new(),
+            disabled_tools: Vec::new(),
+            auto_allow: false,
+            description: None,
+            tags: Vec::new(),
+        };
+
+        let claude = crate::preferences::ClaudeConfigGenerator {
+            config_dir: dir.path().join(".claude"),
+            user_config_path: dir.path().join(".claude.json"),
+        };
+        let entry = claude.preview_server("git", &server).unwrap().unwrap();
+        assert_eq!(entry["command"], "uvx");
+        assert_eq!(entry["args"], json!(["mcp-server-git"]));
+
+        let opencode = crate::preferences::OpenCodeConfigGenerator {
+            config_dir: dir.path().join("opencode"),
+        };
+        let entry = opencode.preview_server("git", &server).unwrap().unwrap();
+        assert_eq!(entry["command"], json!(["uvx", "mcp-server-git"]));
+
+        let continue_dev = crate::preferences::ContinueConfigGenerator {
+            config_dir: dir.path().join(".continue"),
+        };
+        let entry = continue_dev
+            .preview_server("git", &server)
+            .unwrap()
+            .unwrap();
+        assert_eq!(entry["name"], "git");
+
+        // Previewing only generates in memory.
+        assert!(!dir.path().join(".claude.json").exists());
+        assert!(!dir.path().join("opencode").exists());
+        assert!(!dir.path().join(".continue").exists());
+    }
+
+    #[test]
+    fn test_mcp_server_description_is_not_synced() {
+        use crate::app::McpEditorState;
+        use crate::preferences::McpServerConfig;
+
+        let dir = tempdir().unwrap();
+        fs::write(
+            dir.path().join("preferences.toml"),
+            "[mcp_servers.git]
+command = \"uvx\"\nargs = [\"mcp-server-git\"]\ndescription = \"Commit history for code review\"\n",
+        )
+        .unwrap();
+        let mut manager = crate::preferences::PreferenceManager::new(dir.path());
+        manager.load_global().unwrap();
+        let server = &manager.global_prefs.mcp_servers["git"];
+        assert!(matches!(server, McpServerConfig::Stdio { .. }));
+        assert_eq!(server.description(), Some("Commit history for code review"));
+
+        manager.save_global().unwrap();
+        let saved = fs::read_to_string(dir.path().join("preferences.toml")).unwrap();
+        assert!(saved.contains("description = \"Commit history for code review\""));
+
+        let claude = crate::preferences::ClaudeConfigGenerator {
+            config_dir: dir.path().join(".claude"),
+            user_config_path: dir.path().join(".claude.json"),
+        };
+        let gemini = crate::preferences::GeminiConfigGenerator {
+            config_dir: dir.path().join(".gemini"),
+        };
+        let opencode = crate::preferences::OpenCodeConfigGenerator {
+            config_dir: dir.path().join("opencode"),
+        };
+        let generators: [&dyn ConfigGenerator; 3] = [&claude, &gemini, &opencode];
+        for generator in generators {
+            for (_, content) in generator.generate(&manager.global_prefs, None).unwrap() {
+                assert!(!content.contains("Commit history"), "{}", content);
+            }
+        }
+
+        // Blank descriptions from the editor are dropped
+        let mut state = McpEditorState {
+            editing_name: "git".to_string(),
+            editing_command: "uvx".to_string(),
+            editing_description: "  Git history  ".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(state.to_config(false).description(), Some("Git history"));
+        state.editing_description = "   ".to_string();
+        assert_eq!(state.to_config(false).description(), None);
+    }
+
+    #[test]
+    fn test_mcp_server_tags_filter() {
+        use crate::app::McpEditorState;
+        use crate::preferences::McpServerConfig;
+
+        let dir = tempdir().unwrap();
+        fs::write(
+            dir.path().join("preferences.toml"),
+            r#"[mcp_servers.github]
+command = "npx"
+description = "Team repositories"
+tags = ["work", "experimental"]
+
+[mcp_servers.network-scan]
+command = "nmap-mcp"
+"#,
+        )
+        .unwrap();
+        let mut manager = crate::preferences::PreferenceManager::new(dir.path());
+        manager.load_global().unwrap();
+        let servers = &manager.global_prefs.mcp_servers;
+        let github = &servers["github"];
+        let scan = &servers["network-scan"];
+        assert_eq!(github.tags(), ["work", "experimental"]);
+
+        assert!(github.matches_filter("github", "WORK"));
+        assert!(github.matches_filter("github", "repositories"));
+        assert!(scan.matches_filter("network-scan", "work"));
+        assert!(github.matches_filter("github", "#work"));
+        assert!(!scan.matches_filter("network-scan", "#work"));
+        assert!(!github.matches_filter("github", "#exp"));
+
+        let opencode = crate::preferences::OpenCodeConfigGenerator {
+            config_dir: dir.path().join("opencode"),
+        };
+        let files = opencode.generate(&manager.global_prefs, None).unwrap();
+        assert!(!files[0].1.contains("experimental"));
+
+        let state = McpEditorState {
+            editing_command: "npx".to_string(),
+            editing_tags: "work, , experimental".to_string(),
+            ..Default::default()
+        };
+        assert!(matches!(
+            state.to_config(false),
+            McpServerConfig::Stdio { tags, .. } if tags == ["work", "experimental"]
+        ));
+    }
+
+    #[test]
+    fn test_toggle_disabled_mcp_servers() {
+        let mut prefs = crate::preferences::AgentPreferences {
+            disabled_mcp_servers: vec!["github".to_string()],
+            ..Default::default()
+        };
+        let remote = vec!["github".to_string(), "linear".to_string()];
+
+        // Any enabled server means the whole group gets disabled
+        assert_eq!(prefs.toggle_disabled_mcp_servers(&remote), (true, 1));
+        assert_eq!(prefs.disabled_mcp_servers, ["github", "linear"]);
+
+        prefs.disabled_mcp_servers.push("local".to_string());
+        assert_eq!(prefs.toggle_disabled_mcp_servers(&remote), (false, 2));
+        assert_eq!(prefs.disabled_mcp_servers, ["local"]);
+    }
+
+    #[test]
+    fn test_key_values_with_commas_round_trip() {
+        use crate::app::{format_key_values, parse_key_values};
+
+        let parsed = parse_key_values(
+            r#"CONFIG="{\"a\":1,\"b\":2}", PATH_LIST = "/a, /b" ,PLAIN=x=y,BROKEN,EMPTY="#,
+        );
+        assert_eq!(parsed["CONFIG"], r#"{"a":1,"b":2}"#);
+        assert_eq!(parsed["PATH_LIST"], "/a, /b");
+        assert_eq!(parsed["PLAIN"], "x=y");
+        assert_eq!(parsed["EMPTY"], "");
+        assert!(!parsed.contains_key("BROKEN"));
+
+        let mut values = std::collections::HashMap::new();
+        values.insert("JSON".to_string(), r#"{"k":"v,w"}"#.to_string());
+        values.insert("WIN".to_string(), r"C:\tools".to_string());
+        values.insert("PADDED".to_string(), " x ".to_string());
+        values.insert("SIMPLE".to_string(), "1".to_string());
+        let formatted = format_key_values(&values);
+        assert!(formatted.contains(r"WIN=C:\tools"));
+        assert_eq!(parse_key_values(&formatted), values);
+    }
+
+    #[test]
+    fn test_mcp_state_reloads_only_when_config_changes() {
+        let dir = tempdir().unwrap();
+        let config_dir = dir.path().join("config");
+        fs::create_dir_all(&config_dir).unwrap();
+        let config_file = dir.path().join(".mooagent.toml");
+        fs::write(&config_file, "").unwrap();
+
+        let load = || {
+            crate::mcp::McpState::new(crate::config::ConfigPaths {
+                config_file: config_file.clone(),
+                ..test_paths(dir.path())
+            })
+        };
+        let state = load();
+        assert!(!state.is_stale());
+
+        // Rules files are read fresh by each tool, so writing them keeps the cache
+        fs::write(dir.path().join("AGENTS.md"), "# Rules").unwrap();
+        assert!(!state.is_stale());
+
+        // A preferences file appearing or a project config edit forces a reload
+        fs::write(config_dir.join("preferences.toml"), "").unwrap();
+        assert!(state.is_stale());
+        let state = load();
+        assert!(!state.is_stale());
+        fs::write(&config_file, "[preferences]\n").unwrap();
+        assert!(state.is_stale());
+
+        assert!(crate::mcp::McpState::default().is_stale());
+    }
+
+    #[test]
+    fn test_interleaved_mcp_add_calls_both_persist() {
+        let dir = tempdir().unwrap();
+        let config_dir = dir.path().join("config");
+        let instance = || {
+            let mut preferences = crate::preferences::PreferenceManager::new(&config_dir);
+            preferences.load_global().unwrap();
+            crate::config::ConfigPaths {
+                preferences,
+                ..test_paths(dir.path())
+            }
+        };
+
+        // Both instances load the same (empty) server list before either writes
+        let mut first = instance();
+        let mut second = instance();
+        crate::mcp::run_tool(
+            &mut first,
+            "mcp_add",
+            json!({"name": "alpha", "command": "alpha-mcp"}),
+            &crate::mcp::Progress::default(),
+        )
+        .unwrap();
+        crate::mcp::run_tool(
+            &mut second,
+            "mcp_add",
+            json!({"name": "beta", "command": "beta-mcp"}),
+            &crate::mcp::Progress::default(),
+        )
+        .unwrap();
+
+        let saved = instance();
+        let servers = &saved.preferences.global_prefs.mcp_servers;
+        assert!(servers.contains_key("alpha"));
+        assert!(servers.contains_key("beta"));
+
+        crate::mcp::run_tool(
+            &mut first,
+            "mcp_remove",
+            json!({"name": "alpha"}),
+            &crate::mcp::Progress::default(),
+        )
+        .unwrap();
+        let saved = instance();
+        let servers = &saved.preferences.global_prefs.mcp_servers;
+        assert!(!servers.contains_key("alpha"));
+        assert!(servers.contains_key("beta"));
+        // Every call released the lock again
+        assert!(crate::lock::acquire(&config_dir).is_ok());
+    }
+
+    #[test]
+    fn test_sync_status_json() {
+        let dir = tempdir().unwrap();
+        let config_dir = dir.path().join("config");
+        fs::create_dir_all(&config_dir).unwrap();
+        let paths = crate::config::ConfigPaths {
+            agent_configs: vec![crate::config::AgentDefinition {
+                name: "Custom".to_string(),
+                target_path: dir.path().join("CUSTOM.md"),
+                strategy: crate::config::SyncStrategy::Merge,
+                global_file: None,
+                group: None,
+                header: None,
+                footer: None,
+            }],
+            ..test_paths(dir.path())
+        };
+        fs::create_dir_all(&paths.backup_dir).unwrap();
+        paths.ensure_files_exist().unwrap();
+
+        let status = crate::mcp::sync_status_json(&paths);
+        let agent = &status["agents"][0];
+        assert_eq!(agent["name"], "Custom");
+        assert_eq!(agent["status"], "missing");
+        assert_eq!(agent["rules"], "missing");
+        assert_eq!(agent["strategy"], "merge");
+        assert_eq!(agent["global_rules"], "not_applicable");
+        assert_eq!(
+            agent["target_path"],
+            dir.path().join("CUSTOM.md").to_string_lossy().as_ref()
+        );
+        assert!(agent["last_synced"].is_null());
+        assert_eq!(status["global_rules_drift"], json!([]));
+        assert!(status["preference_drift"].is_boolean());
+        assert_eq!(status["mcp_server_count"], 0);
+
+        paths.sync().unwrap();
+        let status = crate::mcp::sync_status_json(&paths);
+        assert_eq!(status["agents"][0]["rules"], "ok");
+        assert!(status["agents"][0]["last_synced"].is_string());
+    }
+
+    #[test]
+    fn test_set_project_dir_rejects_missing_and_non_directories() {
+        let dir = tempdir().unwrap();
+        let err = crate::config::set_project_dir(&dir.path().join("missing")).unwrap_err();
+        assert!(err.to_string().contains("not found"));
+
+        let file = dir.path().join("AGENTS.md");
+        fs::write(&file, "# Rules").unwrap();
+        let err = crate::config::set_project_dir(&file).unwrap_err();
+        assert!(err.to_string().contains("is not a directory"));
+    }
+
+    #[test]
+    fn test_tool_timeout_defaults_and_overrides() {
+        use crate::mcp::{ToolTimeout, tool_timeout};
+        use std::time::Duration;
+
+        let mut general = crate::preferences::GeneralPreferences::default();
+        assert_eq!(tool_timeout(&general, "sync_all"), Duration::from_secs(120));
+        assert_eq!(
+            tool_timeout(&general, "oauth_login"),
+            Duration::from_secs(300)
+        );
+
+        general.mcp_tool_timeout_secs = Some(5);
+        general.oauth_login_timeout_secs = Some(30);
+        assert_eq!(tool_timeout(&general, "sync_all"), Duration::from_secs(5));
+        assert_eq!(
+            tool_timeout(&general, "oauth_login"),
+            Duration::from_secs(30)
+        );
+
+        let err: anyhow::Error = ToolTimeout {
+            tool: "oauth_login".to_string(),
+            secs: 30,
+        }
+        .into();
+        assert!(err.is::<ToolTimeout>());
+        assert!(err.to_string().contains("oauth_login_timeout_secs"));
+    }
+
+    #[test]
+    fn test_sync_reports_progress_when_token_given() {
+        use crate::mcp::{Progress, ToolEvent};
+
+        let dir = tempdir().unwrap();
+        let config_dir = dir.path().join("config");
+        fs::write(dir.path().join("AGENTS.md"), "# Rules").unwrap();
+        let mut preferences = crate::preferences::PreferenceManager::new(&config_dir);
+        preferences.load_global().unwrap();
+        let mut paths = crate::config::ConfigPaths {
+            preferences,
+            ..test_paths(dir.path())
+        };
+
+        let (tx, rx) = std::sync::mpsc::channel();
+        let progress = Progress::new(json!("tok-1"), tx);
+        crate::mcp::run_tool(&mut paths, "sync", json!({}), &progress).unwrap();
+        drop(progress);
+
+        let steps: Vec<u64> = rx
+            .iter()
+            .map(|event| match event {
+                ToolEvent::Progress(notification) => {
+                    assert_eq!(notification["method"], "notifications/progress");
+                    assert_eq!(notification["params"]["progressToken"], "tok-1");
+                    assert_eq!(notification["params"]["total"], 4);
+                    notification["params"]["progress"].as_u64().unwrap()
+                }
+                ToolEvent::Done(..) => panic!("run_tool does not send Done"),
+            })
+            .collect();
+        assert_eq!(steps, vec![0, 1, 2, 3, 4]);
+
+        // Without a token nothing is reported
+        Progress::default().report(1, 1, "ignored");
+    }
+
+    #[test]
+    fn test_append_tools_back_up_unless_disabled() {
+        let dir = tempdir().unwrap();
+        let config_dir = dir.path().join("config");
+        fs::write(dir.path().join("AGENTS.md"), "# Rules").unwrap();
+        let mut preferences = crate::preferences::PreferenceManager::new(&config_dir);
+        preferences.load_global().unwrap();
+        let mut paths = crate::config::ConfigPaths {
+            preferences,
+            ..test_paths(dir.path())
+        };
+        let append = |paths: &mut crate::config::ConfigPaths| {
+            crate::mcp::run_tool(
+                paths,
+                "edit_project_rules",
+                json!({"content": "- Be brief"}),
+                &crate::mcp::Progress::default(),
+            )
+            .unwrap();
+        };
+
+        append(&mut paths);
+        let backups = paths.list_backups_for(&paths.project_agents);
+        assert_eq!(backups.len(), 1);
+        assert_eq!(fs::read_to_string(&backups[0]).unwrap(), "# Rules");
+
+        paths.preferences.global_prefs.general.backup_before_edit = Some(false);
+        fs::remove_file(&backups[0]).unwrap();
+        append(&mut paths);
+        assert!(paths.list_backups_for(&paths.project_agents).is_empty());
+        assert_eq!(
+            fs::read_to_string(&paths.project_agents).unwrap(),
+            "# Rules\n\n- Be brief\n\n- Be brief"
+        );
+    }
+
+    #[test]
+    fn test_env_flag() {
+        assert!(!crate::env_flag(None));
+        assert!(!crate::env_flag(Some("")));
+        assert!(!crate::env_flag(Some("0")));
+        assert!(crate::env_flag(Some("1")));
+        assert!(crate::env_flag(Some("true")));
+    }
+
+    #[test]
+    fn test_export_status_log_writes_wall_clock_entries() {
+        use chrono::TimeZone;
+
+        let dir = tempdir().unwrap();
+        let time = chrono::Local
+            .with_ymd_and_hms(2026, 3, 4, 5, 6, 7)
+            .single()
+            .unwrap();
+        let entries = vec![
+            ("Synced Claude".to_string(), time),
+            ("Error: boom".to_string(), time),
+        ];
+
+        let file = crate::app::export_status_log(&dir.path().join("data"), &entries).unwrap();
+        assert!(file.starts_with(dir.path().join("data")));
+        assert_eq!(
+            fs::read_to_string(file).unwrap(),
+            "[2026-03-04 05:06:07] Synced Claude\n[2026-03-04 05:06:07] Error: boom\n"
+        );
+    }
+
+    #[test]
+    fn test_format_clock_adds_date_for_older_entries() {
+        use chrono::TimeZone;
+
+        let at = |d, h| {
+            chrono::Local
+                .with_ymd_and_hms(2026, 3, d, h, 6, 7)
+                .single()
+                .unwrap()
+        };
+        assert_eq!(crate::ui::format_clock(at(4, 5), at(4, 20)), "05:06:07");
+        assert_eq!(
+            crate::ui::format_clock(at(3, 5), at(4, 20)),
+            "2026-03-03 05:06:07"
+        );
+    }
+
+    #[test]
+    fn test_default_agents_reason_distinguishes_empty_agent_list() {
+        use crate::config::default_agents_reason;
+
+        let dir = tempdir().unwrap();
+        let config_file = dir.path().join(".mooagent.toml");
+        assert!(
+            default_agents_reason(&config_file)
+                .unwrap()
+                .contains("no .mooagent.toml")
+        );
+
+        fs::write(&config_file, "[preferences]\n").unwrap();
+        assert!(
+            default_agents_reason(&config_file)
+                .unwrap()
+                .contains("no [[agents]]")
+        );
+
+        fs::write(&config_file, "agents = []\n\n[preferences]\n").unwrap();
+        assert_eq!(default_agents_reason(&config_file), None);
+
+        fs::write(
+            &config_file,
+            "[[agents]]\nname = \"Claude\"\npath = \"CLAUDE.md\"\n",
+        )
+        .unwrap();
+        assert_eq!(default_agents_reason(&config_file), None);
+    }
+
+    #[test]
+    fn test_expand_agent_targets_fans_out_globs() {
+        use crate::config::{AgentDefinition, SyncStrategy, expand_agent_targets};
+
+        // Glob characters in the project directory itself are matched literally
+        let tmp = tempdir().unwrap();
+        let dir = tmp.path().join("app[v2]");
+        for pkg in ["b", "a"] {
+            fs::create_dir_all(dir.join("packages").join(pkg)).unwrap();
+        }
+        fs::write(dir.join("packages/README.md"), "").unwrap();
+        let expand = |path: &str| {
+            let definition = AgentDefinition {
+                name: "Packages".to_string(),
+                target_path: dir.join(path),
+                strategy: SyncStrategy::Symlink,
+                global_file: Some(dir.join("GLOBAL.md")),
+                group: None,
+                header: None,
+                footer: None,
+            };
+            expand_agent_targets(&dir, path, definition).unwrap()
+        };
+
+        let expanded = expand("packages/*/AGENTS.md");
+        let names: Vec<&str> = expanded.iter().map(|d| d.name.as_str()).collect();
+        assert_eq!(
+            names,
+            vec!["Packages (packages/a)", "Packages (packages/b)"]
+        );
+        assert_eq!(expanded[0].target_path, dir.join("packages/a/AGENTS.md"));
+        assert!(expanded[0].global_file.is_some());
+        assert!(expanded[1].global_file.is_none());
+        assert!(expanded.iter().all(|d| d.base_name() == "Packages"));
+
+        // A wildcard file name only matches files that exist
+        let expanded = expand("packages/*.md");
+        assert_eq!(expanded.len(), 1);
+        assert_eq!(expanded[0].name, "Packages (packages)");
+
+        let plain = expand("AGENTS.md");
+        assert_eq!(plain.len(), 1);
+        assert_eq!(plain[0].name, "Packages");
+        assert_eq!(plain[0].group, None);
+    }
+
+    #[test]
+    fn test_agent_header_and_footer_wrap_synced_content() {
+        use crate::config::{AgentDefinition, AgentStatus, SyncStrategy};
+
+        let dir = tempdir().unwrap();
+        let config_dir = dir.path().join("config");
+        fs::write(dir.path().join("AGENTS.md"), "# Rules\n").unwrap();
+        fs::create_dir_all(&config_dir).unwrap();
+        fs::write(config_dir.join("GLOBAL_RULES.md"), "").unwrap();
+        let paths = crate::config::ConfigPaths {
+            agent_configs: vec![AgentDefinition {
+                name: "Custom".to_string(),
+                target_path: dir.path().join("CURSOR.md"),
+                strategy: SyncStrategy::Merge,
+                global_file: None,
+                group: None,
+                header: Some("---\nalwaysApply: true\n---\n".to_string()),
+                footer: Some("<!-- managed -->".to_string()),
+            }],
+            ..test_paths(dir.path())
+        };
+
+        paths.sync().unwrap();
+        assert_eq!(
+            fs::read_to_string(dir.path().join("CURSOR.md")).unwrap(),
+            "---\nalwaysApply: true\n---\n\n# Rules\n\n<!-- managed -->\n"
+        );
+        assert_eq!(paths.get_agents()[0].status, AgentStatus::Ok);
+
+        // The bare AGENTS.md content no longer counts as in sync
+        fs::write(dir.path().join("CURSOR.md"), "# Rules\n").unwrap();
+        assert_eq!(paths.get_agents()[0].status, AgentStatus::Drift);
+    }
+
+    #[test]
+    fn test_hand_edits_are_detected_and_pulled_back() {
+        use crate::config::{AgentDefinition, SyncStrategy};
+
+        let dir = tempdir().unwrap();
+        let config_dir = dir.path().join("config");
+        let target = dir.path().join("CLAUDE.md");
+        fs::write(dir.path().join("AGENTS.md"), "# Rules\n").unwrap();
+        fs::create_dir_all(&config_dir).unwrap();
+        fs::write(config_dir.join("GLOBAL_RULES.md"), "").unwrap();
+        let paths = crate::config::ConfigPaths {
+            agent_configs: vec![AgentDefinition {
+                name: "Claude".to_string(),
+                target_path: target.clone(),
+                strategy: SyncStrategy::Merge,
+                global_file: None,
+                group: None,
+                header: Some("<!-- managed -->".to_string()),
+                footer: None,
+            }],
+            ..test_paths(dir.path())
+        };
+
+        paths.sync().unwrap();
+        // Drift from editing AGENTS.md is an ordinary sync
+        fs::write(dir.path().join("AGENTS.md"), "# Rules\n- new\n").unwrap();
+        assert_eq!(paths.hand_edit_diff(0), None);
+
+        fs::write(&target, "<!-- managed -->\n\n# Rules\n- mine\n").unwrap();
+        let diff = paths.hand_edit_diff(0).unwrap();
+        assert!(diff.contains("+- mine"));
+        assert!(diff.contains("-- new"));
+
+        paths.pull_agent_edits(0).unwrap();
+        assert_eq!(
+            fs::read_to_string(dir.path().join("AGENTS.md")).unwrap(),
+            "# Rules\n- mine\n"
+        );
+        assert_eq!(paths.hand_edit_diff(0), None);
+        assert_eq!(paths.get_agents()[0].status, crate::config::AgentStatus::Ok);
+    }
+
+    #[test]
+    fn test_restore_backup_keeps_a_backup_of_the_current_file() {
+        let dir = tempdir().unwrap();
+        let target = dir.path().join("CLAUDE.md");
+        fs::write(&target, "old").unwrap();
+        let paths = crate::config::ConfigPaths {
+            agent_configs: vec![crate::config::AgentDefinition {
+                name: "Claude".to_string(),
+                target_path: target.clone(),
+                strategy: crate::config::SyncStrategy::Merge,
+                global_file: None,
+                group: None,
+                header: None,
+                footer: None,
+            }],
+            ..test_paths(dir.path())
+        };
+
+        let backup = paths.backup_if_needed(&target).unwrap().unwrap();
+        fs::write(&target, "new").unwrap();
+        assert_eq!(paths.list_backups(0), vec![backup.clone()]);
+
+        // Backup names have one-second resolution
+        std::thread::sleep(std::time::Duration::from_millis(1100));
+        paths.restore_backup(&backup, &target).unwrap();
+        assert_eq!(fs::read_to_string(&target).unwrap(), "old");
+        let backups = paths.list_backups(0);
+        assert_eq!(backups.len(), 2);
+        assert_eq!(fs::read_to_string(&backups[0]).unwrap(), "new");
+    }
+
+    #[test]
+    fn test_expired_oauth_servers_only_lists_refreshable_tokens() {
+        use crate::credentials::{CredentialManager, StoredToken};
+        use crate::preferences::{AgentPreferences, McpAuth, McpServerConfig};
+
+        let dir = tempdir().unwrap();
+        let mut prefs = AgentPreferences::default();
+        for name in ["expired", "fresh", "no-refresh", "disabled"] {
+            prefs.mcp_servers.insert(
+                name.to_string(),
+                McpServerConfig::Http {
+                    http_url: format!("https://{}.example.com/mcp", name),
+                    auth: McpAuth::OAuth {
+                        client_id: "client".to_string(),
+                        client_secret: None,
+                        auth_server_url: None,
+                        scopes: Vec::new(),
+                    },
+                    headers: Default::default(),
+                    headers_override_auth: false,
+                    disabled_tools: Vec::new(),
+                    auto_allow: false,
+                    description: None,
+                    tags: Vec::new(),
+                },
+            );
+        }
+        prefs.disabled_mcp_servers.push("disabled".to_string());
+
+        let mut paths = test_paths(dir.path());
+        fs::create_dir_all(&paths.config_dir).unwrap();
+        let mut credentials = CredentialManager::new(&paths.config_dir);
+        let past = chrono::Utc::now() - chrono::Duration::hours(1);
+        let future = chrono::Utc::now() + chrono::Duration::hours(1);
+        for (name, expires_at, refresh) in [
+            ("expired", past, true),
+            ("fresh", future, true),
+            ("no-refresh", past, false),
+            ("disabled", past, true),
+        ] {
+            credentials
+                .store_token(
+                    &format!("https://{}.example.com/mcp", name),
+                    StoredToken {
+                        access_token: "access".to_string(),
+                        refresh_token: refresh.then(|| "refresh".to_string()),
+                        expires_at: Some(expires_at),
+                        token_type: "Bearer".to_string(),
+                        scopes: vec![],
+                    },
+                )
+                .unwrap();
+        }
+
+        assert_eq!(
+            crate::config::expired_oauth_servers(&prefs, &credentials),
+            vec!["expired".to_string()]
+        );
+
+        // The dry run lists them, since `sync` refreshes them before writing preferences
+        paths.preferences.global_prefs = prefs;
+        let preview = crate::mcp::sync_preview(&paths).unwrap();
+        assert!(preview.contains("Expired OAuth token(s) that `sync` refreshes first: expired\n"));
+    }
+
+    #[test]
+    fn test_cursor_generator() {
+        let dir = tempdir().unwrap();
+        fs::write(
+            dir.path().join("mcp.json"),
+            json!({ "mcpServers": { "manual": { "command": "manual-server" } } }).to_string(),
+        )
+        .unwrap();
+
+        let mut prefs = crate::preferences::AgentPreferences::default();
+        prefs.mcp_servers.insert(
+            "local".to_string(),
+            crate::preferences::McpServerConfig::Stdio {
+                command: "uvx".to_string(),
+                args: vec!["server".to_string()],
+                env: std::collections::HashMap::from([("DEBUG".to_string(), "1".to_string())]),
+                disabled_tools: Vec::new(),
+                auto_allow: false,
+                description: None,
+                tags: Vec::new(),
+            },
+        );
+        prefs.mcp_servers.insert(
+            "remote".to_string(),
+            crate::preferences::McpServerConfig::Sse {
+                url: "https://example.com/sse".to_string(),
+                auth: crate::preferences::McpAuth::Bearer {
+                    token: "secret".to_string(),
+                },
+                headers: Default::default(),
+                headers_override_auth: false,
+                disabled_tools: Vec::new(),
+                auto_allow: false,
+                description: None,
+                tags: Vec::new(),
+            },
+        );
+
+        let generator = crate::preferences::CursorConfigGenerator {
+            config_dir: dir.path().to_path_buf(),
+        };
+        let results = generator.generate(&prefs, None).unwrap();
+        assert_eq!(results[0].0, dir.path().join("mcp.json"));
+        let config: serde_json::Value = serde_json::from_str(&results[0].1).unwrap();
+
+        assert_eq!(
+            config["mcpServers"],
+            json!({
+                "manual": { "command": "manual-server" },
+                "local": { "command": "uvx", "args": ["server"], "env": { "DEBUG": "1" } },
+                "remote": {
+                    "url": "https://example.com/sse",
+                    "headers": { "Authorization": "Bearer secret" }
+                }
+            })
+        );
+    }
+
+    #[test]
+    fn test_mcp_rename_keeps_server_config() {
+        let dir = tempdir().unwrap();
+        let config_dir = dir.path().join("config");
+        let mut preferences = crate::preferences::PreferenceManager::new(&config_dir);
+        preferences.load_global().unwrap();
+        let mut paths = crate::config::ConfigPaths {
+            preferences,
+            ..test_paths(dir.path())
+        };
+        let run = |paths: &mut crate::config::ConfigPaths, name: &str, arguments| {
+            crate::mcp::run_tool(paths, name, arguments, &crate::mcp::Progress::default())
+        };
+
+        run(
+            &mut paths,
+            "mcp_add",
+            json!({"name": "old", "command": "old-mcp", "args": ["--verbose"]}),
+        )
+        .unwrap();
+        run(
+            &mut paths,
+            "mcp_add",
+            json!({"name": "other", "command": "x"}),
+        )
+        .unwrap();
+        let before = paths.preferences.global_prefs.mcp_servers["old"].clone();
+        let mut project = crate::preferences::AgentPreferences::default();
+        project.disabled_mcp_servers.push("old".to_string());
+        project
+            .mcp_servers
+            .insert("old".to_string(), before.clone());
+        paths.preferences.project_prefs = Some(project);
+
+        let message = run(
+            &mut paths,
+            "mcp_rename",
+            json!({"old_name": "old", "new_name": "new"}),
+        )
+        .unwrap();
+        assert!(message.contains("Run 'sync'"));
+        assert!(message.contains("Other projects"));
+
+        let mut saved = crate::preferences::PreferenceManager::new(&config_dir);
+        saved.load_global().unwrap();
+        assert!(!saved.global_prefs.mcp_servers.contains_key("old"));
+        assert_eq!(saved.global_prefs.mcp_servers["new"], before);
+        saved.load_project(&paths.config_file).unwrap();
+        let project = saved.project_prefs.unwrap();
+        assert_eq!(project.disabled_mcp_servers, vec!["new".to_string()]);
+        assert!(project.mcp_servers.contains_key("new"));
+        assert!(!project.mcp_servers.contains_key("old"));
+
+        assert!(
+            run(
+                &mut paths,
+                "mcp_rename",
+                json!({"old_name": "new", "new_name": "other"}),
+            )
+            .is_err()
+        );
+        assert!(
+            run(
+                &mut paths,
+                "mcp_rename",
+                json!({"old_name": "missing", "new_name": "fresh"}),
+            )
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn test_get_diff_is_a_unified_diff() {
+        let dir = tempdir().unwrap();
+        let config_dir = dir.path().join("config");
+        fs::create_dir_all(&config_dir).unwrap();
+        fs::write(dir.path().join("AGENTS.md"), "# Rules\n- one\n- two\n").unwrap();
+        let target = dir.path().join("CUSTOM.md");
+        let paths = crate::config::ConfigPaths {
+            agent_configs: vec![crate::config::AgentDefinition {
+                name: "Custom".to_string(),
+                target_path: target.clone(),
+                strategy: crate::config::SyncStrategy::Merge,
+                global_file: None,
+                group: None,
+                header: None,
+                footer: None,
+            }],
+            ..test_paths(dir.path())
+        };
+
+        paths.sync().unwrap();
+        assert_eq!(paths.get_diff(0), None);
+
+        fs::write(&target, "# Rules\n- one\n- three\n").unwrap();
+        let diff = paths.get_diff(0).unwrap();
+        let lines: Vec<&str> = diff.lines().collect();
+        assert!(lines.contains(&"--- expected"));
+        assert!(lines.contains(&" - one"));
+        assert!(lines.contains(&"-- two"));
+        assert!(lines.contains(&"+- three"));
+
+        fs::write(&target, [0xff, 0xfe, 0x00]).unwrap();
+        assert!(paths.get_diff(0).unwrap().starts_with("Cannot diff"));
+    }
+
+    #[test]
+    fn test_claude_user_config_keeps_foreign_servers() {
+        let dir = tempdir().unwrap();
+        let config_dir = dir.path().join("config");
+        let user_config = dir.path().join(".claude.json");
+        fs::write(
+            &user_config,
+            json!({
+                "numStartups": 12,
+                "projects": { "/code/app": { "allowedTools": [] } },
+                "mcpServers": { "manual": { "type": "stdio", "command": "manual" } }
+            })
+            .to_string(),
+        )
+        .unwrap();
+
+        let paths = test_paths(dir.path());
+        let generator = crate::preferences::ClaudeConfigGenerator {
+            config_dir: dir.path().join(".claude"),
+            user_config_path: user_config.clone(),
+        };
+        let credentials = crate::credentials::CredentialManager::new(&config_dir);
+        let user_file = |files: &[(PathBuf, String)]| {
+            let (_, content) = files.iter().find(|(p, _)| *p == user_config).unwrap();
+            serde_json::from_str::<serde_json::Value>(content).unwrap()
+        };
+
+        let mut prefs = crate::preferences::AgentPreferences::default();
+        prefs.mcp_servers.insert(
+            "managed".to_string(),
+            crate::preferences::McpServerConfig::Stdio {
+                command: "managed".to_string(),
+                args: Vec::new(),
+                env: std::collections::HashMap::new(),
+                disabled_tools: Vec::new(),
+                auto_allow: false,
+                description: None,
+                tags: Vec::new(),
+            },
+        );
+        let mut managed = Default::default();
+        let files = paths
+            .generate_reconciled(&generator, &prefs, &credentials, &mut managed)
+            .unwrap();
+        let config = user_file(&files);
+        assert_eq!(config["mcpServers"]["managed"]["command"], "managed");
+        fs::write(&user_config, config.to_string()).unwrap();
+
+        // Removing the server from mooagent prunes only the entry mooagent wrote
+        prefs.mcp_servers.clear();
+        let files = paths
+            .generate_reconciled(&generator, &prefs, &credentials, &mut managed)
+            .unwrap();
+        let config = user_file(&files);
+        assert_eq!(
+            config["mcpServers"],
+            json!({ "manual": { "type": "stdio", "command": "manual" } })
+        );
+        assert_eq!(config["numStartups"], 12);
+        assert!(config["projects"]["/code/app"].is_object());
+    }
+
+    #[test]
+    fn test_tab_at_follows_rendered_titles() {
+        use crate::app::{ActiveTab, tab_at, tab_titles};
+
+        let clean = tab_titles(false);
+        let dirty = tab_titles(true);
+        assert_eq!(dirty[1], format!("{} *", ActiveTab::ALL[1].title()));
+
+        // The first column of the last tab shifts right by the width of " *"
+        let last = ActiveTab::ALL.len() - 1;
+        let start = |titles: &[String]| -> u16 {
+            titles[..last]
+                .iter()
+                .map(|t| t.chars().count() as u16 + 3)
+                .sum()
+        };
+        assert_eq!(tab_at(&clean, start(&clean)), Some(last));
+        assert_eq!(tab_at(&dirty, start(&clean)), Some(last - 1));
+        assert_eq!(tab_at(&dirty, start(&dirty)), Some(last));
+        assert_eq!(tab_at(&dirty, 0), Some(0));
+        assert_eq!(tab_at(&dirty, 500), None);
+    }
+
+    #[test]
+    fn test_run_sync_dry_run_only_previews() {
+        let dir = tempdir().unwrap();
+        let mut paths = crate::config::ConfigPaths {
+            agent_configs: vec![crate::config::AgentDefinition {
+                name: "Custom".to_string(),
+                target_path: dir.path().join("CUSTOM.md"),
+                strategy: crate::config::SyncStrategy::Merge,
+                global_file: None,
+                group: None,
+                header: None,
+                footer: None,
+            }],
+            ..test_paths(dir.path())
+        };
+        fs::create_dir_all(&paths.config_dir).unwrap();
+        fs::write(&paths.project_agents, "# Rules\n").unwrap();
+
+        let (mut out, mut err) = (Vec::new(), Vec::new());
+        assert!(crate::run_sync(&mut paths, true, &mut out, &mut err).unwrap());
+        let out = String::from_utf8(out).unwrap();
+        assert!(out.contains("Sync Preview"));
+        assert!(out.contains("**Custom** [CREATE]"));
+        assert!(err.is_empty());
+        assert!(!dir.path().join("CUSTOM.md").exists());
+    }
+
+    #[test]
+    fn test_run_sync_stops_at_failed_rules() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("blocker"), "").unwrap();
+        let mut paths = crate::config::ConfigPaths {
+            agent_configs: vec![crate::config::AgentDefinition {
+                name: "Broken".to_string(),
+                target_path: dir.path().join("blocker/BROKEN.md"),
+                strategy: crate::config::SyncStrategy::Merge,
+                global_file: None,
+                group: None,
+                header: None,
+                footer: None,
+            }],
+            ..test_paths(dir.path())
+        };
+        fs::create_dir_all(&paths.config_dir).unwrap();
+        fs::create_dir_all(&paths.backup_dir).unwrap();
+
+        let (mut out, mut err) = (Vec::new(), Vec::new());
+        assert!(!crate::run_sync(&mut paths, false, &mut out, &mut err).unwrap());
+        let err = String::from_utf8(err).unwrap();
+        assert!(err.starts_with("Rules sync failed: Synced 0 of 1 agent(s); failed: Broken ("));
+        // Global rules and preferences are never reached
+        assert!(out.is_empty());
+    }
+
+    #[test]
+    fn test_refresh_picks_up_edited_preferences() {
+        let dir = tempdir().unwrap();
+        let mut paths = test_paths(dir.path());
+        fs::create_dir_all(&paths.config_dir).unwrap();
+        paths.preferences.load_global().unwrap();
+        assert!(
+            !paths
+                .preferences
+                .get_merged()
+                .mcp_servers
+                .contains_key("edited")
+        );
+
+        let prefs_path = paths.config_dir.join("preferences.toml");
+        let mut content = fs::read_to_string(&prefs_path).unwrap();
+        content.push_str("\n[mcp_servers.edited]\ncommand = \"edited-mcp\"\n");
+        fs::write(&prefs_path, content).unwrap();
+
+        let message = crate::mcp::run_tool(
+            &mut paths,
+            "refresh",
+            json!({}),
+            &crate::mcp::Progress::default(),
+        )
+        .unwrap();
+        assert!(message.starts_with(&format!(
+            "Reloaded configuration for {}",
+            dir.path().display()
+        )));
+        assert!(
+            paths
+                .preferences
+                .get_merged()
+                .mcp_servers
+                .contains_key("edited")
+        );
+        // The reload keeps this project and its directories
+        assert_eq!(paths.project_agents, dir.path().join("AGENTS.md"));
+        assert_eq!(paths.sync_state_file, dir.path().join("sync_state.json"));
+    }
+
+    #[test]
+    fn test_saving_preferences_keeps_dotfile_symlink() {
+        let dir = tempdir().unwrap();
+        let config_dir = dir.path().join("config");
+        fs::create_dir_all(&config_dir).unwrap();
+        let dotfile = dir.path().join("dotfiles.toml");
+        fs::write(&dotfile, "").unwrap();
+        std::os::unix::fs::symlink(&dotfile, config_dir.join("preferences.toml")).unwrap();
+
+        let mut preferences = crate::preferences::PreferenceManager::new(&config_dir);
+        preferences.load_global().unwrap();
+        preferences.global_prefs.general.auto_accept_tools = Some(false);
+        preferences.save_global().unwrap();
+
+        // Written through the link into the managed file rather than replacing the link
+        assert!(config_dir.join("preferences.toml").is_symlink());
+        assert!(
+            fs::read_to_string(&dotfile)
+                .unwrap()
+                .contains("auto_accept_tools = false")
+        );
+    }
+
+    #[test]
+    fn test_watched_change_ignores_other_config_dir_files() {
+        let config_dir = Path::new("/cfg");
+        let store = PathBuf::from("/cfg/credentials.json");
+        let files = [store.clone()];
+
+        // The store is picked up even when it is created after the TUI started
+        assert!(crate::watched_change(&[store], config_dir, &files));
+        assert!(!crate::watched_change(
+            &[PathBuf::from("/cfg/mooagent.lock")],
+            config_dir,
+            &files
+        ));
+        // Files watched on their own, elsewhere
+        assert!(crate::watched_change(
+            &[PathBuf::from("/project/AGENTS.md")],
+            config_dir,
+            &files
+        ));
+    }
+
+    #[test]
+    fn test_destructive_tools_need_token_from_first_call() {
+        let dir = tempdir().unwrap();
+        let mut paths = test_paths(dir.path());
+        fs::create_dir_all(&paths.config_dir).unwrap();
+        fs::create_dir_all(&paths.backup_dir).unwrap();
+        fs::write(&paths.project_agents, "# Old").unwrap();
+        paths
+            .preferences
+            .global_prefs
+            .general
+            .require_confirmation_for_destructive = Some(true);
+
+        let mut set_rules = |confirm: serde_json::Value| {
+            crate::mcp::run_tool(
+                &mut paths,
+                "set_project_rules",
+                json!({"content": "# New", "confirm": confirm}),
+                &crate::mcp::Progress::default(),
+            )
+            .unwrap()
+        };
+        let token_in = |message: &str| {
+            let start = message.find("\"confirm\": \"").unwrap() + 12;
+            message[start..start + 16].to_string()
+        };
+
+        // Confirming up front is not enough
+        let refused = set_rules(json!(true));
+        assert!(refused.contains("requires confirmation"));
+        assert_eq!(
+            fs::read_to_string(dir.path().join("AGENTS.md")).unwrap(),
+            "# Old"
+        );
+
+        let token = token_in(&refused);
+        assert!(!set_rules(json!(token)).contains("requires confirmation"));
+        assert_eq!(
+            fs::read_to_string(dir.path().join("AGENTS.md")).unwrap(),
+            "# New"
+        );
+
+        // Each token confirms one call
+        let reused = set_rules(json!(token));
+        assert!(reused.contains("token is unknown, expired, already used"));
+        assert_ne!(token_in(&reused), token);
+    }
+
+    #[test]
+    fn test_sections_ignore_tags_shebangs_and_code_fences() {
+        use crate::mcp::{edit_markdown_section, list_markdown_sections};
+
+        let content =
+            "## Setup\n#tag\n```sh\n#!/bin/sh\n# Install\nmake\n```\nDone.\n\n## Next\nKeep.";
+
+        let sections: Vec<String> = list_markdown_sections(content)
+            .into_iter()
+            .map(|(heading, _)| heading)
+            .collect();
+        assert_eq!(sections, vec!["## Setup", "## Next"]);
+
+        // The whole section up to `## Next` goes, fenced comment included
+        let deleted = edit_markdown_section(content, "## Setup", "", "delete", false).unwrap();
+        assert_eq!(deleted, "## Next\nKeep.");
+        assert!(edit_markdown_section(content, "# Install", "", "delete", true).is_err());
+    }
+
+    #[test]
+    fn test_failed_oauth_refresh_keeps_token_and_carries_on() {
+        use crate::credentials::{CredentialManager, StoredToken};
+        use crate::preferences::{McpAuth, McpServerConfig};
+
+        let dir = tempdir().unwrap();
+        let mut paths = test_paths(dir.path());
+        fs::create_dir_all(&paths.config_dir).unwrap();
+        // Nothing listens on port 1, so the refresh fails straight away
+        let url = "http://127.0.0.1:1/mcp";
+        paths.preferences.global_prefs.mcp_servers.insert(
+            "unreachable".to_string(),
+            McpServerConfig::Http {
+                http_url: url.to_string(),
+                auth: McpAuth::OAuth {
+                    client_id: "client".to_string(),
+                    client_secret: None,
+                    auth_server_url: Some("http://127.0.0.1:1".to_string()),
+                    scopes: Vec::new(),
+                },
+                headers: Default::default(),
+                headers_override_auth: false,
+                disabled_tools: Vec::new(),
+                auto_allow: false,
+                description: None,
+                tags: Vec::new(),
+            },
+        );
+        let mut credentials = CredentialManager::new(&paths.config_dir);
+        credentials
+            .store_token(
+                url,
+                StoredToken {
+                    access_token: "old".to_string(),
+                    refresh_token: Some("refresh".to_string()),
+                    expires_at: Some(chrono::Utc::now() - chrono::Duration::hours(1)),
+                    token_type: "Bearer".to_string(),
+                    scopes: vec![],
+                },
+            )
+            .unwrap();
+
+        assert!(paths.refresh_expired_oauth_tokens().is_empty());
+        let mut credentials = CredentialManager::new(&paths.config_dir);
+        credentials.load().unwrap();
+        assert_eq!(credentials.get_token(url).unwrap().access_token, "old");
+        // The lock was never left held
+        assert!(crate::lock::acquire(&paths.config_dir).is_ok());
+    }
 }
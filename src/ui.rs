@@ -1,7 +1,9 @@
-use crate::app::{ActiveTab, App, AppMode, Focus, McpAuthType, McpFieldFocus, PrefEditorFocus};
+use crate::app::{
+    ActiveTab, App, AppMode, DashboardLayout, Focus, McpAuthType, McpFieldFocus, PrefEditorFocus,
+};
 use crate::config::{AgentStatus, SyncState, SyncStrategy};
 use crate::credentials::TokenStatus;
-use crate::preferences::McpAuth;
+use crate::preferences::{GeneralSetting, McpAuth};
 use ratatui::{
     Frame,
     layout::{Constraint, Direction, Layout, Rect},
@@ -9,6 +11,7 @@ use ratatui::{
     text::{Line, Span},
     widgets::{Block, Borders, Clear, Paragraph, Row, Table, Tabs, Wrap},
 };
+use std::collections::HashMap;
 use std::sync::LazyLock;
 use syntect::easy::HighlightLines;
 use syntect::highlighting::ThemeSet;
@@ -17,6 +20,34 @@ use syntect::parsing::SyntaxSet;
 static SYNTAX_SET: LazyLock<SyntaxSet> = LazyLock::new(SyntaxSet::load_defaults_newlines);
 static THEME_SET: LazyLock<ThemeSet> = LazyLock::new(ThemeSet::load_defaults);
 
+/// Below this width the dashboard shows one rule pane at a time and drops the hints line.
+const COMPACT_WIDTH: u16 = 100;
+
+fn format_header_details(
+    details: &mut Vec<Line>,
+    headers: &HashMap<String, String>,
+    override_auth: bool,
+) {
+    if headers.is_empty() {
+        return;
+    }
+    let mut names: Vec<&str> = headers.keys().map(|k| k.as_str()).collect();
+    names.sort_unstable();
+
+    details.push(Line::from(""));
+    let mut spans = vec![
+        Span::styled("Headers: ", Style::default().add_modifier(Modifier::BOLD)),
+        Span::raw(names.join(", ")),
+    ];
+    if override_auth {
+        spans.push(Span::styled(
+            " (overrides auth)",
+            Style::default().fg(Color::Yellow),
+        ));
+    }
+    details.push(Line::from(spans));
+}
+
 fn format_auth_details<'a>(details: &mut Vec<Line<'a>>, auth: &'a McpAuth) {
     match auth {
         McpAuth::None => {}
@@ -27,6 +58,13 @@ fn format_auth_details<'a>(details: &mut Vec<Line<'a>>, auth: &'a McpAuth) {
                 Span::styled("Bearer token", Style::default().fg(Color::Green)),
             ]));
         }
+        McpAuth::BearerRef { .. } => {
+            details.push(Line::from(""));
+            details.push(Line::from(vec![
+                Span::styled("Auth: ", Style::default().add_modifier(Modifier::BOLD)),
+                Span::styled("Bearer token (stored)", Style::default().fg(Color::Green)),
+            ]));
+        }
         McpAuth::OAuth {
             client_id, scopes, ..
         } => {
@@ -111,10 +149,56 @@ fn format_oauth_status(details: &mut Vec<Line<'_>>, app: &App, url: &str, auth:
     }
 }
 
-fn highlight_markdown(content: &str) -> Vec<Line<'_>> {
+/// Compact relative time used by the status log and the agent table.
+fn format_ago(secs: u64) -> String {
+    if secs < 60 {
+        format!("{}s ago", secs)
+    } else if secs < 3600 {
+        format!("{}m ago", secs / 60)
+    } else if secs < 86400 {
+        format!("{}h ago", secs / 3600)
+    } else {
+        format!("{}d ago", secs / 86400)
+    }
+}
+
+/// Wall-clock time of a log entry; the date is included once it is no longer today.
+pub fn format_clock(
+    time: chrono::DateTime<chrono::Local>,
+    now: chrono::DateTime<chrono::Local>,
+) -> String {
+    if time.date_naive() == now.date_naive() {
+        time.format("%H:%M:%S").to_string()
+    } else {
+        time.format("%Y-%m-%d %H:%M:%S").to_string()
+    }
+}
+
+/// Absolute and relative time columns for a line of the error/status log.
+fn log_time_spans(time: chrono::DateTime<chrono::Local>) -> [Span<'static>; 2] {
+    let now = chrono::Local::now();
+    let elapsed = (now - time).num_seconds().max(0) as u64;
+    [
+        Span::styled(
+            format!("{} ", format_clock(time, now)),
+            Style::default().fg(Color::Gray),
+        ),
+        Span::styled(
+            format!("[{:>7}] ", format_ago(elapsed)),
+            Style::default().fg(Color::DarkGray),
+        ),
+    ]
+}
+
+fn highlight_markdown(content: &str) -> Vec<Line<'static>> {
+    highlight(content, "md")
+}
+
+/// Syntax-highlights `content` as the language of files with `extension`.
+fn highlight(content: &str, extension: &str) -> Vec<Line<'static>> {
     let ps = &SYNTAX_SET;
     let syntax = ps
-        .find_syntax_by_extension("md")
+        .find_syntax_by_extension(extension)
         .unwrap_or_else(|| ps.find_syntax_plain_text());
     let theme = &THEME_SET.themes["base16-ocean.dark"];
     let mut highlighter = HighlightLines::new(syntax, theme);
@@ -156,10 +240,17 @@ pub fn render(f: &mut Frame, app: &App) {
             return;
         }
         AppMode::Search => {
-            if app.active_tab == ActiveTab::Dashboard {
-                render_main(f, app);
-                render_search_dialog(f, app);
+            match app.active_tab {
+                ActiveTab::Dashboard => render_main(f, app),
+                ActiveTab::McpServers => render_mcp_servers(f, app),
+                ActiveTab::Preferences => return,
             }
+            render_search_dialog(f, app);
+            return;
+        }
+        AppMode::SectionOutline => {
+            render_main(f, app);
+            render_section_outline(f, app);
             return;
         }
         AppMode::ConfirmSync | AppMode::ConfirmSyncAll | AppMode::ConfirmDeleteMcp | AppMode::ConfirmAutoSync => {
@@ -190,12 +281,11 @@ pub fn render(f: &mut Frame, app: &App) {
 }
 
 fn render_tabs(f: &mut Frame, app: &App, area: Rect) {
-    let titles = vec!["[1] Dashboard", "[2] Preferences", "[3] MCP Servers"];
-    let index = match app.active_tab {
-        ActiveTab::Dashboard => 0,
-        ActiveTab::Preferences => 1,
-        ActiveTab::McpServers => 2,
-    };
+    let titles = crate::app::tab_titles(app.prefs_dirty);
+    let index = ActiveTab::ALL
+        .iter()
+        .position(|tab| *tab == app.active_tab)
+        .unwrap_or(0);
 
     let tabs = Tabs::new(titles)
         .select(index)
@@ -208,10 +298,13 @@ fn render_tabs(f: &mut Frame, app: &App, area: Rect) {
         )
         .divider(Span::raw("|"));
 
+    app.tab_area.set(area);
     f.render_widget(tabs, area);
 }
 
 fn render_main(f: &mut Frame, app: &App) {
+    let compact = f.area().width < COMPACT_WIDTH;
+
     let chunks = Layout::default()
         .direction(Direction::Vertical)
         .constraints([
@@ -220,7 +313,7 @@ fn render_main(f: &mut Frame, app: &App) {
             Constraint::Min(10),
             Constraint::Length(6),
             Constraint::Length(1),
-            Constraint::Length(1),
+            Constraint::Length(if compact { 0 } else { 1 }),
         ])
         .split(f.area());
 
@@ -274,6 +367,27 @@ fn render_main(f: &mut Frame, app: &App) {
                 .add_modifier(Modifier::BOLD),
         ));
     }
+    if !app.paths.has_mooagent_mcp() {
+        title_spans.push(Span::raw(" "));
+        title_spans.push(Span::styled(
+            "[mooagent MCP: not bootstrapped (press I)]",
+            Style::default().fg(Color::DarkGray),
+        ));
+    }
+    if !app.oversized_rules.is_empty() {
+        title_spans.push(Span::raw(" "));
+        title_spans.push(Span::styled(
+            format!("[⚠ {}]", app.oversized_rules.join("; ")),
+            Style::default().fg(Color::Yellow),
+        ));
+    }
+    if !app.target_conflicts.is_empty() {
+        title_spans.push(Span::raw(" "));
+        title_spans.push(Span::styled(
+            format!("[✗ {}]", app.target_conflicts.join("; ")),
+            Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+        ));
+    }
     if app.show_error_log {
         title_spans.push(Span::raw(" "));
         title_spans.push(Span::styled(
@@ -308,10 +422,20 @@ fn render_main(f: &mut Frame, app: &App) {
     );
     f.render_widget(header, chunks[1]);
 
-    let workspace_chunks = Layout::default()
-        .direction(Direction::Horizontal)
-        .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
-        .split(chunks[2]);
+    // In compact mode only the focused rule pane is shown, full width
+    let (global_area, project_area) = if compact {
+        if app.focus == Focus::Global {
+            (Some(chunks[2]), None)
+        } else {
+            (None, Some(chunks[2]))
+        }
+    } else {
+        let workspace_chunks = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+            .split(chunks[2]);
+        (Some(workspace_chunks[0]), Some(workspace_chunks[1]))
+    };
 
     let global_lines: Vec<Line> = highlight_markdown(&app.global_content)
         .into_iter()
@@ -336,7 +460,9 @@ fn render_main(f: &mut Frame, app: &App) {
                 }),
         )
         .wrap(Wrap { trim: true });
-    f.render_widget(global_rules, workspace_chunks[0]);
+    if let Some(area) = global_area {
+        f.render_widget(global_rules, area);
+    }
 
     let project_lines: Vec<Line> = highlight_markdown(&app.project_content)
         .into_iter()
@@ -361,7 +487,15 @@ fn render_main(f: &mut Frame, app: &App) {
                 }),
         )
         .wrap(Wrap { trim: true });
-    f.render_widget(project_rules, workspace_chunks[1]);
+    if let Some(area) = project_area {
+        f.render_widget(project_rules, area);
+    }
+
+    app.dashboard_layout.set(DashboardLayout {
+        agent_table: chunks[3],
+        global_pane: global_area.unwrap_or_default(),
+        project_pane: project_area.unwrap_or_default(),
+    });
 
     let visible_agents = app.get_visible_agents();
     let rows: Vec<Row> = visible_agents
@@ -374,6 +508,7 @@ fn render_main(f: &mut Frame, app: &App) {
                 .unwrap_or(0);
 
             let strategy_text = match agent.strategy {
+                _ if agent.has_broken_link() => "Broken",
                 SyncStrategy::Merge => "Merge",
                 SyncStrategy::Symlink => "Symlink",
             };
@@ -403,6 +538,13 @@ fn render_main(f: &mut Frame, app: &App) {
                 agent.sync_status.preferences.symbol().to_string(),
                 agent.sync_status.mcp_servers.symbol().to_string(),
                 strategy_text.to_string(),
+                agent
+                    .last_synced
+                    .map(|t| {
+                        let elapsed = (chrono::Local::now() - t).num_seconds().max(0);
+                        format_ago(elapsed as u64)
+                    })
+                    .unwrap_or_else(|| "never".to_string()),
             ])
             .style(row_style)
         })
@@ -464,22 +606,32 @@ fn render_main(f: &mut Frame, app: &App) {
                 Constraint::Length(6),
                 Constraint::Length(5),
                 Constraint::Length(8),
+                Constraint::Length(8),
             ],
         )
         .header(
-            Row::new(vec!["   Agent", "Rules", "Global", "Prefs", "MCP", "Strategy"])
-                .style(Style::default().add_modifier(Modifier::BOLD)),
+            Row::new(vec![
+                "   Agent", "Rules", "Global", "Prefs", "MCP", "Strategy", "Synced",
+            ])
+            .style(Style::default().add_modifier(Modifier::BOLD)),
         )
-        .block(
-            Block::default()
+        .block({
+            let block = Block::default()
                 .borders(Borders::ALL)
                 .title(table_title)
                 .border_style(if app.focus == Focus::Agents {
                     Style::default().fg(Color::Yellow)
                 } else {
                     Style::default()
-                }),
-        );
+                });
+            match app.default_agents_note {
+                Some(note) => block.title_bottom(Span::styled(
+                    format!(" {} ", note),
+                    Style::default().fg(Color::DarkGray),
+                )),
+                None => block,
+            }
+        });
         f.render_widget(table, chunks[3]);
     }
 
@@ -495,6 +647,10 @@ fn render_main(f: &mut Frame, app: &App) {
         );
     }
 
+    if compact {
+        return;
+    }
+
     let auto_sync_indicator = if app.auto_sync { " [AUTO-SYNC ON]" } else { "" };
     let search_indicator = if !app.search_query.is_empty() {
         format!(" [SEARCH: {}]", app.search_query)
@@ -535,11 +691,31 @@ fn render_main(f: &mut Frame, app: &App) {
     f.render_widget(Paragraph::new(hints), chunks[5]);
 }
 
+/// Spans for a single-line text input with a block cursor at character `cursor`, scrolled
+/// horizontally so the cursor stays within `width` columns.
+fn input_spans(text: &str, cursor: usize, width: usize, style: Style) -> Vec<Span<'static>> {
+    let chars: Vec<char> = text.chars().collect();
+    let width = width.max(1);
+    let cursor = cursor.min(chars.len());
+    let start = (cursor + 1).saturating_sub(width);
+    let end = chars.len().min(start + width);
+
+    let before: String = chars[start..cursor].iter().collect();
+    let under = chars.get(cursor).map_or(" ".to_string(), |c| c.to_string());
+    let after: String = chars[(cursor + 1).min(end)..end].iter().collect();
+
+    vec![
+        Span::styled(before, style),
+        Span::styled(under, style.add_modifier(Modifier::REVERSED)),
+        Span::styled(after, style),
+    ]
+}
+
 fn render_search_dialog(f: &mut Frame, app: &App) {
     let area = f.area();
 
     let popup_width = 60;
-    let popup_height = 5;
+    let popup_height = 6;
     let x = (area.width.saturating_sub(popup_width)) / 2;
     let y = (area.height.saturating_sub(popup_height)) / 2;
 
@@ -550,16 +726,28 @@ fn render_search_dialog(f: &mut Frame, app: &App) {
         height: popup_height,
     };
 
+    let (query, cursor, title) = if app.active_tab == ActiveTab::McpServers {
+        (
+            &app.mcp_editor_state.filter,
+            app.mcp_editor_state.filter_cursor,
+            "Filter MCP Servers (#tag for an exact tag)",
+        )
+    } else {
+        (&app.search_query, app.search_cursor, "Search Agents")
+    };
+
     let text = vec![
         Line::from(""),
-        Line::from(vec![
-            Span::raw("Search: "),
-            Span::styled(
-                &app.search_query,
-                Style::default().add_modifier(Modifier::BOLD),
-            ),
-            Span::styled("_", Style::default().add_modifier(Modifier::SLOW_BLINK)),
-        ]),
+        Line::from(
+            std::iter::once(Span::raw("Search: "))
+                .chain(input_spans(
+                    query,
+                    cursor,
+                    popup_width as usize - 2 - "Search: ".len(),
+                    Style::default().add_modifier(Modifier::BOLD),
+                ))
+                .collect::<Vec<_>>(),
+        ),
         Line::from(""),
         Line::from("[Esc] Cancel | [Enter] Apply | [Bksp] Del"),
     ];
@@ -567,7 +755,70 @@ fn render_search_dialog(f: &mut Frame, app: &App) {
     let dialog = Paragraph::new(text).block(
         Block::default()
             .borders(Borders::ALL)
-            .title("Search Agents")
+            .title(title)
+            .style(Style::default().bg(Color::Black)),
+    );
+
+    f.render_widget(Clear, popup_area);
+    f.render_widget(dialog, popup_area);
+}
+
+fn render_section_outline(f: &mut Frame, app: &App) {
+    let area = f.area();
+    let sections = app.outline_sections();
+
+    let popup_width = 70.min(area.width);
+    let popup_height = (sections.len() as u16 + 3)
+        .min(area.height.saturating_sub(4))
+        .max(5);
+    let popup_area = Rect {
+        x: (area.width.saturating_sub(popup_width)) / 2,
+        y: (area.height.saturating_sub(popup_height)) / 2,
+        width: popup_width,
+        height: popup_height,
+    };
+
+    // Keep the selection in view: borders and the hint line take 3 rows
+    let visible = popup_height.saturating_sub(3) as usize;
+    let offset = (app.outline_selected + 1).saturating_sub(visible);
+
+    let mut lines: Vec<Line> = sections
+        .iter()
+        .enumerate()
+        .skip(offset)
+        .take(visible)
+        .map(|(idx, (heading, line_num))| {
+            let level = heading.chars().take_while(|&c| c == '#').count();
+            let text = format!(
+                "{}{}  (line {})",
+                "  ".repeat(level.saturating_sub(1)),
+                heading.trim_start_matches('#').trim(),
+                line_num
+            );
+            if idx == app.outline_selected {
+                Line::from(Span::styled(
+                    text,
+                    Style::default().fg(Color::Black).bg(Color::Cyan),
+                ))
+            } else {
+                Line::from(text)
+            }
+        })
+        .collect();
+    lines.push(Line::from(Span::styled(
+        "[j/k] Move | [Enter] Jump | [Esc] Close",
+        Style::default().fg(Color::DarkGray),
+    )));
+
+    let title = if app.focus == Focus::Global {
+        "Outline: Global Rules"
+    } else {
+        "Outline: Project Rules"
+    };
+    let dialog = Paragraph::new(lines).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(title)
             .style(Style::default().bg(Color::Black)),
     );
 
@@ -596,22 +847,47 @@ fn render_error_log(f: &mut Frame, app: &App) {
     log_lines.push(Line::from(""));
 
     for (msg, time) in app.status_log.iter().rev().take(50) {
-        let elapsed = time.elapsed().as_secs();
-        let time_str = if elapsed < 60 {
-            format!("{}s ago", elapsed)
-        } else if elapsed < 3600 {
-            format!("{}m ago", elapsed / 60)
+        let mut spans = log_time_spans(*time).to_vec();
+        spans.push(Span::raw(msg));
+        log_lines.push(Line::from(spans));
+    }
+
+    log_lines.push(Line::from(""));
+    log_lines.push(Line::from(vec![
+        Span::styled(
+            "MCP Tool Calls",
+            Style::default()
+                .add_modifier(Modifier::BOLD)
+                .fg(Color::Cyan),
+        ),
+        Span::raw(format!(" ({})", app.paths.audit_log_file.display())),
+    ]));
+    log_lines.push(Line::from(""));
+
+    if app.audit_entries.is_empty() {
+        log_lines.push(Line::from(Span::styled(
+            "No tool calls recorded yet",
+            Style::default().fg(Color::DarkGray),
+        )));
+    }
+
+    for entry in app.audit_entries.iter().rev() {
+        let (marker, color) = if entry.ok {
+            ("✓", Color::Green)
         } else {
-            format!("{}h ago", elapsed / 3600)
+            ("✗", Color::Red)
         };
 
-        log_lines.push(Line::from(vec![
+        let mut spans = log_time_spans(entry.timestamp).to_vec();
+        spans.extend([
+            Span::styled(format!("{} ", marker), Style::default().fg(color)),
             Span::styled(
-                format!("[{}] ", time_str),
-                Style::default().fg(Color::DarkGray),
+                entry.tool.clone(),
+                Style::default().add_modifier(Modifier::BOLD),
             ),
-            Span::raw(msg),
-        ]));
+            Span::raw(format!(" {}", entry.arguments)),
+        ]);
+        log_lines.push(Line::from(spans));
     }
 
     let log = Paragraph::new(log_lines)
@@ -626,15 +902,121 @@ fn render_error_log(f: &mut Frame, app: &App) {
 
     let hint = Line::from(vec![
         Span::styled("[v/Esc]", Style::default().fg(Color::Cyan)),
-        Span::raw(" Close Log"),
+        Span::raw(" Close Log  "),
+        Span::styled("[e]", Style::default().fg(Color::Cyan)),
+        Span::raw(" Export Status Log"),
     ]);
     f.render_widget(Paragraph::new(hint), chunks[1]);
 }
 
+/// Keybindings by section; drives both the help overlay and `mooagent --keys`.
+pub const KEYBINDINGS: &[(&str, &[(&str, &str)])] = &[
+    (
+        "Navigation",
+        &[
+            ("Tab / Ctrl+w", "Cycle focus between panes"),
+            ("h / l or ← / →", "Move focus Agents ↔ Global ↔ Project"),
+            ("j / k or ↓ / ↑", "Navigate/Scroll focused pane"),
+            ("gg / G", "Jump to Top / Bottom of focused pane"),
+            ("Ctrl+u / Ctrl+d", "Half-page Up / Down focused pane"),
+            ("PgUp / PgDn", "Page Up / Down rules pane"),
+            ("Home / End", "Jump to Top / Bottom of rules pane"),
+            ("{ / }", "Jump to previous / next heading in rules pane"),
+            ("o", "Outline of headings in focused rules pane"),
+            ("Mouse Scroll", "Scroll focused pane"),
+            (
+                "Mouse Click",
+                "Select agent row / focus rules pane / switch tab",
+            ),
+        ],
+    ),
+    (
+        "Actions",
+        &[
+            ("s", "Sync all agents (with confirmation)"),
+            ("Enter", "Sync selected agent (with confirmation)"),
+            ("d", "View diff for selected agent"),
+            ("b", "View / restore backups for selected agent"),
+            ("B", "Back up global and project rules now"),
+            (
+                "I",
+                "Install (bootstrap) mooagent MCP server into all agents",
+            ),
+            ("Ctrl+g", "Edit global rules (syncs to all agents)"),
+            ("Ctrl+e", "Edit project rules (AGENTS.md)"),
+            ("Ctrl+c", "Edit config file (.mooagent.toml)"),
+            ("a", "Toggle auto-sync mode"),
+            ("/", "Search agents by name/path"),
+            ("v", "Toggle error/status log"),
+            ("e (in log)", "Export the status log to a file"),
+        ],
+    ),
+    (
+        "Preferences (Tab 2)",
+        &[
+            ("Tab", "Cycle panels"),
+            ("Space / Enter", "Toggle selected item"),
+            ("a", "Add individual tool"),
+            ("h / l", "Switch global / per-agent general settings"),
+            ("s", "Sync preferences to all agents"),
+            ("w", "Save preferences (tab shows * while unsaved)"),
+        ],
+    ),
+    (
+        "MCP Servers (Tab 3)",
+        &[
+            ("a", "Add new MCP server"),
+            ("e / Enter", "Edit selected server"),
+            ("d", "Delete selected server"),
+            ("o", "OAuth login/logout (for OAuth servers)"),
+            ("m", "Add default MCP servers (magic setup)"),
+            ("p", "Preview the generated agent config for the server"),
+            ("/", "Filter servers by name, description or #tag"),
+            ("A", "Disable / re-enable all listed servers in project"),
+            ("R", "Disable / re-enable listed remote servers"),
+            ("s", "Sync preferences to all agents"),
+            ("r", "Re-point stale mooagent MCP to current binary"),
+        ],
+    ),
+    (
+        "MCP Server Editor",
+        &[
+            ("Tab", "Next field"),
+            ("← / → / Home / End", "Move the cursor in the focused field"),
+            ("Backspace / Delete", "Delete before / under the cursor"),
+            ("Enter", "Save server"),
+            ("Esc", "Cancel"),
+        ],
+    ),
+    (
+        "Other",
+        &[("?", "Show this help"), ("q or Esc", "Quit / Close dialog")],
+    ),
+];
+
+/// The keybinding table as markdown, one table per section.
+pub fn keybindings_markdown() -> String {
+    let mut out = String::from("# mooagent keybindings\n");
+    for (section, bindings) in KEYBINDINGS {
+        out.push_str(&format!(
+            "\n## {}\n\n| Key | Action |\n| --- | --- |\n",
+            section
+        ));
+        for (keys, action) in *bindings {
+            out.push_str(&format!(
+                "| `{}` | {} |\n",
+                keys.replace('|', "\\|"),
+                action.replace('|', "\\|")
+            ));
+        }
+    }
+    out
+}
+
 fn render_help(f: &mut Frame, app: &App) {
     let area = f.area();
 
-    let help_text = vec![
+    let mut help_text = vec![
         Line::from(vec![Span::styled(
             "MooAgent - Help",
             Style::default()
@@ -642,50 +1024,18 @@ fn render_help(f: &mut Frame, app: &App) {
                 .fg(Color::Cyan),
         )]),
         Line::from(""),
-        Line::from(vec![Span::styled(
-            "Navigation:",
-            Style::default().add_modifier(Modifier::BOLD),
-        )]),
-        Line::from("  Tab / Ctrl+w      - Cycle focus between panes"),
-        Line::from("  h / l or ← / →    - Move focus between Global and Project rules"),
-        Line::from("  j / k or ↓ / ↑    - Navigate/Scroll focused pane"),
-        Line::from("  gg / G            - Jump to Top / Bottom of focused pane"),
-        Line::from("  Ctrl+u / Ctrl+d   - Half-page Up / Down focused pane"),
-        Line::from("  Mouse Scroll      - Scroll focused pane"),
-        Line::from(""),
-        Line::from(vec![Span::styled(
-            "Actions:",
-            Style::default().add_modifier(Modifier::BOLD),
-        )]),
-        Line::from("  s                 - Sync all agents (with confirmation)"),
-        Line::from("  Enter             - Sync selected agent (with confirmation)"),
-        Line::from("  d                 - View diff for selected agent"),
-        Line::from("  b                 - View backups for selected agent"),
-        Line::from("  Ctrl+g            - Edit global rules (syncs to all agents)"),
-        Line::from("  Ctrl+e            - Edit project rules (AGENTS.md)"),
-        Line::from("  Ctrl+c            - Edit config file (.mooagent.toml)"),
-        Line::from("  a                 - Toggle auto-sync mode"),
-        Line::from("  /                 - Search agents by name/path"),
-        Line::from("  v                 - Toggle error/status log"),
-        Line::from(""),
-        Line::from(vec![Span::styled(
-            "MCP Servers (Tab 3):",
-            Style::default().add_modifier(Modifier::BOLD),
-        )]),
-        Line::from("  a                 - Add new MCP server"),
-        Line::from("  e / Enter         - Edit selected server"),
-        Line::from("  d                 - Delete selected server"),
-        Line::from("  o                 - OAuth login/logout (for OAuth servers)"),
-        Line::from("  m                 - Add default MCP servers (magic setup)"),
-        Line::from("  s                 - Sync preferences to all agents"),
-        Line::from(""),
-        Line::from(vec![Span::styled(
-            "Other:",
+    ];
+    for (section, bindings) in KEYBINDINGS {
+        help_text.push(Line::from(vec![Span::styled(
+            format!("{}:", section),
             Style::default().add_modifier(Modifier::BOLD),
-        )]),
-        Line::from("  ?                 - Show this help"),
-        Line::from("  q or Esc          - Quit / Close dialog"),
-        Line::from(""),
+        )]));
+        for (keys, action) in *bindings {
+            help_text.push(Line::from(format!("  {:<18}- {}", keys, action)));
+        }
+        help_text.push(Line::from(""));
+    }
+    help_text.extend([
         Line::from(vec![Span::styled(
             "Status Indicators:",
             Style::default().add_modifier(Modifier::BOLD),
@@ -748,7 +1098,7 @@ fn render_help(f: &mut Frame, app: &App) {
         Line::from("  • Logging to ~/.local/share/mooagent/mooagent.log"),
         Line::from(""),
         Line::from("Press any key to close..."),
-    ];
+    ]);
 
     let help = Paragraph::new(help_text)
         .block(
@@ -778,6 +1128,12 @@ fn render_confirm_dialog(f: &mut Frame, app: &App) {
         height: popup_height,
     };
 
+    if matches!(app.mode, AppMode::ConfirmSync | AppMode::ConfirmSyncAll)
+        && !app.hand_edits.is_empty()
+    {
+        return render_hand_edit_dialog(f, app);
+    }
+
     let (message, detail) = match app.mode {
         AppMode::ConfirmSyncAll => ("Sync all agents?", "This will backup and overwrite existing files."),
         AppMode::ConfirmSync => {
@@ -794,7 +1150,12 @@ fn render_confirm_dialog(f: &mut Frame, app: &App) {
                 .get(app.mcp_editor_state.selected_server_idx)
                 .map(|s| s.as_str())
                 .unwrap_or("unknown");
-            return render_delete_confirm_dialog(f, popup_area, server_name);
+            return render_delete_confirm_dialog(
+                f,
+                popup_area,
+                server_name,
+                app.selected_mcp_has_stored_credentials(),
+            );
         }
         AppMode::ConfirmAutoSync => (
             "Enable auto-sync?",
@@ -833,7 +1194,81 @@ fn render_confirm_dialog(f: &mut Frame, app: &App) {
     f.render_widget(dialog, popup_area);
 }
 
-fn render_delete_confirm_dialog(f: &mut Frame, popup_area: ratatui::layout::Rect, server_name: &str) {
+/// Sync confirmation for agents whose files were edited by hand, showing what would be
+/// overwritten and offering to pull the edits into AGENTS.md instead.
+fn render_hand_edit_dialog(f: &mut Frame, app: &App) {
+    let area = f.area();
+    let popup_area = Rect {
+        x: area.width / 10,
+        y: area.height / 10,
+        width: area.width * 8 / 10,
+        height: area.height * 8 / 10,
+    };
+
+    let names: Vec<&str> = app
+        .hand_edits
+        .iter()
+        .filter_map(|(idx, _)| app.agents.get(*idx).map(|a| a.name.as_str()))
+        .collect();
+    let mut lines = vec![
+        Line::from(Span::styled(
+            format!("⚠ Edited by hand since the last sync: {}", names.join(", ")),
+            Style::default()
+                .fg(Color::Yellow)
+                .add_modifier(Modifier::BOLD),
+        )),
+        Line::from("Syncing backs these files up and overwrites them with AGENTS.md."),
+        Line::from(""),
+        Line::from(vec![
+            Span::styled("[y]", Style::default().fg(Color::Green)),
+            Span::raw(" Sync anyway   "),
+            Span::styled("[p]", Style::default().fg(Color::Cyan)),
+            Span::raw(format!(" Pull {}'s edits into AGENTS.md   ", names[0])),
+            Span::styled("[n/Esc]", Style::default().fg(Color::Red)),
+            Span::raw(" Cancel"),
+        ]),
+        Line::from(""),
+    ];
+
+    for (idx, diff) in &app.hand_edits {
+        if let Some(agent) = app.agents.get(*idx) {
+            lines.push(Line::from(Span::styled(
+                agent.name.clone(),
+                Style::default().add_modifier(Modifier::BOLD),
+            )));
+        }
+        lines.extend(diff_lines(diff));
+        lines.push(Line::from(""));
+    }
+
+    let dialog = Paragraph::new(lines)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(format!(
+                    "Overwrite hand edits? [Scroll: j/k] [Line: {}]",
+                    app.detail_scroll
+                ))
+                .style(Style::default().bg(Color::Black)),
+        )
+        .wrap(Wrap { trim: false })
+        .scroll((app.detail_scroll as u16, 0));
+
+    f.render_widget(Clear, popup_area);
+    f.render_widget(dialog, popup_area);
+}
+
+fn render_delete_confirm_dialog(
+    f: &mut Frame,
+    popup_area: ratatui::layout::Rect,
+    server_name: &str,
+    has_credentials: bool,
+) {
+    let warning = if has_credentials {
+        "Its stored token will be removed too. This cannot be undone."
+    } else {
+        "This action cannot be undone."
+    };
     let text = vec![
         Line::from(""),
         Line::from(vec![Span::styled(
@@ -841,10 +1276,7 @@ fn render_delete_confirm_dialog(f: &mut Frame, popup_area: ratatui::layout::Rect
             Style::default().add_modifier(Modifier::BOLD),
         )]),
         Line::from(""),
-        Line::from(vec![Span::styled(
-            "This action cannot be undone.",
-            Style::default().fg(Color::Red),
-        )]),
+        Line::from(vec![Span::styled(warning, Style::default().fg(Color::Red))]),
         Line::from(""),
         Line::from(vec![
             Span::styled("[y]", Style::default().fg(Color::Green)),
@@ -867,15 +1299,45 @@ fn render_delete_confirm_dialog(f: &mut Frame, popup_area: ratatui::layout::Rect
     f.render_widget(dialog, popup_area);
 }
 
+/// Colors a unified diff: additions green, removals red, hunk headers cyan.
+fn diff_lines(diff: &str) -> Vec<Line<'static>> {
+    diff.lines()
+        .map(|line| {
+            let style = match line.chars().next() {
+                Some('+') => Style::default().fg(Color::Green),
+                Some('-') => Style::default().fg(Color::Red),
+                Some('@') => Style::default().fg(Color::Cyan),
+                _ => Style::default(),
+            };
+            Line::from(Span::styled(line.to_string(), style))
+        })
+        .collect()
+}
+
 fn render_diff(f: &mut Frame, app: &App) {
     let area = f.area();
 
     let diff_content = if app.agents.is_empty() {
-        "No agents available".to_string()
+        vec![Line::from("No agents available")]
     } else {
-        app.paths
-            .get_diff(app.selected_agent)
-            .unwrap_or_else(|| "No diff available (agent is in sync or missing)".to_string())
+        let mut lines = Vec::new();
+        if let Some(agent) = app.agents.get(app.selected_agent)
+            && let Some(link) = &agent.link
+        {
+            lines.push(Line::from(format!(
+                "{} {}",
+                agent.target_path.display(),
+                link.describe()
+            )));
+            lines.push(Line::from(""));
+        }
+        match app.paths.get_diff(app.selected_agent) {
+            Some(diff) => lines.extend(diff_lines(&diff)),
+            None => lines.push(Line::from(
+                "No diff available (agent is in sync or missing)",
+            )),
+        }
+        lines
     };
 
     let diff = Paragraph::new(diff_content)
@@ -887,7 +1349,7 @@ fn render_diff(f: &mut Frame, app: &App) {
                     .unwrap_or("Unknown"),
                 app.detail_scroll
             )))
-        .wrap(Wrap { trim: true })
+        .wrap(Wrap { trim: false })
         .scroll((app.detail_scroll as u16, 0));
 
     f.render_widget(Clear, area);
@@ -932,38 +1394,61 @@ fn render_backups(f: &mut Frame, app: &App) {
                 .file_name()
                 .and_then(|n| n.to_str())
                 .unwrap_or("Unknown");
-            lines.push(Line::from(format!("  {}. {}", idx + 1, name)));
+            let (marker, style) = if idx == app.backup_selected {
+                (">>", Style::default().add_modifier(Modifier::REVERSED))
+            } else {
+                ("  ", Style::default())
+            };
+            lines.push(Line::from(Span::styled(
+                format!("{} {}. {}", marker, idx + 1, name),
+                style,
+            )));
         }
-        lines.push(Line::from(""));
-        lines.push(Line::from(
-            "Note: Restore functionality requires manual file operations",
-        ));
     }
 
     lines.push(Line::from(""));
-    lines.push(Line::from(vec![
+    let mut hint = vec![
         Span::styled("[Esc/q]", Style::default().fg(Color::Cyan)),
         Span::raw(" Close"),
-    ]));
+    ];
+    if !backups.is_empty() {
+        hint.extend([
+            Span::raw("  "),
+            Span::styled("[j/k]", Style::default().fg(Color::Cyan)),
+            Span::raw(" Select  "),
+            Span::styled("[r]", Style::default().fg(Color::Cyan)),
+            Span::raw(" Restore (backs up the current file first)"),
+        ]);
+    }
+    lines.push(Line::from(hint));
 
+    // Keep the highlighted entry on screen below the two header lines
+    let visible = area.height.saturating_sub(2) as usize;
+    let scroll = (app.backup_selected + 3).saturating_sub(visible);
     let backup_list = Paragraph::new(lines)
         .block(Block::default().borders(Borders::ALL).title(format!(
-            "Backup Files [Scroll: j/k] [Line: {}]",
-            app.detail_scroll
+            "Backup Files [{}/{}]",
+            (app.backup_selected + 1).min(backups.len()),
+            backups.len()
         )))
         .wrap(Wrap { trim: true })
-        .scroll((app.detail_scroll as u16, 0));
+        .scroll((scroll as u16, 0));
 
     f.render_widget(Clear, area);
     f.render_widget(backup_list, area);
 }
 
 fn render_preferences(f: &mut Frame, app: &App) {
+    let header_height = if app.preference_drift.is_empty() {
+        4
+    } else {
+        5
+    };
     let chunks = Layout::default()
         .direction(Direction::Vertical)
         .constraints([
             Constraint::Length(2),
-            Constraint::Length(4),
+            Constraint::Length(header_height),
             Constraint::Min(5),
             Constraint::Length(1),
             Constraint::Length(1),
@@ -972,7 +1457,7 @@ fn render_preferences(f: &mut Frame, app: &App) {
 
     render_tabs(f, app, chunks[0]);
 
-    let drift_status = if app.preference_drift {
+    let drift_status = if !app.preference_drift.is_empty() {
         Span::styled(
             " [DRIFT DETECTED - Sync Recommended]",
             Style::default()
@@ -983,7 +1468,7 @@ fn render_preferences(f: &mut Frame, app: &App) {
         Span::styled(" [Synced]", Style::default().fg(Color::Green))
     };
 
-    let header = Paragraph::new(vec![
+    let mut header_lines = vec![
         Line::from(vec![
             Span::styled(
                 "Preference Editor: ",
@@ -996,8 +1481,29 @@ fn render_preferences(f: &mut Frame, app: &App) {
             Span::styled("Path: ", Style::default().add_modifier(Modifier::BOLD)),
             Span::raw(app.paths.preferences.global_path.display().to_string()),
         ]),
-    ])
-    .block(
+    ];
+
+    if !app.preference_drift.is_empty() {
+        let details: Vec<String> = app
+            .preference_drift
+            .iter()
+            .map(|d| {
+                let file = d.path.file_name().and_then(|n| n.to_str()).unwrap_or("?");
+                format!("{} {} ({})", d.agent, file, d.reason())
+            })
+            .collect();
+        header_lines.push(Line::from(vec![
+            Span::styled(
+                "Out of sync: ",
+                Style::default()
+                    .fg(Color::Yellow)
+                    .add_modifier(Modifier::BOLD),
+            ),
+            Span::raw(details.join(", ")),
+        ]));
+    }
+
+    let header = Paragraph::new(header_lines).block(
         Block::default()
             .borders(Borders::ALL)
             .title("MooAgent Preferences"),
@@ -1007,9 +1513,10 @@ fn render_preferences(f: &mut Frame, app: &App) {
     let editor_chunks = Layout::default()
         .direction(Direction::Horizontal)
         .constraints([
-            Constraint::Percentage(33),
-            Constraint::Percentage(33),
-            Constraint::Percentage(33),
+            Constraint::Percentage(25),
+            Constraint::Percentage(25),
+            Constraint::Percentage(25),
+            Constraint::Percentage(25),
         ])
         .split(chunks[2]);
 
@@ -1019,6 +1526,8 @@ fn render_preferences(f: &mut Frame, app: &App) {
 
     render_general_panel(f, app, editor_chunks[2]);
 
+    render_mooagent_tools_panel(f, app, editor_chunks[3]);
+
     if let Some((msg, _)) = &app.status_message {
         f.render_widget(
             Paragraph::new(Span::styled(
@@ -1071,16 +1580,33 @@ fn render_add_tool_dialog(f: &mut Frame, app: &App) {
 
     let text = vec![
         Line::from(""),
+        Line::from(
+            std::iter::once(Span::raw("Tool Name: "))
+                .chain(input_spans(
+                    &app.new_tool_input,
+                    app.new_tool_cursor,
+                    popup_width as usize - 2 - "Tool Name: ".len(),
+                    Style::default().add_modifier(Modifier::BOLD),
+                ))
+                .collect::<Vec<_>>(),
+        ),
         Line::from(vec![
-            Span::raw("Tool Name: "),
+            Span::raw("Category:  "),
             Span::styled(
-                &app.new_tool_input,
+                app.pending_tool_category().label(),
                 Style::default().add_modifier(Modifier::BOLD),
             ),
-            Span::styled("_", Style::default().add_modifier(Modifier::SLOW_BLINK)),
+            Span::styled(
+                if app.new_tool_category.is_none() {
+                    " (guessed from name)"
+                } else {
+                    ""
+                },
+                Style::default().fg(Color::DarkGray),
+            ),
         ]),
         Line::from(""),
-        Line::from("[Esc] Cancel | [Enter] Add Tool"),
+        Line::from("[Esc] Cancel | [Tab] Category | [Enter] Add Tool"),
     ];
 
     let dialog = Paragraph::new(text).block(
@@ -1151,6 +1677,7 @@ fn render_tools_panel(f: &mut Frame, app: &App, area: ratatui::layout::Rect) {
     let mut lines = Vec::new();
     let mgr = &app.paths.preferences;
 
+    let index = crate::preferences::tool_preset_index();
     let mut section = "";
 
     for (idx, tool) in app
@@ -1159,13 +1686,7 @@ fn render_tools_panel(f: &mut Frame, app: &App, area: ratatui::layout::Rect) {
         .iter()
         .enumerate()
     {
-        let is_llm = crate::app::is_llm_tool(tool);
-        let current_section = if is_llm {
-            "LLM Specific"
-        } else {
-            "Regular Programs"
-        };
-
+        let (_, current_section) = crate::app::tool_section(tool, &index, &mgr.global_prefs);
         if current_section != section {
             lines.push(Line::from(vec![Span::styled(
                 format!("--- {} ---", current_section),
@@ -1188,10 +1709,15 @@ fn render_tools_panel(f: &mut Frame, app: &App, area: ratatui::layout::Rect) {
             Style::default().fg(Color::DarkGray)
         };
 
-        lines.push(Line::from(vec![Span::styled(
-            format!("{} {}", check, tool),
-            style,
-        )]));
+        let mut spans = vec![Span::styled(format!("{} {}", check, tool), style)];
+        // Tools shared between presets are listed once, under the first of them
+        if let Some(others) = index.get(tool.as_str()).filter(|p| p.len() > 1) {
+            spans.push(Span::styled(
+                format!(" (also {})", others[1..].join(", ")),
+                Style::default().fg(Color::DarkGray),
+            ));
+        }
+        lines.push(Line::from(spans));
     }
 
     f.render_widget(Paragraph::new(lines).block(block), area);
@@ -1199,9 +1725,13 @@ fn render_tools_panel(f: &mut Frame, app: &App, area: ratatui::layout::Rect) {
 
 fn render_general_panel(f: &mut Frame, app: &App, area: ratatui::layout::Rect) {
     let is_focused = app.pref_editor_state.focus == PrefEditorFocus::GeneralSettings;
+    let scope = app.pref_editor_state.general_scope.as_deref();
     let block = Block::default()
         .borders(Borders::ALL)
-        .title("General Settings")
+        .title(format!(
+            "General Settings: {} (h/l to switch)",
+            scope.unwrap_or("Global")
+        ))
         .border_style(if is_focused {
             Style::default().fg(Color::Yellow)
         } else {
@@ -1212,38 +1742,190 @@ fn render_general_panel(f: &mut Frame, app: &App, area: ratatui::layout::Rect) {
     let mgr = &app.paths.preferences;
     let general_prefs = &mgr.global_prefs.general;
 
-    let settings = [
-        (
-            "Auto-Accept Tools",
-            general_prefs.auto_accept_tools.unwrap_or(true),
-        ),
-        (
-            "Enable Logging",
-            general_prefs.enable_logging.unwrap_or(true),
-        ),
-        (
-            "Sandboxed Mode",
-            general_prefs.sandboxed_mode.unwrap_or(true),
-        ),
-    ];
+    if let Some(agent) = scope {
+        render_agent_general_overrides(f, app, agent, block, area);
+        return;
+    }
 
-    for (idx, (name, val)) in settings.iter().enumerate() {
-        let check = if *val { "[x]" } else { "[ ]" };
+    // Toggles that map onto agent settings name the agents honoring them; ones no
+    // synced agent supports are greyed out.
+    // Unset agent toggles ([-]) leave the agents' own settings alone.
+    type Toggle<'a> = (&'a str, Option<bool>, Option<Vec<String>>);
+    let mut settings: Vec<Toggle> = GeneralSetting::ALL
+        .iter()
+        .map(|setting| {
+            (
+                setting.label(),
+                setting.value(general_prefs),
+                Some(app.paths.general_setting_agents(*setting)),
+            )
+        })
+        .collect();
+    settings.push((
+        "Confirm Destructive MCP Tools",
+        Some(
+            general_prefs
+                .require_confirmation_for_destructive
+                .unwrap_or(false),
+        ),
+        None,
+    ));
+    settings.push((
+        "Back Up Before Append",
+        Some(general_prefs.backup_before_edit.unwrap_or(true)),
+        None,
+    ));
+
+    for (idx, (name, val, agents)) in settings.iter().enumerate() {
+        let check = match val {
+            Some(true) => "[x]",
+            Some(false) => "[ ]",
+            None => "[-]",
+        };
+        let unsupported = agents.as_ref().is_some_and(|a| a.is_empty());
         let style = if is_focused && idx == app.pref_editor_state.selected_general {
             Style::default().fg(Color::Black).bg(Color::Cyan)
-        } else if *val {
+        } else if unsupported || *val != Some(true) {
+            Style::default().fg(Color::DarkGray)
+        } else {
+            Style::default().fg(Color::Green)
+        };
+
+        let mut spans = vec![Span::styled(format!("{} {}", check, name), style)];
+        if let Some(agents) = agents {
+            let note = if agents.is_empty() {
+                " (no synced agent supports this)".to_string()
+            } else if val.is_none() {
+                format!(" (not set; {} keep their own)", agents.join(", "))
+            } else {
+                format!(" ({})", agents.join(", "))
+            };
+            spans.push(Span::styled(note, Style::default().fg(Color::DarkGray)));
+        }
+        lines.push(Line::from(spans));
+    }
+
+    f.render_widget(Paragraph::new(lines).block(block), area);
+}
+
+/// Per-agent overrides cycle inherit -> on -> off; inherited rows show the global value.
+fn render_agent_general_overrides(
+    f: &mut Frame,
+    app: &App,
+    agent: &str,
+    block: Block,
+    area: ratatui::layout::Rect,
+) {
+    let global = &app.paths.preferences.global_prefs;
+    let overrides = global
+        .agent_specific
+        .get(agent)
+        .and_then(|ap| ap.general.clone())
+        .unwrap_or_default();
+
+    let mut lines = Vec::new();
+    for (idx, setting) in GeneralSetting::ALL.iter().enumerate() {
+        let inherited = match setting.value(&global.general) {
+            Some(true) => "on",
+            Some(false) => "off",
+            None => "not set",
+        };
+        let supported = app
+            .paths
+            .general_setting_agents(*setting)
+            .iter()
+            .any(|a| a == agent);
+        let (check, note) = match setting.value(&overrides) {
+            Some(true) => ("[x]", String::new()),
+            Some(false) => ("[ ]", String::new()),
+            None => ("[-]", format!(" (inherits {})", inherited)),
+        };
+        let note = if supported {
+            note
+        } else {
+            format!("{} (not supported by {})", note, agent)
+        };
+
+        let style = if app.pref_editor_state.focus == PrefEditorFocus::GeneralSettings
+            && idx == app.pref_editor_state.selected_general
+        {
+            Style::default().fg(Color::Black).bg(Color::Cyan)
+        } else if !supported || check != "[x]" {
+            Style::default().fg(Color::DarkGray)
+        } else {
+            Style::default().fg(Color::Green)
+        };
+
+        lines.push(Line::from(vec![
+            Span::styled(format!("{} {}", check, setting.label()), style),
+            Span::styled(note, Style::default().fg(Color::DarkGray)),
+        ]));
+    }
+
+    f.render_widget(Paragraph::new(lines).block(block), area);
+}
+
+fn render_mooagent_tools_panel(f: &mut Frame, app: &App, area: ratatui::layout::Rect) {
+    let is_focused = app.pref_editor_state.focus == PrefEditorFocus::MooagentTools;
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title("mooagent MCP Tools")
+        .border_style(if is_focused {
+            Style::default().fg(Color::Yellow)
+        } else {
+            Style::default()
+        });
+
+    let Some(config) = app
+        .paths
+        .preferences
+        .global_prefs
+        .mcp_servers
+        .get("mooagent")
+    else {
+        let hint = Paragraph::new(vec![
+            Line::from("mooagent MCP is not configured."),
+            Line::from(""),
+            Line::from("Press I on the dashboard to add it."),
+        ])
+        .style(Style::default().fg(Color::DarkGray))
+        .wrap(Wrap { trim: true })
+        .block(block);
+        f.render_widget(hint, area);
+        return;
+    };
+
+    let disabled = config.disabled_tools();
+    let selected = app.pref_editor_state.selected_mooagent_tool;
+    let mut lines = Vec::new();
+
+    for (idx, tool) in app.pref_editor_state.mooagent_tool_list.iter().enumerate() {
+        let enabled = !disabled.contains(tool);
+        let check = if enabled { "[x]" } else { "[ ]" };
+        let style = if is_focused && idx == selected {
+            Style::default().fg(Color::Black).bg(Color::Cyan)
+        } else if enabled {
             Style::default().fg(Color::Green)
         } else {
             Style::default().fg(Color::DarkGray)
         };
 
         lines.push(Line::from(vec![Span::styled(
-            format!("{} {}", check, name),
+            format!("{} {}", check, tool),
             style,
         )]));
     }
 
-    f.render_widget(Paragraph::new(lines).block(block), area);
+    // The list is longer than most panes; keep the selection in view
+    let visible = area.height.saturating_sub(2) as usize;
+    let offset = selected.saturating_sub(visible.saturating_sub(1));
+
+    f.render_widget(
+        Paragraph::new(lines)
+            .block(block)
+            .scroll((offset as u16, 0)),
+        area,
+    );
 }
 
 fn render_mcp_servers(f: &mut Frame, app: &App) {
@@ -1260,14 +1942,27 @@ fn render_mcp_servers(f: &mut Frame, app: &App) {
 
     render_tabs(f, app, chunks[0]);
 
-    let header = Paragraph::new(vec![Line::from(vec![
-        Span::styled(
-            "Global MCP Servers: ",
-            Style::default().add_modifier(Modifier::BOLD),
-        ),
-        Span::raw("Define once, sync to all agents (Claude, Gemini, OpenCode)"),
-    ])])
-    .block(
+    let header_line = if let Some(command) = &app.stale_mooagent_command {
+        Line::from(vec![
+            Span::styled(
+                "⚠ Stale binary path: ",
+                Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+            ),
+            Span::styled(
+                format!("mooagent MCP points at {} (press r to re-point)", command),
+                Style::default().fg(Color::Yellow),
+            ),
+        ])
+    } else {
+        Line::from(vec![
+            Span::styled(
+                "Global MCP Servers: ",
+                Style::default().add_modifier(Modifier::BOLD),
+            ),
+            Span::raw("Define once, sync to all agents (Claude, Gemini, OpenCode, Cursor)"),
+        ])
+    };
+    let header = Paragraph::new(vec![header_line]).block(
         Block::default()
             .borders(Borders::ALL)
             .title("MooAgent MCP Config"),
@@ -1279,9 +1974,15 @@ fn render_mcp_servers(f: &mut Frame, app: &App) {
         .constraints([Constraint::Percentage(35), Constraint::Percentage(65)])
         .split(chunks[2]);
 
+    let filter = app.mcp_editor_state.filter.trim();
     let servers: Vec<Line> = if app.mcp_editor_state.server_list.is_empty() {
+        let message = if filter.is_empty() {
+            "  No MCP servers configured"
+        } else {
+            "  No MCP servers match the filter"
+        };
         vec![Line::from(Span::styled(
-            "  No MCP servers configured",
+            message,
             Style::default().fg(Color::DarkGray),
         ))]
     } else {
@@ -1298,6 +1999,23 @@ fn render_mcp_servers(f: &mut Frame, app: &App) {
                     .map(|p| p.disabled_mcp_servers.contains(server))
                     .unwrap_or(false);
 
+                let is_unknown = app
+                    .paths
+                    .preferences
+                    .global_prefs
+                    .mcp_servers
+                    .get(server)
+                    .is_some_and(|c| c.is_unknown());
+                let label = if is_unknown {
+                    format!("{} (Unrecognized config)", server)
+                } else {
+                    server.clone()
+                };
+                let config = app.paths.preferences.global_prefs.mcp_servers.get(server);
+                let description = config.and_then(|c| c.description());
+                let tags = config.map(|c| c.tags()).unwrap_or_default();
+                let server = &label;
+
                 let (style, text) = if idx == app.mcp_editor_state.selected_server_idx {
                     let s = Style::default().fg(Color::Black).bg(Color::Cyan);
                     if is_disabled {
@@ -1310,18 +2028,38 @@ fn render_mcp_servers(f: &mut Frame, app: &App) {
                         Style::default().fg(Color::Red),
                         format!("  {} (Disabled)", server),
                     )
+                } else if is_unknown {
+                    (Style::default().fg(Color::Red), format!("  {}", server))
                 } else {
                     (Style::default().fg(Color::White), format!("  {}", server))
                 };
-                Line::from(vec![Span::styled(text, style)])
+                let mut spans = vec![Span::styled(text, style)];
+                if !tags.is_empty() {
+                    spans.push(Span::styled(
+                        format!(" [{}]", tags.join(", ")),
+                        Style::default().fg(Color::Magenta),
+                    ));
+                }
+                if let Some(description) = description {
+                    spans.push(Span::styled(
+                        format!(" - {}", description),
+                        Style::default().fg(Color::DarkGray),
+                    ));
+                }
+                Line::from(spans)
             })
             .collect()
     };
 
+    let servers_title = if filter.is_empty() {
+        "MCP Servers [j/k]".to_string()
+    } else {
+        format!("MCP Servers [j/k] (filter: {})", filter)
+    };
     let servers_block = Block::default()
         .borders(Borders::ALL)
         .border_style(Style::default().fg(Color::Yellow))
-        .title("MCP Servers [j/k]");
+        .title(servers_title);
     f.render_widget(Paragraph::new(servers).block(servers_block), main_chunks[0]);
 
     let mut details = Vec::new();
@@ -1348,6 +2086,24 @@ fn render_mcp_servers(f: &mut Frame, app: &App) {
                 Span::styled("Name: ", Style::default().add_modifier(Modifier::BOLD)),
                 Span::raw(server_name),
             ]));
+            if let Some(description) = config.description() {
+                details.push(Line::from(vec![
+                    Span::styled(
+                        "Description: ",
+                        Style::default().add_modifier(Modifier::BOLD),
+                    ),
+                    Span::raw(description),
+                ]));
+            }
+            if !config.tags().is_empty() {
+                details.push(Line::from(vec![
+                    Span::styled("Tags: ", Style::default().add_modifier(Modifier::BOLD)),
+                    Span::styled(
+                        config.tags().join(", "),
+                        Style::default().fg(Color::Magenta),
+                    ),
+                ]));
+            }
 
             if is_disabled {
                 details.push(Line::from(""));
@@ -1366,6 +2122,7 @@ fn render_mcp_servers(f: &mut Frame, app: &App) {
                     env,
                     disabled_tools,
                     auto_allow,
+                    ..
                 } => {
                     details.push(Line::from(vec![
                         Span::styled("Type: ", Style::default().add_modifier(Modifier::BOLD)),
@@ -1392,7 +2149,14 @@ fn render_mcp_servers(f: &mut Frame, app: &App) {
                             "Environment:",
                             Style::default().add_modifier(Modifier::BOLD),
                         )]));
-                        for (k, v) in env {
+                        let mut vars: Vec<_> = env.iter().collect();
+                        vars.sort();
+                        for (k, v) in vars {
+                            let v = if crate::preferences::is_secret_key(k) {
+                                "***"
+                            } else {
+                                v
+                            };
                             details.push(Line::from(format!("  {}={}", k, v)));
                         }
                         details.push(Line::from(""));
@@ -1420,8 +2184,11 @@ fn render_mcp_servers(f: &mut Frame, app: &App) {
                 crate::preferences::McpServerConfig::Sse {
                     url,
                     auth,
+                    headers,
+                    headers_override_auth,
                     disabled_tools,
                     auto_allow,
+                    ..
                 } => {
                     details.push(Line::from(vec![
                         Span::styled("Type: ", Style::default().add_modifier(Modifier::BOLD)),
@@ -1436,6 +2203,7 @@ fn render_mcp_servers(f: &mut Frame, app: &App) {
 
                     format_auth_details(&mut details, auth);
                     format_oauth_status(&mut details, app, url, auth);
+                    format_header_details(&mut details, headers, *headers_override_auth);
 
                     if *auto_allow {
                         details.push(Line::from(vec![Span::styled(
@@ -1459,8 +2227,11 @@ fn render_mcp_servers(f: &mut Frame, app: &App) {
                 crate::preferences::McpServerConfig::Http {
                     http_url,
                     auth,
+                    headers,
+                    headers_override_auth,
                     disabled_tools,
                     auto_allow,
+                    ..
                 } => {
                     details.push(Line::from(vec![
                         Span::styled("Type: ", Style::default().add_modifier(Modifier::BOLD)),
@@ -1475,6 +2246,7 @@ fn render_mcp_servers(f: &mut Frame, app: &App) {
 
                     format_auth_details(&mut details, auth);
                     format_oauth_status(&mut details, app, http_url, auth);
+                    format_header_details(&mut details, headers, *headers_override_auth);
 
                     if *auto_allow {
                         details.push(Line::from(vec![Span::styled(
@@ -1495,11 +2267,33 @@ fn render_mcp_servers(f: &mut Frame, app: &App) {
                         details.push(Line::from(""));
                     }
                 }
+                crate::preferences::McpServerConfig::Unknown(raw) => {
+                    details.push(Line::from(vec![
+                        Span::styled("Type: ", Style::default().add_modifier(Modifier::BOLD)),
+                        Span::styled(
+                            "unrecognized config",
+                            Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+                        ),
+                    ]));
+                    details.push(Line::from(""));
+                    if let Some(fields) = raw.as_object() {
+                        let keys: Vec<&str> = fields.keys().map(|k| k.as_str()).collect();
+                        details.push(Line::from(vec![
+                            Span::styled("Fields: ", Style::default().add_modifier(Modifier::BOLD)),
+                            Span::raw(keys.join(", ")),
+                        ]));
+                        details.push(Line::from(""));
+                    }
+                    details.push(Line::from(Span::styled(
+                        "Kept as-is but not synced. Fix the entry in preferences.toml.",
+                        Style::default().fg(Color::Yellow),
+                    )));
+                }
             }
 
             details.push(Line::from(""));
             details.push(Line::from(vec![Span::styled(
-                "Syncs to: Claude, Gemini, OpenCode",
+                "Syncs to: Claude, Gemini, OpenCode, Cursor",
                 Style::default().fg(Color::DarkGray),
             )]));
         }
@@ -1513,7 +2307,15 @@ fn render_mcp_servers(f: &mut Frame, app: &App) {
         )]));
     }
 
-    let details_block = Block::default().borders(Borders::ALL).title("Details");
+    let (details, details_title) = if app.mcp_editor_state.show_preview {
+        (
+            generated_config_preview(app),
+            "Generated Config (read-only) [p: details]",
+        )
+    } else {
+        (details, "Details [p: generated config]")
+    };
+    let details_block = Block::default().borders(Borders::ALL).title(details_title);
     f.render_widget(Paragraph::new(details).block(details_block), main_chunks[1]);
 
     if let Some((msg, _)) = &app.status_message {
@@ -1549,6 +2351,10 @@ fn render_mcp_servers(f: &mut Frame, app: &App) {
     hint_spans.extend(vec![
         Span::styled("[m]", Style::default().fg(Color::Cyan)),
         Span::raw(" Magic | "),
+        Span::styled("[p]", Style::default().fg(Color::Cyan)),
+        Span::raw(" Preview | "),
+        Span::styled("[/]", Style::default().fg(Color::Cyan)),
+        Span::raw(" Filter | "),
         Span::styled("[s]", Style::default().fg(Color::Cyan)),
         Span::raw(" Sync | "),
         Span::styled("[q]", Style::default().fg(Color::Cyan)),
@@ -1559,6 +2365,47 @@ fn render_mcp_servers(f: &mut Frame, app: &App) {
     f.render_widget(Paragraph::new(hints), chunks[4]);
 }
 
+/// The JSON each agent would receive for the selected server, generated in memory.
+fn generated_config_preview(app: &App) -> Vec<Line<'static>> {
+    let Some(server_name) = app
+        .mcp_editor_state
+        .server_list
+        .get(app.mcp_editor_state.selected_server_idx)
+    else {
+        return vec![Line::from("No MCP servers configured.")];
+    };
+
+    let mut lines = Vec::new();
+    for (agent, preview) in app.paths.preview_mcp_server(server_name) {
+        lines.push(Line::from(Span::styled(
+            agent,
+            Style::default()
+                .fg(Color::Cyan)
+                .add_modifier(Modifier::BOLD),
+        )));
+        match preview {
+            Ok(Some(entry)) => {
+                let json = serde_json::to_string_pretty(&entry).unwrap_or_default();
+                lines.extend(highlight(&json, "json"));
+            }
+            Ok(None) => lines.push(Line::from(Span::styled(
+                "(not written for this agent)",
+                Style::default().fg(Color::DarkGray),
+            ))),
+            Err(e) => lines.push(Line::from(Span::styled(
+                format!("Could not generate: {}", e),
+                Style::default().fg(Color::Red),
+            ))),
+        }
+        lines.push(Line::from(""));
+    }
+    lines.push(Line::from(Span::styled(
+        "Secrets are redacted. Press s to sync.",
+        Style::default().fg(Color::DarkGray),
+    )));
+    lines
+}
+
 fn render_mcp_edit_dialog(f: &mut Frame, app: &App) {
     let area = f.area();
     let is_remote = app.mcp_editor_state.is_remote_server();
@@ -1566,12 +2413,12 @@ fn render_mcp_edit_dialog(f: &mut Frame, app: &App) {
 
     let height = if is_remote {
         match auth_type {
-            McpAuthType::None => 18,
-            McpAuthType::Bearer => 21,
-            McpAuthType::OAuth => 30,
+            McpAuthType::None => 27,
+            McpAuthType::Bearer => 30,
+            McpAuthType::OAuth => 39,
         }
     } else {
-        18
+        24
     };
 
     let width = 80;
@@ -1618,9 +2465,14 @@ fn render_mcp_edit_dialog(f: &mut Frame, app: &App) {
             .border_style(style);
 
         let text = if focus == target {
-            format!("{}_", content)
+            Line::from(input_spans(
+                content,
+                app.mcp_editor_state.cursor,
+                area.width.saturating_sub(2) as usize,
+                Style::default(),
+            ))
         } else {
-            content.to_string()
+            Line::from(content.to_string())
         };
 
         f.render_widget(Paragraph::new(text).block(block), area);
@@ -1669,6 +2521,9 @@ fn render_mcp_edit_dialog(f: &mut Frame, app: &App) {
             Constraint::Length(3),
             Constraint::Length(3),
             Constraint::Length(3),
+            Constraint::Length(3),
+            Constraint::Length(3),
+            Constraint::Length(3),
         ];
 
         match auth_type {
@@ -1702,13 +2557,38 @@ fn render_mcp_edit_dialog(f: &mut Frame, app: &App) {
             McpFieldFocus::Name,
             chunks[0],
         );
+        draw_input(
+            f,
+            "Description (optional, not synced)",
+            &app.mcp_editor_state.editing_description,
+            app.mcp_editor_state.focus,
+            McpFieldFocus::Description,
+            chunks[1],
+        );
+        draw_input(
+            f,
+            "Tags (comma separated, optional, not synced)",
+            &app.mcp_editor_state.editing_tags,
+            app.mcp_editor_state.focus,
+            McpFieldFocus::Tags,
+            chunks[2],
+        );
         draw_input(
             f,
             "URL (SSE/HTTP endpoint)",
             &app.mcp_editor_state.editing_command,
             app.mcp_editor_state.focus,
             McpFieldFocus::Command,
-            chunks[1],
+            chunks[3],
+        );
+
+        draw_input(
+            f,
+            "Headers (KEY=VAL,KEY=\"a,b\")",
+            &app.mcp_editor_state.editing_headers,
+            app.mcp_editor_state.focus,
+            McpFieldFocus::Headers,
+            chunks[4],
         );
 
         let auth_idx = match auth_type {
@@ -1723,11 +2603,11 @@ fn render_mcp_edit_dialog(f: &mut Frame, app: &App) {
             auth_idx,
             app.mcp_editor_state.focus,
             McpFieldFocus::AuthType,
-            chunks[2],
+            chunks[5],
         );
 
         let help_idx = match auth_type {
-            McpAuthType::None => 3,
+            McpAuthType::None => 6,
             McpAuthType::Bearer => {
                 draw_input(
                     f,
@@ -1735,9 +2615,9 @@ fn render_mcp_edit_dialog(f: &mut Frame, app: &App) {
                     &app.mcp_editor_state.editing_bearer_token,
                     app.mcp_editor_state.focus,
                     McpFieldFocus::BearerToken,
-                    chunks[3],
+                    chunks[6],
                 );
-                4
+                7
             }
             McpAuthType::OAuth => {
                 draw_input(
@@ -1746,7 +2626,7 @@ fn render_mcp_edit_dialog(f: &mut Frame, app: &App) {
                     &app.mcp_editor_state.editing_oauth_client_id,
                     app.mcp_editor_state.focus,
                     McpFieldFocus::OAuthClientId,
-                    chunks[3],
+                    chunks[6],
                 );
                 draw_input(
                     f,
@@ -1754,7 +2634,7 @@ fn render_mcp_edit_dialog(f: &mut Frame, app: &App) {
                     &app.mcp_editor_state.editing_oauth_client_secret,
                     app.mcp_editor_state.focus,
                     McpFieldFocus::OAuthClientSecret,
-                    chunks[4],
+                    chunks[7],
                 );
                 draw_input(
                     f,
@@ -1762,7 +2642,7 @@ fn render_mcp_edit_dialog(f: &mut Frame, app: &App) {
                     &app.mcp_editor_state.editing_oauth_scopes,
                     app.mcp_editor_state.focus,
                     McpFieldFocus::OAuthScopes,
-                    chunks[5],
+                    chunks[8],
                 );
                 draw_input(
                     f,
@@ -1770,9 +2650,9 @@ fn render_mcp_edit_dialog(f: &mut Frame, app: &App) {
                     &app.mcp_editor_state.editing_oauth_auth_server_url,
                     app.mcp_editor_state.focus,
                     McpFieldFocus::OAuthAuthServerUrl,
-                    chunks[6],
+                    chunks[9],
                 );
-                7
+                10
             }
         };
 
@@ -1800,6 +2680,8 @@ fn render_mcp_edit_dialog(f: &mut Frame, app: &App) {
                 Constraint::Length(3),
                 Constraint::Length(3),
                 Constraint::Length(3),
+                Constraint::Length(3),
+                Constraint::Length(3),
                 Constraint::Min(3),
             ])
             .split(dialog_area);
@@ -1812,13 +2694,29 @@ fn render_mcp_edit_dialog(f: &mut Frame, app: &App) {
             McpFieldFocus::Name,
             chunks[0],
         );
+        draw_input(
+            f,
+            "Description (optional, not synced)",
+            &app.mcp_editor_state.editing_description,
+            app.mcp_editor_state.focus,
+            McpFieldFocus::Description,
+            chunks[1],
+        );
+        draw_input(
+            f,
+            "Tags (comma separated, optional, not synced)",
+            &app.mcp_editor_state.editing_tags,
+            app.mcp_editor_state.focus,
+            McpFieldFocus::Tags,
+            chunks[2],
+        );
         draw_input(
             f,
             "Command",
             &app.mcp_editor_state.editing_command,
             app.mcp_editor_state.focus,
             McpFieldFocus::Command,
-            chunks[1],
+            chunks[3],
         );
         draw_input(
             f,
@@ -1826,15 +2724,15 @@ fn render_mcp_edit_dialog(f: &mut Frame, app: &App) {
             &app.mcp_editor_state.editing_args,
             app.mcp_editor_state.focus,
             McpFieldFocus::Args,
-            chunks[2],
+            chunks[4],
         );
         draw_input(
             f,
-            "Env (KEY=VAL,KEY=VAL)",
+            "Env (KEY=VAL,KEY=\"a,b\")",
             &app.mcp_editor_state.editing_env,
             app.mcp_editor_state.focus,
             McpFieldFocus::Env,
-            chunks[3],
+            chunks[5],
         );
 
         let help = vec![
@@ -1851,6 +2749,6 @@ fn render_mcp_edit_dialog(f: &mut Frame, app: &App) {
                 Span::raw("Use http(s):// URL for remote SSE servers"),
             ]),
         ];
-        f.render_widget(Paragraph::new(help), chunks[4]);
+        f.render_widget(Paragraph::new(help), chunks[6]);
     }
 }